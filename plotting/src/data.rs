@@ -1,7 +1,13 @@
 use std::convert::TryInto;
 use std::{cell::RefCell, collections::HashMap};
 
-use crate::structs::{DataPrec, RangePrec, Segment, TraceData};
+use crate::ingest::{decompress, DedupPolicy, IngestReport, Rows};
+use crate::stats::{
+    self, Anomaly, AnomalyMethod, AnomalyParams, Binning, BucketSize, Correlation, CorrelationMatrix, Histogram,
+    Forecast, GapReport, Period, PeriodMethod, RankStat, ThresholdParams, ThresholdReport, TrendMethod, TrendParams,
+};
+use crate::structs::{DataPrec, Interpolation, RangePrec, Segment, TraceData};
+use crate::utils::to_js;
 use lazy_static::lazy_static;
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -116,6 +122,7 @@ pub fn create_trace(id: &str, x_type: &str) -> DataIdx {
             TraceData {
                 id: String::from(id.clone()),
                 x_type: String::from(x_type.clone()),
+                interpolation: Interpolation::Linear,
 
                 segments: vec![],
             },
@@ -129,6 +136,16 @@ pub fn dispose_trace(handle: DataIdx) {
     DATA.with(|data| data.borrow_mut().remove(&handle));
 }
 
+pub fn find_traces<P: Fn(&TraceData) -> bool>(pred: P) -> Vec<DataIdx> {
+    DATA.with(|data| {
+        data.borrow()
+            .iter()
+            .filter(|(_, t)| pred(t))
+            .map(|(h, _)| *h)
+            .collect()
+    })
+}
+
 pub fn get_trace<T: FnMut(&mut TraceData)>(handle: DataIdx, mut func: T) {
     DATA.with(|data| func(data.borrow_mut().get_mut(&handle).unwrap()));
 }
@@ -150,9 +167,9 @@ pub fn op_traces(output: DataIdx, ptrs: &[DataIdx], op: &str, from: RangePrec, t
     };
 
     let mut data = get_trace_ret(*ptrs.first().unwrap(), |t| {
-        let mut current = Vec::<(i32, f64)>::new();
+        let mut current = Vec::<(RangePrec, RangePrec)>::new();
         for (x, y) in t.get_data_high_prec(from, to) {
-            current.push((x as i32, y * mul));
+            current.push((x, y * mul));
         }
 
         current
@@ -166,15 +183,42 @@ pub fn op_traces(output: DataIdx, ptrs: &[DataIdx], op: &str, from: RangePrec, t
         });
     }
 
-    let data = unsafe { std::mem::transmute(data) };
     get_trace_once(output, move |t| {
-        t.push_segment(create_segment(t.x_type.as_str(), "double", from, to, data));
+        t.push_segment(create_derived_segment(t.x_type.as_str(), "double", from, to, &data));
     });
 }
 
+/// Aggregates a `datetime` trace into calendar buckets of the given time zone, see [`BucketSize::parse`].
+/// The aggregate is one of `avg`, `max`, `sum` or `pN` and the output gets one point per bucket start.
+#[wasm_bindgen]
+pub fn bucket_trace(
+    output: DataIdx,
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    size: &str,
+    tz: &str,
+    aggregate: &str,
+) -> Result<(), JsValue> {
+    let size = BucketSize::parse(size)?;
+    let tz = stats::parse_tz(tz)?;
+    let aggregate = stats::Aggregate::parse(aggregate)?;
+
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(ptr, |t| t.get_data_high_prec(from, to).collect());
+    let buckets = stats::bucketize(&points, size, tz, aggregate);
+
+    get_trace_once(output, move |t| {
+        let seg_from = buckets.first().map_or(from, |b| b.0.min(from));
+        t.push_segment(create_derived_segment(t.x_type.as_str(), "double", seg_from, to, &buckets));
+    });
+
+    Ok(())
+}
+
 #[wasm_bindgen]
 pub fn trace_avgs(ptrs: &[DataIdx], from: RangePrec, to: RangePrec) -> JsValue {
-    JsValue::from_serde(
+    to_js(
         &ptrs
             .iter()
             .map(|t| {
@@ -195,6 +239,38 @@ pub fn trace_avgs(ptrs: &[DataIdx], from: RangePrec, to: RangePrec) -> JsValue {
     .unwrap()
 }
 
+pub fn calc_ranking(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    stat: &RankStat,
+    n: usize,
+    top: bool,
+) -> Vec<(DataIdx, RangePrec)> {
+    stats::select_top(
+        ptrs.iter().filter_map(|&p| {
+            get_trace_ret(p, |trace| stat.evaluate(trace.get_data_high_prec(from, to))).map(|v| (p, v))
+        }),
+        n,
+        top,
+    )
+}
+
+#[wasm_bindgen]
+pub fn rank_traces(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    stat: &str,
+    threshold: RangePrec,
+    n: usize,
+    top: bool,
+) -> Result<JsValue, JsValue> {
+    let stat = RankStat::parse(stat, threshold)?;
+
+    to_js(&calc_ranking(ptrs, from, to, &stat, n, top))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct TraceMetas {
     pub avg: RangePrec,
@@ -202,8 +278,7 @@ pub struct TraceMetas {
     pub max: RangePrec,
 }
 
-#[wasm_bindgen]
-pub fn get_trace_metas(ptr: DataIdx, from: RangePrec, to: RangePrec) -> JsValue {
+pub fn calc_trace_metas(ptr: DataIdx, from: RangePrec, to: RangePrec) -> TraceMetas {
     let mut metas = TraceMetas { avg: 0.0, min: RangePrec::MAX, max: RangePrec::MIN };
     let mut pts = 0;
 
@@ -217,127 +292,407 @@ pub fn get_trace_metas(ptr: DataIdx, from: RangePrec, to: RangePrec) -> JsValue
         }
     });
 
-    metas.avg = metas.avg / pts as RangePrec;
+    metas.avg /= pts as RangePrec;
+
+    metas
+}
+
+#[wasm_bindgen]
+pub fn get_trace_metas(ptr: DataIdx, from: RangePrec, to: RangePrec) -> JsValue {
+    to_js(&calc_trace_metas(ptr, from, to)).unwrap()
+}
+
+pub fn calc_histograms(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    binning: &Binning,
+) -> Vec<(DataIdx, Histogram)> {
+    ptrs.iter()
+        .map(|&p| {
+            let values: Vec<RangePrec> = get_trace_ret(p, |trace| {
+                trace.get_data_high_prec(from, to).map(|(_, y)| y).collect()
+            });
+
+            (p, Histogram::new(&values, binning))
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn histogram(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    binning: &str,
+    bins: usize,
+) -> Result<JsValue, JsValue> {
+    let binning = Binning::parse(binning, bins)?;
+
+    to_js(&calc_histograms(ptrs, from, to, &binning))
+}
+
+pub fn calc_correlation(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    method: &Correlation,
+    max_lag: usize,
+) -> CorrelationMatrix {
+    let series: Vec<Vec<(RangePrec, RangePrec)>> = ptrs
+        .iter()
+        .map(|&p| get_trace_ret(p, |trace| trace.get_data_high_prec(from, to).collect()))
+        .collect();
+
+    let (_, aligned) = stats::align(&series);
+
+    CorrelationMatrix::new(&aligned, method, max_lag)
+}
+
+#[wasm_bindgen]
+pub fn correlate(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    method: &str,
+    max_lag: usize,
+) -> Result<JsValue, JsValue> {
+    let method = Correlation::parse(method)?;
+
+    to_js(&calc_correlation(ptrs, from, to, &method, max_lag))
+}
+
+pub fn calc_anomalies(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &AnomalyMethod,
+    params: &AnomalyParams,
+) -> Vec<Anomaly> {
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(ptr, |trace| trace.get_data_high_prec(from, to).collect());
+
+    stats::detect_anomalies(&points, method, params)
+}
+
+#[wasm_bindgen]
+pub fn detect_anomalies(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &str,
+    params: &AnomalyParams,
+) -> Result<JsValue, JsValue> {
+    let method = AnomalyMethod::parse(method)?;
+
+    to_js(&calc_anomalies(ptr, from, to, &method, params))
+}
+
+pub fn calc_forecast(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &TrendMethod,
+    params: &TrendParams,
+) -> Forecast {
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(ptr, |trace| trace.get_data_high_prec(from, to).collect());
+
+    stats::forecast(&points, method, params)
+}
+
+/// Fits a trend and writes the forecast, lower and upper band into the `outputs` traces (in that order,
+/// any of them may be omitted). Returns the slope, residual deviation and the threshold crossing.
+#[wasm_bindgen]
+pub fn fit_trend(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &str,
+    params: &TrendParams,
+    outputs: &[DataIdx],
+) -> Result<JsValue, JsValue> {
+    let method = TrendMethod::parse(method)?;
+    let mut forecast = calc_forecast(ptr, from, to, &method, params);
+    let x_type = get_trace_ret(ptr, |t| t.x_type.clone());
+
+    if let (Some(first), Some(last)) = (forecast.points.first(), forecast.points.last()) {
+        let (seg_from, seg_to) = (first.0, last.0 + 1.0);
+
+        for (i, &output) in outputs.iter().enumerate().take(3) {
+            let points: Vec<(RangePrec, RangePrec)> = forecast
+                .points
+                .iter()
+                .map(|p| (p.0, [p.1, p.2, p.3][i]))
+                .collect();
+
+            get_trace_once(output, |t| {
+                t.push_segment(create_derived_segment(&x_type, "double", seg_from, seg_to, &points))
+            });
+        }
+    }
+
+    // The points have been handed over as traces
+    forecast.points.clear();
+
+    to_js(&forecast)
+}
+
+pub fn calc_periods(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &PeriodMethod,
+    count: usize,
+) -> Vec<Period> {
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(ptr, |trace| trace.get_data_high_prec(from, to).collect());
+
+    stats::find_periods(&points, method, count)
+}
+
+#[wasm_bindgen]
+pub fn find_periods(
+    ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    method: &str,
+    count: usize,
+) -> Result<JsValue, JsValue> {
+    let method = PeriodMethod::parse(method)?;
+
+    to_js(&calc_periods(ptr, from, to, &method, count))
+}
+
+/// Writes the trace without its seasonal component into `output`. When `period` is not positive,
+/// the dominant period is detected by autocorrelation. Returns the period used, `NaN` if there was none.
+#[wasm_bindgen]
+pub fn deseasonalize(output: DataIdx, ptr: DataIdx, from: RangePrec, to: RangePrec, period: RangePrec) -> RangePrec {
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(ptr, |trace| trace.get_data_high_prec(from, to).collect());
+
+    let period = if period > 0.0 {
+        period
+    } else {
+        match stats::find_periods(&points, &PeriodMethod::Autocorrelation, 1).first() {
+            Some(p) => p.period,
+            None => return RangePrec::NAN,
+        }
+    };
+
+    let result = stats::deseasonalize(&points, period);
+
+    get_trace_once(output, |t| {
+        t.push_segment(create_derived_segment(t.x_type.as_str(), "double", from, to, &result))
+    });
+
+    period
+}
+
+pub fn calc_gap_reports(
+    ptrs: &[DataIdx],
+    from: RangePrec,
+    to: RangePrec,
+    k: RangePrec,
+) -> Vec<(DataIdx, GapReport)> {
+    ptrs.iter()
+        .map(|&p| {
+            let report = get_trace_ret(p, |trace| {
+                let points: Vec<(RangePrec, RangePrec)> = trace.get_data_high_prec(from, to).collect();
+                stats::gap_report(&trace.get_loaded_ranges(), &points, from, to, k)
+            });
+
+            (p, report)
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn gap_report(ptrs: &[DataIdx], from: RangePrec, to: RangePrec, k: RangePrec) -> JsValue {
+    to_js(&calc_gap_reports(ptrs, from, to, k)).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn loaded_ranges(handle: DataIdx) -> JsValue {
+    to_js(&get_trace_ret(handle, |trace| trace.get_loaded_ranges())).unwrap()
+}
+
+/// Merged parts of `from..to` that are not loaded in at least one of the traces.
+pub fn calc_missing_ranges(ptrs: &[DataIdx], from: RangePrec, to: RangePrec) -> Vec<(RangePrec, RangePrec)> {
+    let mut missing: Vec<(RangePrec, RangePrec)> = ptrs
+        .iter()
+        .flat_map(|&p| get_trace_ret(p, |trace| trace.get_missing_ranges(from, to)))
+        .collect();
+    missing.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut ranges: Vec<(RangePrec, RangePrec)> = vec![];
+
+    for (range_from, range_to) in missing {
+        match ranges.last_mut() {
+            Some(last) if range_from <= last.1 => last.1 = last.1.max(range_to),
+            _ => ranges.push((range_from, range_to)),
+        }
+    }
+
+    ranges
+}
+
+#[wasm_bindgen]
+pub fn missing_ranges(ptrs: &[DataIdx], from: RangePrec, to: RangePrec) -> JsValue {
+    to_js(&calc_missing_ranges(ptrs, from, to)).unwrap()
+}
+
+/// Sets the default interpolation of the trace, one of `linear`, `step-before`, `step-after` or `nearest`.
+#[wasm_bindgen]
+pub fn set_interpolation(ptr: DataIdx, mode: &str) -> Result<(), JsValue> {
+    let mode = Interpolation::parse(mode)?;
+    get_trace_once(ptr, |trace| trace.interpolation = mode);
 
-    JsValue::from_serde(&metas).unwrap()
+    Ok(())
 }
 
+/// Values of the traces at `x`, using `mode` or each trace's own interpolation if `None`.
 pub fn get_data_at_iter<'a>(
     ptrs: &'a [DataIdx],
     x: RangePrec,
+    mode: Option<Interpolation>,
 ) -> impl Iterator<Item = (DataIdx, RangePrec)> + 'a {
     ptrs.iter()
-        .map(move |&p| (p, get_trace_ret(p, |trace| trace.get_data_at(x))))
+        .map(move |&p| {
+            (
+                p,
+                get_trace_ret(p, |trace| {
+                    trace.get_data_at_with(x, mode.unwrap_or(trace.interpolation))
+                }),
+            )
+        })
         .filter(|a| a.1.is_some())
         .map(|(a, b)| (a, b.unwrap()))
 }
 
 #[wasm_bindgen]
-pub fn get_data_at(ptrs: &[DataIdx], x: RangePrec) -> JsValue {
-    JsValue::from_serde(
-        &ptrs
-            .iter()
-            .map(move |&p| (p, get_trace_ret(p, |trace| trace.get_data_at(x))))
-            .filter(|a| a.1.is_some())
-            .map(|(a, b)| (a, b.unwrap()))
-            .collect::<Vec<(DataIdx, RangePrec)>>(),
-    )
-    .unwrap()
+pub fn get_data_at(ptrs: &[DataIdx], x: RangePrec, mode: Option<String>) -> Result<JsValue, JsValue> {
+    let mode = mode.as_deref().map(Interpolation::parse).transpose()?;
+
+    to_js(&get_data_at_iter(ptrs, x, mode).collect::<Vec<(DataIdx, RangePrec)>>())
 }
 
 #[wasm_bindgen]
-pub fn find_closest(ptrs: &[DataIdx], x: RangePrec, y: RangePrec, max_dy: RangePrec) -> Option<DataIdx> {
-    let mut dists: Vec<(DataIdx, RangePrec)> = get_data_at_iter(ptrs, x)
+pub fn find_closest(
+    ptrs: &[DataIdx],
+    x: RangePrec,
+    y: RangePrec,
+    max_dy: RangePrec,
+    mode: Option<String>,
+) -> Result<Option<DataIdx>, JsValue> {
+    let mode = mode.as_deref().map(Interpolation::parse).transpose()?;
+    let mut dists: Vec<(DataIdx, RangePrec)> = get_data_at_iter(ptrs, x, mode)
         .map(|d| (d.0, (d.1 - y).abs()))
         .filter(|(_, dy)| *dy < max_dy)
         .collect();
 
     dists.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
 
-    dists.first().map(|f| f.0)
+    Ok(dists.first().map(|f| f.0))
 }
 
-#[wasm_bindgen]
-pub fn bulkload_segments(ptrs: &[DataIdx], x_type: &str, y_type: &str, data: Box<[u8]>) {
+/// Decodes a bulkload payload of rows with a timestamp and one value per trace, sorts and dedupes
+/// the rows and pushes a segment to each of the traces. The payload may be compressed, see [`decompress`].
+/// The segments span the requested `range` if given, also when it has no rows, otherwise just the rows.
+pub fn bulkload(
+    ptrs: &[DataIdx],
+    x_type: &str,
+    y_type: &str,
+    data: &[u8],
+    dedup: DedupPolicy,
+    range: Option<(RangePrec, RangePrec)>,
+) -> Result<IngestReport, String> {
+    let data = decompress(data)?;
     let x_desc = TYPE_SIZES.get(x_type).unwrap();
     let y_desc = TYPE_SIZES.get(y_type).unwrap();
-    let row_len = x_desc.size + y_desc.size;
-
-    let wins = data.chunks_exact(x_desc.size + y_desc.size * ptrs.len());
-    let points = wins.clone().len();
-    let mut out = vec![vec![0u8; points * row_len]; ptrs.len()];
 
-    let mut row_idx = 0;
-
-    let start = (x_desc.parser)(&data[0..x_desc.size]);
-    let mut cur = start;
-
-    for row in wins {
-        cur = (x_desc.parser)(&row[0..x_desc.size]);
-
-        for i in 0..ptrs.len() {
-            let data_pos = x_desc.size + i * y_desc.size;
-            let dest_x = row_idx * row_len;
-            let dest_y = dest_x + x_desc.size;
+    let mut report = IngestReport::default();
+    let (mut rows, trailing_bytes) = Rows::decode(&data, x_desc, y_desc, ptrs.len(), &mut report);
+    report.trailing_bytes = trailing_bytes;
+
+    rows.normalize(dedup, &mut report);
+
+    let first = rows.xs.first().copied();
+    let last = rows.xs.last().copied();
+
+    match (range, first, last) {
+        (Some((from, to)), _, _) => push_rows(
+            ptrs,
+            x_type,
+            y_type,
+            first.map_or(from, |x| x.min(from)),
+            last.map_or(to, |x| x.max(to)),
+            &rows,
+        ),
+        (None, Some(from), Some(to)) => push_rows(ptrs, x_type, y_type, from, to, &rows),
+        _ => {}
+    }
 
-            out[i][dest_x..(dest_x + x_desc.size)].copy_from_slice(&row[0..x_desc.size]);
-            out[i][dest_y..(dest_y + y_desc.size)]
-                .copy_from_slice(&row[data_pos..(data_pos + y_desc.size)]);
-        }
+    Ok(report)
+}
 
-        row_idx += 1;
-    }
+/// Pushes a segment with a column of `rows` to each of the traces.
+pub fn push_rows(ptrs: &[DataIdx], x_type: &str, y_type: &str, from: RangePrec, to: RangePrec, rows: &Rows) {
+    for (i, &handle) in ptrs.iter().enumerate() {
+        let points = rows.column(i);
 
-    for (d, handle) in out.drain(0..).zip(ptrs.iter()) {
-        get_trace_once(*handle, move |trace| {
-            trace.push_segment(create_segment(x_type, y_type, start, cur, d))
+        get_trace_once(handle, |trace| {
+            trace.push_segment(create_derived_segment(x_type, y_type, from, to, &points))
         });
     }
 }
 
-pub fn create_segment(
+/// See [`bulkload`], `dedup` is one of `first`, `last` (default), `mean`, `min` or `max`. `from` and
+/// `to` are the requested range.
+#[wasm_bindgen]
+pub fn bulkload_segments(
+    ptrs: &[DataIdx],
+    x_type: &str,
+    y_type: &str,
+    data: Box<[u8]>,
+    dedup: Option<String>,
+    from: Option<RangePrec>,
+    to: Option<RangePrec>,
+) -> Result<JsValue, JsValue> {
+    let dedup = dedup.as_deref().map_or(Ok(DedupPolicy::Last), DedupPolicy::parse)?;
+    let report = bulkload(ptrs, x_type, y_type, &data, dedup, from.zip(to)).map_err(|e| JsValue::from_str(&e))?;
+
+    to_js(&report)
+}
+
+/// Creates a segment from already computed points, converting them to the given types.
+pub fn create_derived_segment(
     x_type: &str,
     y_type: &str,
     from: RangePrec,
     to: RangePrec,
-    d: Vec<u8>,
+    points: &[(RangePrec, RangePrec)],
 ) -> Box<dyn Segment> {
     macro_rules! create_segment {
-        ( $xt:ty, $yt:ty, $d:expr ) => {
+        ( $xt:ty, $yt:ty ) => {
             Box::new(crate::structs::DataSegment::<$xt, $yt> {
                 from,
                 to,
-                data: unsafe { std::mem::transmute($d) },
+                data: points.iter().map(|&(x, y)| (x as $xt, y as $yt)).collect(),
             })
         };
     }
 
     match (x_type, y_type) {
-        ("datetime", "short") => {
-            create_segment!(type_map!("datetime"), type_map!("short"), d)
-        }
-        ("datetime", "int") => create_segment!(type_map!("datetime"), type_map!("int"), d),
-        ("datetime", "long") => {
-            create_segment!(type_map!("datetime"), type_map!("long"), d)
-        }
-        ("datetime", "byte") => {
-            create_segment!(type_map!("datetime"), type_map!("byte"), d)
-        }
-        ("datetime", "ushort") => {
-            create_segment!(type_map!("datetime"), type_map!("ushort"), d)
-        }
-        ("datetime", "uint") => {
-            create_segment!(type_map!("datetime"), type_map!("uint"), d)
-        }
-        ("datetime", "ulong") => {
-            create_segment!(type_map!("datetime"), type_map!("ulong"), d)
-        }
-        ("datetime", "float") => {
-            create_segment!(type_map!("datetime"), type_map!("float"), d)
-        }
-        ("datetime", "double") => {
-            create_segment!(type_map!("datetime"), type_map!("double"), d)
-        }
+        ("datetime", "short") => create_segment!(type_map!("datetime"), type_map!("short")),
+        ("datetime", "int") => create_segment!(type_map!("datetime"), type_map!("int")),
+        ("datetime", "long") => create_segment!(type_map!("datetime"), type_map!("long")),
+        ("datetime", "byte") => create_segment!(type_map!("datetime"), type_map!("byte")),
+        ("datetime", "ushort") => create_segment!(type_map!("datetime"), type_map!("ushort")),
+        ("datetime", "uint") => create_segment!(type_map!("datetime"), type_map!("uint")),
+        ("datetime", "ulong") => create_segment!(type_map!("datetime"), type_map!("ulong")),
+        ("datetime", "float") => create_segment!(type_map!("datetime"), type_map!("float")),
+        ("datetime", "double") => create_segment!(type_map!("datetime"), type_map!("double")),
         _ => panic!("Unknown XY pair"),
     }
 }
@@ -362,6 +717,30 @@ pub fn treshold(data_ptr: DataIdx, from: RangePrec, to: RangePrec, tres: DataPre
     result
 }
 
+pub fn calc_threshold_intervals(
+    data_ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    tres: RangePrec,
+    params: &ThresholdParams,
+) -> ThresholdReport {
+    let points: Vec<(RangePrec, RangePrec)> =
+        get_trace_ret(data_ptr, |trace| trace.get_data_high_prec(from, to).collect());
+
+    stats::threshold_intervals(&points, tres, params)
+}
+
+#[wasm_bindgen]
+pub fn threshold_intervals(
+    data_ptr: DataIdx,
+    from: RangePrec,
+    to: RangePrec,
+    tres: RangePrec,
+    params: &ThresholdParams,
+) -> JsValue {
+    to_js(&calc_threshold_intervals(data_ptr, from, to, tres, params)).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn get_extents(data_ptr: DataIdx, from: RangePrec, to: RangePrec) -> Box<[RangePrec]> {
     let mut result = (0.0, 1.0);
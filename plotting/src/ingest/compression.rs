@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read, Write};
+
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use flate2::write;
+use ruzstd::decoding::StreamingDecoder;
+
+/// ID bytes followed by the deflate method, which is the only one defined.
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Bytes needed to tell the compression of a payload.
+const MAGIC_LEN: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compression {
+    None,
+    Gzip,
+    /// Zlib wrapped deflate, which is what HTTP calls `deflate`.
+    Zlib,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression from the magic bytes at the start of a payload.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if data.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if is_zlib_header(data) {
+            Compression::Zlib
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Deflate with the default 32K window, no preset dictionary and a valid header checksum.
+fn is_zlib_header(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => {
+            *cmf == 0x78 && flg & 0x20 == 0 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+/// Decompresses a whole payload, payloads without a known magic are returned as they are.
+pub fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    let mut out = vec![];
+
+    match Compression::detect(data) {
+        Compression::None => return Ok(Cow::Borrowed(data)),
+        Compression::Gzip => {
+            let mut decoder = MultiGzDecoder::new(data);
+
+            if let Err(e) = decoder.read_to_end(&mut out) {
+                // Raw rows can start with the magic too, but not with a whole gzip header
+                return match decoder.header() {
+                    None => Ok(Cow::Borrowed(data)),
+                    Some(_) => Err(e.to_string()),
+                };
+            }
+        }
+        Compression::Zlib => {
+            // The zlib header is short enough to occur in uncompressed rows, those fail to inflate
+            // instead of ending early like a stream cut short
+            if let Err(e) = ZlibDecoder::new(data).read_to_end(&mut out) {
+                return match e.kind() {
+                    ErrorKind::UnexpectedEof => Err(e.to_string()),
+                    _ => Ok(Cow::Borrowed(data)),
+                };
+            }
+        }
+        Compression::Zstd => {
+            StreamingDecoder::new(data)
+                .map_err(|e| e.to_string())?
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+        }
+    };
+
+    Ok(Cow::Owned(out))
+}
+
+/// Incremental [`decompress`]. Gzip is inflated as the chunks arrive, zlib and zstd payloads
+/// are collected and decompressed once complete.
+pub enum Decompressor {
+    Detecting(Vec<u8>),
+    None,
+    Gzip {
+        decoder: Box<write::MultiGzDecoder<Vec<u8>>>,
+        /// Bytes written until the header parses, passed on as they are if it does not.
+        head: Vec<u8>,
+    },
+    Buffered(Vec<u8>),
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Decompressor::Detecting(vec![])
+    }
+}
+
+impl Decompressor {
+    /// Appends the decompressed bytes available after `chunk` to `out`.
+    pub fn write(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            Decompressor::Detecting(head) => {
+                head.extend_from_slice(chunk);
+
+                if head.len() < MAGIC_LEN {
+                    return Ok(());
+                }
+
+                let head = std::mem::take(head);
+
+                *self = match Compression::detect(&head) {
+                    Compression::None => Decompressor::None,
+                    Compression::Gzip => Decompressor::Gzip {
+                        decoder: Box::new(write::MultiGzDecoder::new(vec![])),
+                        head: vec![],
+                    },
+                    Compression::Zlib | Compression::Zstd => Decompressor::Buffered(vec![]),
+                };
+
+                self.write(&head, out)
+            }
+            Decompressor::None => {
+                out.extend_from_slice(chunk);
+                Ok(())
+            }
+            Decompressor::Gzip { decoder, head } => {
+                let parsed = decoder.header().is_some();
+
+                if !parsed {
+                    head.extend_from_slice(chunk);
+                }
+
+                match decoder.write_all(chunk).and_then(|_| decoder.flush()) {
+                    Ok(()) => {
+                        if decoder.header().is_some() {
+                            *head = vec![];
+                        }
+
+                        out.append(decoder.get_mut());
+                        Ok(())
+                    }
+                    Err(_) if decoder.header().is_none() => {
+                        out.append(head);
+                        *self = Decompressor::None;
+                        Ok(())
+                    }
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Decompressor::Buffered(data) => {
+                data.extend_from_slice(chunk);
+                Ok(())
+            }
+        }
+    }
+
+    /// Appends the rest of the decompressed payload to `out`.
+    pub fn finish(self, out: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            Decompressor::Detecting(data) | Decompressor::Buffered(data) => {
+                out.extend_from_slice(&decompress(&data)?);
+            }
+            Decompressor::None => {}
+            Decompressor::Gzip { decoder, mut head } => {
+                if decoder.header().is_none() {
+                    out.append(&mut head);
+                } else {
+                    out.append(&mut decoder.finish().map_err(|e| e.to_string())?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+mod compression;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::data::{self, DataIdx, TypeDescriptor, TYPE_SIZES};
+use crate::structs::RangePrec;
+use crate::utils::to_js;
+
+pub use compression::{decompress, Compression, Decompressor};
+
+/// Decoded rows a [`BulkLoader`] collects before pushing them to the traces.
+const FLUSH_ROWS: usize = 16384;
+
+/// Which value is kept when several rows share a timestamp. Only finite values are considered,
+/// the first value is kept if there are none.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DedupPolicy {
+    First,
+    Last,
+    Mean,
+    Min,
+    Max,
+}
+
+impl DedupPolicy {
+    /// Parses `first`, `last`, `mean`, `min` or `max`.
+    pub fn parse(policy: &str) -> Result<Self, String> {
+        match policy {
+            "first" => Ok(DedupPolicy::First),
+            "last" => Ok(DedupPolicy::Last),
+            "mean" => Ok(DedupPolicy::Mean),
+            "min" => Ok(DedupPolicy::Min),
+            "max" => Ok(DedupPolicy::Max),
+            _ => Err(format!("Unknown dedup policy '{}'", policy)),
+        }
+    }
+
+    fn merge(self, mut values: impl Iterator<Item = RangePrec> + Clone) -> RangePrec {
+        let mut finite = values.clone().filter(|v| v.is_finite()).peekable();
+
+        if finite.peek().is_none() {
+            return values.next().unwrap_or(RangePrec::NAN);
+        }
+
+        match self {
+            DedupPolicy::First => finite.next().unwrap(),
+            DedupPolicy::Last => finite.last().unwrap(),
+            DedupPolicy::Mean => {
+                let (sum, count) = finite.fold((0.0, 0), |acc, v| (acc.0 + v, acc.1 + 1));
+                sum / count as RangePrec
+            }
+            DedupPolicy::Min => finite.fold(RangePrec::INFINITY, RangePrec::min),
+            DedupPolicy::Max => finite.fold(RangePrec::NEG_INFINITY, RangePrec::max),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct IngestReport {
+    /// Complete rows in the payload.
+    pub rows: usize,
+    /// Rows with a lower timestamp than the row before them.
+    pub out_of_order: usize,
+    /// Rows merged into another row with the same timestamp.
+    pub duplicates: usize,
+    /// `NaN` values, these are kept but skipped when merging duplicates.
+    pub nan: usize,
+    /// Infinite values, kept as well.
+    pub infinite: usize,
+    /// Rows with a `NaN` or infinite timestamp, these are dropped.
+    pub invalid_timestamps: usize,
+    /// Rows older than data a [`BulkLoader`] already pushed to the traces, these are dropped.
+    pub late: usize,
+    /// Bytes at the end of the payload that do not form a whole row.
+    pub trailing_bytes: usize,
+}
+
+/// Decoded rows of a bulkload payload, each has a timestamp and one value per trace.
+pub struct Rows {
+    pub xs: Vec<RangePrec>,
+    pub ys: Vec<RangePrec>,
+    pub columns: usize,
+}
+
+impl Rows {
+    /// Decodes all whole rows of `data`, returning them with the length of the incomplete rest.
+    /// Decoded rows and invalid values are counted in `report`.
+    pub fn decode(
+        data: &[u8],
+        x_desc: &TypeDescriptor,
+        y_desc: &TypeDescriptor,
+        columns: usize,
+        report: &mut IngestReport,
+    ) -> (Self, usize) {
+        let row_len = x_desc.size + y_desc.size * columns;
+        let chunks = data.chunks_exact(row_len);
+        let trailing = chunks.remainder().len();
+
+        let mut xs = Vec::with_capacity(chunks.len());
+        let mut ys = Vec::with_capacity(chunks.len() * columns);
+
+        report.rows += chunks.len();
+
+        for row in chunks {
+            let x = (x_desc.parser)(&row[0..x_desc.size]);
+
+            // Such rows have no place on the x axis
+            if !x.is_finite() {
+                report.invalid_timestamps += 1;
+                continue;
+            }
+
+            xs.push(x);
+            ys.extend(
+                row[x_desc.size..]
+                    .chunks_exact(y_desc.size)
+                    .map(y_desc.parser),
+            );
+        }
+
+        for y in ys.iter() {
+            if y.is_nan() {
+                report.nan += 1;
+            } else if y.is_infinite() {
+                report.infinite += 1;
+            }
+        }
+
+        (Self { xs, ys, columns }, trailing)
+    }
+
+    pub fn append(&mut self, other: &mut Rows) {
+        self.xs.append(&mut other.xs);
+        self.ys.append(&mut other.ys);
+    }
+
+    pub fn split_off(&mut self, at: usize) -> Rows {
+        Rows {
+            xs: self.xs.split_off(at),
+            ys: self.ys.split_off(at * self.columns),
+            columns: self.columns,
+        }
+    }
+
+    /// Removes rows at or before `x`, returning how many there were.
+    pub fn drop_until(&mut self, x: RangePrec) -> usize {
+        let columns = self.columns;
+        let keep: Vec<bool> = self.xs.iter().map(|&rx| rx > x).collect();
+
+        let mut i = 0;
+        self.ys.retain(|_| {
+            i += 1;
+            keep[(i - 1) / columns]
+        });
+        self.xs.retain(|&rx| rx > x);
+
+        keep.iter().filter(|k| !**k).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.xs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xs.is_empty()
+    }
+
+    /// Points of the trace in the given column.
+    pub fn column(&self, column: usize) -> Vec<(RangePrec, RangePrec)> {
+        self.xs
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| (x, self.ys[i * self.columns + column]))
+            .collect()
+    }
+
+    /// Sorts the rows by timestamp and merges rows with equal timestamps, counting every fix in `report`.
+    pub fn normalize(&mut self, dedup: DedupPolicy, report: &mut IngestReport) {
+        let out_of_order = self.xs.windows(2).filter(|w| w[1] < w[0]).count();
+        report.out_of_order += out_of_order;
+
+        let mut order: Vec<usize> = (0..self.len()).collect();
+
+        // The sort is stable, so duplicates stay in the order they were received in
+        if out_of_order > 0 {
+            order.sort_by(|&a, &b| self.xs[a].total_cmp(&self.xs[b]));
+        }
+
+        let mut xs = Vec::with_capacity(self.len());
+        let mut ys = Vec::with_capacity(self.ys.len());
+        let mut start = 0;
+
+        while start < order.len() {
+            let x = self.xs[order[start]];
+            let end = start
+                + order[start..]
+                    .iter()
+                    .take_while(|&&i| self.xs[i] == x)
+                    .count();
+            let group = &order[start..end];
+
+            xs.push(x);
+
+            for c in 0..self.columns {
+                let values = group.iter().map(|&i| self.ys[i * self.columns + c]);
+                ys.push(dedup.merge(values));
+            }
+
+            report.duplicates += group.len() - 1;
+            start = end;
+        }
+
+        self.xs = xs;
+        self.ys = ys;
+    }
+}
+
+/// Streaming variant of [`data::bulkload`], accepts the payload in arbitrary chunks and pushes
+/// decoded rows to the traces as they arrive. Compressed payloads are handled by a [`Decompressor`].
+#[wasm_bindgen]
+pub struct BulkLoader {
+    ptrs: Vec<DataIdx>,
+    x_type: String,
+    y_type: String,
+    dedup: DedupPolicy,
+    total: Option<usize>,
+    received: usize,
+    /// Requested range, loaded as a whole once the payload is finished.
+    range: Option<(RangePrec, RangePrec)>,
+
+    decompressor: Decompressor,
+    partial: Vec<u8>,
+    pending: Rows,
+    flushed_to: Option<RangePrec>,
+    report: IngestReport,
+}
+
+#[wasm_bindgen]
+impl BulkLoader {
+    /// `total` is the expected payload size in bytes, if known. `dedup` is one of `first`, `last` (default),
+    /// `mean`, `min` or `max`. `from` and `to` are the requested range, which counts as loaded even
+    /// where it has no rows.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        ptrs: &[DataIdx],
+        x_type: &str,
+        y_type: &str,
+        total: Option<usize>,
+        dedup: Option<String>,
+        from: Option<RangePrec>,
+        to: Option<RangePrec>,
+    ) -> Result<BulkLoader, JsValue> {
+        let dedup = dedup
+            .as_deref()
+            .map_or(Ok(DedupPolicy::Last), DedupPolicy::parse)?;
+
+        Ok(Self {
+            ptrs: ptrs.to_vec(),
+            x_type: x_type.to_owned(),
+            y_type: y_type.to_owned(),
+            dedup,
+            total,
+            received: 0,
+            range: from.zip(to),
+            decompressor: Decompressor::default(),
+            partial: vec![],
+            pending: Rows {
+                xs: vec![],
+                ys: vec![],
+                columns: ptrs.len(),
+            },
+            flushed_to: None,
+            report: IngestReport::default(),
+        })
+    }
+
+    /// Decodes the whole rows of `chunk`, a partial row at its end is kept until the next chunk.
+    /// Returns whether rows were flushed to the traces.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<bool, JsValue> {
+        self.write(chunk).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Pushes the decoded rows to the traces. The newest timestamp is held back, so that duplicates
+    /// of it in the following chunks can still be merged. Returns whether any rows were pushed.
+    pub fn flush(&mut self) -> bool {
+        self.flush_rows(false)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rows(&self) -> usize {
+        self.report.rows
+    }
+
+    /// Fraction of the expected bytes received so far, `NaN` without a known total.
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> RangePrec {
+        match self.total {
+            Some(total) if total > 0 => (self.received as RangePrec / total as RangePrec).min(1.0),
+            _ => RangePrec::NAN,
+        }
+    }
+
+    /// Flushes all remaining rows and returns the ingest report. The loader still has to be freed.
+    pub fn finish(&mut self) -> Result<JsValue, JsValue> {
+        let report = self.close().map_err(|e| JsValue::from_str(&e))?;
+
+        to_js(&report)
+    }
+}
+
+impl BulkLoader {
+    /// See [`BulkLoader::push`].
+    pub fn write(&mut self, chunk: &[u8]) -> Result<bool, String> {
+        self.received += chunk.len();
+        self.decompressor.write(chunk, &mut self.partial)?;
+        self.decode();
+
+        Ok(self.pending.len() >= FLUSH_ROWS && self.flush())
+    }
+
+    /// See [`BulkLoader::finish`].
+    pub fn close(&mut self) -> Result<IngestReport, String> {
+        std::mem::take(&mut self.decompressor).finish(&mut self.partial)?;
+        self.decode();
+
+        self.flush_rows(true);
+        self.report.trailing_bytes = self.partial.len();
+
+        Ok(std::mem::take(&mut self.report))
+    }
+
+    fn decode(&mut self) {
+        let (mut rows, rest) = Rows::decode(
+            &self.partial,
+            TYPE_SIZES.get(self.x_type.as_str()).unwrap(),
+            TYPE_SIZES.get(self.y_type.as_str()).unwrap(),
+            self.ptrs.len(),
+            &mut self.report,
+        );
+
+        self.partial.drain(..self.partial.len() - rest);
+        self.pending.append(&mut rows);
+    }
+
+    fn flush_rows(&mut self, last: bool) -> bool {
+        if let Some(flushed_to) = self.flushed_to {
+            self.report.late += self.pending.drop_until(flushed_to);
+        }
+
+        self.pending.normalize(self.dedup, &mut self.report);
+
+        let held = match self.pending.len() {
+            len if len > 0 && !last => self.pending.split_off(len - 1),
+            len => self.pending.split_off(len),
+        };
+
+        // The last segment reaches the end of the requested range, also without any rows
+        let range_to = match (last, self.range) {
+            (true, Some((_, to))) if !matches!(self.flushed_to, Some(f) if f >= to) => Some(to),
+            _ => None,
+        };
+
+        let push = !self.pending.is_empty() || range_to.is_some();
+
+        if push {
+            // Start where the previous rows ended, so the pushed segments form one loaded range
+            let first = self.pending.xs.first().copied();
+            let from = match (self.flushed_to, self.range) {
+                (Some(flushed_to), _) => flushed_to,
+                (None, Some((from, _))) => first.map_or(from, |x| x.min(from)),
+                (None, None) => first.unwrap(),
+            };
+            let last_x = self.pending.xs.last().copied().unwrap_or(from);
+            let to = range_to.map_or(last_x, |to| last_x.max(to));
+
+            data::push_rows(
+                &self.ptrs,
+                &self.x_type,
+                &self.y_type,
+                from,
+                to,
+                &self.pending,
+            );
+            self.flushed_to = Some(to);
+        }
+
+        self.pending = held;
+
+        push
+    }
+}
@@ -1,5 +1,8 @@
 pub mod data;
+pub mod ingest;
+pub mod pipeline;
 pub mod renderers;
+pub mod stats;
 pub mod structs;
 pub mod utils;
 
@@ -0,0 +1,233 @@
+use crate::structs::RangePrec;
+
+/// Arithmetic expression over the sample position `x` and value `y`, e.g. `abs(y - 10) * 2`.
+///
+/// Supports numbers, `+ - * / % ^`, unary minus, parentheses and the functions
+/// `abs`, `sqrt`, `ln`, `log10`, `exp`, `floor`, `ceil`, `min` and `max`.
+#[derive(Debug)]
+pub enum Expr {
+    Num(RangePrec),
+    X,
+    Y,
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(RangePrec),
+    Ident(String),
+    Op(char),
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+
+        let expr = parser.expression()?;
+
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(t) => Err(format!("Unexpected {:?} in expression", t)),
+        }
+    }
+
+    pub fn eval(&self, x: RangePrec, y: RangePrec) -> RangePrec {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::X => x,
+            Expr::Y => y,
+            Expr::Neg(e) => -e.eval(x, y),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(x, y), b.eval(x, y));
+
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '%' => a % b,
+                    '^' => a.powf(b),
+                    _ => unreachable!(),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args: Vec<RangePrec> = args.iter().map(|a| a.eval(x, y)).collect();
+
+                match (name.as_str(), args.as_slice()) {
+                    ("abs", [a]) => a.abs(),
+                    ("sqrt", [a]) => a.sqrt(),
+                    ("ln", [a]) => a.ln(),
+                    ("log10", [a]) => a.log10(),
+                    ("exp", [a]) => a.exp(),
+                    ("floor", [a]) => a.floor(),
+                    ("ceil", [a]) => a.ceil(),
+                    ("min", [a, b]) => a.min(*b),
+                    ("max", [a, b]) => a.max(*b),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut num = String::new();
+
+            while let Some(&c) = chars.peek() {
+                let exponent_sign = (c == '-' || c == '+') && num.ends_with(['e', 'E']);
+
+                if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                    num.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            tokens
+                .push(Token::Num(num.parse().map_err(|_| {
+                    format!("Invalid number '{}' in expression", num)
+                })?));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut ident = String::new();
+
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    ident.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            return Err(format!("Unexpected character '{}' in expression", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(c)) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in expression", op))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+
+        while let Some(op @ ('+' | '-')) = self.peek_op() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+
+        while let Some(op @ ('*' | '/' | '%')) = self.peek_op() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_op() == Some('-') {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.primary()?;
+
+        if self.peek_op() == Some('^') {
+            self.pos += 1;
+            // Right associative, binds tighter than unary minus on its left
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| String::from("Unexpected end of expression"))?;
+        self.pos += 1;
+
+        match token {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Op('(') => {
+                let inner = self.expression()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Token::Ident(name) if name == "x" => Ok(Expr::X),
+            Token::Ident(name) if name == "y" => Ok(Expr::Y),
+            Token::Ident(name) => {
+                let arity = match name.as_str() {
+                    "abs" | "sqrt" | "ln" | "log10" | "exp" | "floor" | "ceil" => 1,
+                    "min" | "max" => 2,
+                    _ => return Err(format!("Unknown identifier '{}' in expression", name)),
+                };
+
+                self.expect('(')?;
+                let mut args = vec![self.expression()?];
+
+                while self.peek_op() == Some(',') {
+                    self.pos += 1;
+                    args.push(self.expression()?);
+                }
+
+                self.expect(')')?;
+
+                if args.len() != arity {
+                    return Err(format!("Function '{}' expects {} argument(s)", name, arity));
+                }
+
+                Ok(Expr::Call(name, args))
+            }
+            Token::Op(c) => Err(format!("Unexpected '{}' in expression", c)),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+mod expr;
+
+pub use expr::Expr;
+
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    data::{self, DataIdx},
+    stats,
+    structs::RangePrec,
+    utils::{from_js, to_js},
+};
+
+#[derive(Deserialize)]
+pub struct PipelineRequest {
+    pub from: RangePrec,
+    pub to: RangePrec,
+
+    pub pipelines: Vec<NodeDescriptor>,
+}
+
+#[derive(Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NodeDescriptor {
+    Data {
+        dataset: DatasetDescriptor,
+    },
+    Diff {
+        child: Box<NodeDescriptor>,
+    },
+    Expr {
+        options: ExprOptions,
+        child: Box<NodeDescriptor>,
+    },
+    Sum {
+        children: Vec<NodeDescriptor>,
+    },
+    Avg {
+        children: Vec<NodeDescriptor>,
+    },
+}
+
+#[derive(Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+pub struct DatasetDescriptor {
+    pub source: String,
+    pub id: String,
+    pub variant: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+pub struct ExprOptions {
+    pub expression: String,
+}
+
+/// Canonical textual form of the node, see [`NodeDescriptor::output_id`].
+impl fmt::Display for NodeDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            children: &[NodeDescriptor],
+        ) -> fmt::Result {
+            write!(f, "{}(", name)?;
+
+            for (i, c) in children.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ",")?;
+                }
+
+                write!(f, "{}", c)?;
+            }
+
+            write!(f, ")")
+        }
+
+        match self {
+            NodeDescriptor::Data { dataset } => write!(f, "{}", dataset.trace_id()),
+            NodeDescriptor::Diff { child } => write!(f, "diff({})", child),
+            NodeDescriptor::Expr { options, child } => {
+                write!(f, "expr[{}]({})", options.expression, child)
+            }
+            NodeDescriptor::Sum { children } => list(f, "sum", children),
+            NodeDescriptor::Avg { children } => list(f, "avg", children),
+        }
+    }
+}
+
+impl NodeDescriptor {
+    /// Id of the trace a pipeline is evaluated into. A bare dataset is wrapped, so that its output
+    /// never replaces the source trace.
+    pub fn output_id(&self) -> String {
+        match self {
+            NodeDescriptor::Data { .. } => format!("data({})", self),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl DatasetDescriptor {
+    /// Id under which the data job stores the downloaded trace.
+    pub fn trace_id(&self) -> String {
+        match &self.variant {
+            Some(variant) => format!("{}::{}::{}", self.source, self.id, variant),
+            None => format!("{}::{}", self.source, self.id),
+        }
+    }
+
+    /// Without a variant, the dataset has to be loaded in exactly one variant.
+    fn resolve(&self) -> Result<DataIdx, String> {
+        let id = self.trace_id();
+        let prefix = format!("{}::", id);
+
+        let candidates = data::find_traces(|t| match self.variant {
+            Some(_) => t.id == id,
+            None => t.id == id || t.id.starts_with(&prefix),
+        });
+
+        match candidates.as_slice() {
+            [handle] => Ok(*handle),
+            [] => Err(format!("Trace '{}' is not loaded", id)),
+            _ => Err(format!("Trace '{}' is loaded in multiple variants", id)),
+        }
+    }
+}
+
+pub struct Series {
+    pub x_type: String,
+    pub y_type: String,
+
+    pub points: Vec<(RangePrec, RangePrec)>,
+    /// Parts of the evaluated range loaded in every leaf, sorted and disjoint.
+    pub ranges: Vec<(RangePrec, RangePrec)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PipelineOutput {
+    pub handle: DataIdx,
+    pub id: String,
+    pub x_type: String,
+    pub y_type: String,
+}
+
+/// Evaluates nodes bottom-up, identical subtrees are evaluated only once.
+pub struct Evaluator {
+    from: RangePrec,
+    to: RangePrec,
+
+    cache: HashMap<NodeDescriptor, Rc<Series>>,
+}
+
+impl Evaluator {
+    pub fn new(from: RangePrec, to: RangePrec) -> Self {
+        Self {
+            from,
+            to,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn eval(&mut self, node: &NodeDescriptor) -> Result<Rc<Series>, String> {
+        if let Some(series) = self.cache.get(node) {
+            return Ok(series.clone());
+        }
+
+        let series = Rc::new(match node {
+            NodeDescriptor::Data { dataset } => {
+                let handle = dataset.resolve()?;
+
+                data::get_trace_ret(handle, |t| Series {
+                    x_type: t.x_type.clone(),
+                    y_type: String::from(t.get_y_type().unwrap_or("double")),
+                    points: t.get_data_high_prec(self.from, self.to).collect(),
+                    ranges: intersect(&t.get_loaded_ranges(), &[(self.from, self.to)]),
+                })
+            }
+            NodeDescriptor::Diff { child } => {
+                let child = self.eval(child)?;
+
+                Series {
+                    x_type: child.x_type.clone(),
+                    y_type: String::from("double"),
+                    points: child
+                        .points
+                        .windows(2)
+                        .map(|w| (w[1].0, w[1].1 - w[0].1))
+                        .collect(),
+                    ranges: child.ranges.clone(),
+                }
+            }
+            NodeDescriptor::Expr { options, child } => {
+                let expr = Expr::parse(&options.expression)?;
+                let child = self.eval(child)?;
+
+                Series {
+                    x_type: child.x_type.clone(),
+                    y_type: String::from("double"),
+                    points: child
+                        .points
+                        .iter()
+                        .map(|&(x, y)| (x, expr.eval(x, y)))
+                        .collect(),
+                    ranges: child.ranges.clone(),
+                }
+            }
+            NodeDescriptor::Sum { children } => self.eval_many(children, 1.0)?,
+            NodeDescriptor::Avg { children } => {
+                self.eval_many(children, 1.0 / children.len() as RangePrec)?
+            }
+        });
+
+        self.cache.insert(node.clone(), series.clone());
+
+        Ok(series)
+    }
+
+    fn eval_many(&mut self, children: &[NodeDescriptor], mul: RangePrec) -> Result<Series, String> {
+        let children = children
+            .iter()
+            .map(|c| self.eval(c))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let x_type = match children.first() {
+            Some(c) => c.x_type.clone(),
+            None => return Err(String::from("Aggregation without children")),
+        };
+
+        if children.iter().any(|c| c.x_type != x_type) {
+            return Err(String::from("Aggregated traces have different x types"));
+        }
+
+        let ranges = children[1..]
+            .iter()
+            .fold(children[0].ranges.clone(), |acc, c| {
+                intersect(&acc, &c.ranges)
+            });
+
+        let (xs, ys) = stats::align(
+            &children
+                .iter()
+                .map(|c| c.points.clone())
+                .collect::<Vec<_>>(),
+        );
+
+        Ok(Series {
+            x_type,
+            y_type: String::from("double"),
+            points: xs
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| (x, ys.iter().map(|y| y[i]).sum::<RangePrec>() * mul))
+                .collect(),
+            ranges,
+        })
+    }
+}
+
+/// Overlaps of two sorted lists of disjoint ranges.
+fn intersect(
+    a: &[(RangePrec, RangePrec)],
+    b: &[(RangePrec, RangePrec)],
+) -> Vec<(RangePrec, RangePrec)> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        let from = a[i].0.max(b[j].0);
+        let to = a[i].1.min(b[j].1);
+
+        if from < to {
+            result.push((from, to));
+        }
+
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    result
+}
+
+/// Evaluates every pipeline of the request into a trace with the id of [`NodeDescriptor::output_id`].
+/// Only the parts of the range loaded in all leaves are stored as loaded.
+pub fn eval_request(request: &PipelineRequest) -> Result<Vec<PipelineOutput>, String> {
+    let mut evaluator = Evaluator::new(request.from, request.to);
+
+    request
+        .pipelines
+        .iter()
+        .map(|node| {
+            let series = evaluator.eval(node)?;
+            let id = node.output_id();
+
+            // Re-evaluating a pipeline over another range extends its existing trace
+            let handle = match data::find_traces(|t| t.id == id).first() {
+                Some(handle) => *handle,
+                None => data::create_trace(&id, &series.x_type),
+            };
+
+            for &(from, to) in &series.ranges {
+                let points: Vec<_> = series
+                    .points
+                    .iter()
+                    .copied()
+                    .filter(|p| p.0 >= from && p.0 <= to)
+                    .collect();
+
+                data::get_trace_once(handle, |t| {
+                    t.push_segment(data::create_derived_segment(
+                        &series.x_type,
+                        &series.y_type,
+                        from,
+                        to,
+                        &points,
+                    ))
+                });
+            }
+
+            Ok(PipelineOutput {
+                handle,
+                id,
+                x_type: series.x_type.clone(),
+                y_type: series.y_type.clone(),
+            })
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn eval_pipelines(request: &JsValue) -> Result<JsValue, JsValue> {
+    let request: PipelineRequest = from_js(request)?;
+
+    to_js(&eval_request(&request)?)
+}
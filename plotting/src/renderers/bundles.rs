@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::{data::DataIdx, structs::RangePrec};
+
+use super::BundleEntry;
+
+pub struct BundleRow<T> {
+    pub style: BundleEntry,
+    /// What the backend keeps for the trace, e.g. its points or a GPU buffer.
+    pub data: T,
+}
+
+pub struct Bundle<T> {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub rows: Vec<BundleRow<T>>,
+}
+
+/// Bundles of a renderer by their handles, `alloc` callbacks load the data of a new row.
+pub struct Bundles<T> {
+    counter: usize,
+    bundles: HashMap<usize, Bundle<T>>,
+}
+
+impl<T> Default for Bundles<T> {
+    fn default() -> Self {
+        Self {
+            counter: 0,
+            bundles: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Bundles<T> {
+    pub fn get(&self, bundle: usize) -> Option<&Bundle<T>> {
+        self.bundles.get(&bundle)
+    }
+
+    pub fn create<E>(
+        &mut self,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+        mut alloc: impl FnMut(RangePrec, RangePrec, &BundleEntry) -> Result<T, E>,
+    ) -> Result<usize, E> {
+        let mut rows = Vec::with_capacity(data.len());
+
+        for row in data {
+            rows.push(BundleRow {
+                style: *row,
+                data: alloc(from, to, row)?,
+            });
+        }
+
+        let handle = self.counter;
+        self.counter += 1;
+        self.bundles.insert(handle, Bundle { from, to, rows });
+
+        Ok(handle)
+    }
+
+    /// Adds, removes and restyles rows of a bundle, returns the data of the removed ones. Rows that
+    /// change their line shape are loaded again, their old data is returned too.
+    pub fn rebundle<E>(
+        &mut self,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+        mut alloc: impl FnMut(RangePrec, RangePrec, &BundleEntry) -> Result<T, E>,
+    ) -> Result<Vec<T>, E> {
+        let b = self.bundles.get_mut(&bundle).unwrap();
+
+        for row in to_add {
+            b.rows.push(BundleRow {
+                style: *row,
+                data: alloc(b.from, b.to, row)?,
+            });
+        }
+
+        let mut removed = vec![];
+        let mut i = 0;
+        while i < b.rows.len() {
+            if to_del.contains(&b.rows[i].style.handle) {
+                removed.push(b.rows.remove(i).data);
+            } else {
+                i += 1;
+            }
+        }
+
+        for row in to_mod {
+            if let Some(entry) = b.rows.iter_mut().find(|e| e.style.handle == row.handle) {
+                // The corners of steps are part of the loaded data
+                if entry.style.shape != row.shape {
+                    let data = alloc(b.from, b.to, row)?;
+                    removed.push(std::mem::replace(&mut entry.data, data));
+                }
+
+                entry.style = *row;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    pub fn dispose(&mut self, bundle: usize) -> Option<Bundle<T>> {
+        self.bundles.remove(&bundle)
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = Bundle<T>> + '_ {
+        self.bundles.drain().map(|(_, b)| b)
+    }
+}
+
+/// Points of a bundle row relative to the bundle start, for backends that draw from memory.
+/// Steps of the row are included as extra corners.
+pub fn load_points(from: RangePrec, to: RangePrec, entry: &BundleEntry) -> Vec<(f32, f32)> {
+    let points = crate::data::get_trace_ret(entry.handle, |t| {
+        t.get_data_with_origin(from, to, from, 0.0).collect()
+    });
+
+    entry.shape.apply(points)
+}
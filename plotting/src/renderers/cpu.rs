@@ -0,0 +1,540 @@
+use std::ops::Range;
+
+use wasm_bindgen::JsValue;
+
+use crate::{
+    data::DataIdx,
+    structs::{RangePrec, RenderJob},
+};
+
+use super::{
+    bundles::load_points,
+    font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH},
+    scene::{Anchor, Baseline, Color, PlotArea, Primitive, Space, Stroke},
+    stroke, BundleEntry, Bundles, RenderJobResult, Renderer, Scene,
+};
+
+const CSS_DPI: f32 = 96.0;
+const METERS_PER_INCH: f32 = 0.0254;
+
+/// Area of the window in pixels with the origin at the bottom left, as in a WebGL viewport.
+#[derive(Clone, Copy)]
+struct Viewport {
+    left: f32,
+    bottom: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Viewport {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left
+            && x < self.left + self.width
+            && y >= self.bottom
+            && y < self.bottom + self.height
+    }
+
+    /// Pixel columns whose centers lie between `a` and `b`, limited to the viewport.
+    fn columns(&self, a: f32, b: f32) -> Range<i32> {
+        pixel_span(a, b, self.left, self.left + self.width)
+    }
+
+    /// Pixel rows whose centers lie between `a` and `b`, limited to the viewport.
+    fn rows(&self, a: f32, b: f32) -> Range<i32> {
+        pixel_span(a, b, self.bottom, self.bottom + self.height)
+    }
+
+    /// Liang–Barsky clipping of a line, `None` if it lies outside.
+    fn clip(&self, from: (f32, f32), to: (f32, f32)) -> Option<((f32, f32), (f32, f32))> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let mut t0: f32 = 0.0;
+        let mut t1: f32 = 1.0;
+
+        for (p, q) in [
+            (-dx, from.0 - self.left),
+            (dx, self.left + self.width - from.0),
+            (-dy, from.1 - self.bottom),
+            (dy, self.bottom + self.height - from.1),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+
+        if t0 > t1 {
+            return None;
+        }
+
+        Some((
+            (from.0 + t0 * dx, from.1 + t0 * dy),
+            (from.0 + t1 * dx, from.1 + t1 * dy),
+        ))
+    }
+}
+
+fn finite(p: (f32, f32)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
+}
+
+/// Pixels whose centers lie between `a` and `b`, limited to those in `min..max`. Empty when the
+/// coordinates are NaN, infinite ones are cut off at the limits.
+fn pixel_span(a: f32, b: f32, min: f32, max: f32) -> Range<i32> {
+    if a.is_nan() || b.is_nan() {
+        return 0..0;
+    }
+
+    let first = (a.min(b) - 0.5).ceil().max(min.ceil());
+    let last = (a.max(b) - 0.5).ceil().min(max.ceil());
+
+    if first < last {
+        first as i32..last as i32
+    } else {
+        0..0
+    }
+}
+
+/// Renders into an RGBA pixel buffer in memory, it needs no canvas and draws the same
+/// scene as [`super::WebGlRenderer`]. Text is not drawn and lines are stamped with square
+/// brushes, without joins or caps.
+pub struct CpuRenderer {
+    width: u32,
+    height: u32,
+    /// Device pixels per CSS pixel, job layout and line widths are in CSS pixels.
+    scale: f32,
+    pixels: Vec<u8>,
+
+    bundles: Bundles<Vec<(f32, f32)>>,
+}
+
+impl CpuRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_scale(width, height, 1.0)
+    }
+
+    /// Renderer of `width` by `height` device pixels, see [`CpuRenderer::scale`].
+    pub fn with_scale(width: u32, height: u32, scale: f32) -> Self {
+        Self {
+            width,
+            height,
+            scale,
+            pixels: vec![0; (width * height * 4) as usize],
+
+            bundles: Bundles::default(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Rows of RGBA pixels with straight alpha, starting at the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Pixel at the given column and row, rows start at the top.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let idx = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[idx],
+            self.pixels[idx + 1],
+            self.pixels[idx + 2],
+            self.pixels[idx + 3],
+        ]
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|p| *p = 0);
+    }
+
+    /// Encodes the pixels as a PNG, its physical size is set to 96 DPI times the scale.
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut out = vec![];
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        let pixels_per_meter = (CSS_DPI * self.scale / METERS_PER_INCH).round() as u32;
+
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: png::Unit::Meter,
+        }));
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|e| e.to_string())?;
+
+        Ok(out)
+    }
+
+    fn window(&self) -> Viewport {
+        Viewport {
+            left: 0.0,
+            bottom: 0.0,
+            width: self.width as f32,
+            height: self.height as f32,
+        }
+    }
+
+    fn plot_viewport(&self, plot: &PlotArea) -> Viewport {
+        let scaled = |v: f32| (v * self.scale).round();
+
+        Viewport {
+            left: scaled(plot.left),
+            bottom: scaled(plot.bottom),
+            width: scaled(plot.left + plot.width) - scaled(plot.left),
+            height: scaled(plot.bottom + plot.height) - scaled(plot.bottom),
+        }
+    }
+
+    /// Blends a color over the pixel at the given window coordinates.
+    fn blend(&mut self, x: f32, y: f32, color: [f32; 4]) {
+        let (col, row) = (x.floor() as u32, self.height - 1 - y.floor() as u32);
+        let idx = ((row * self.width + col) * 4) as usize;
+        let dst = &mut self.pixels[idx..idx + 4];
+
+        let src_a = color[3];
+        let dst_a = dst[3] as f32 / 255.0;
+        let out_a = src_a + dst_a * (1.0 - src_a);
+
+        if out_a <= 0.0 {
+            return;
+        }
+
+        for c in 0..3 {
+            let mixed = (color[c] * src_a + dst[c] as f32 / 255.0 * dst_a * (1.0 - src_a)) / out_a;
+            dst[c] = (mixed * 255.0).round() as u8;
+        }
+
+        dst[3] = (out_a * 255.0).round() as u8;
+    }
+
+    /// Fills a square of `size` pixels centered at the pixel containing the given point.
+    fn stamp(&mut self, x: f32, y: f32, size: f32, color: [f32; 4], clip: Viewport) {
+        let size = size.round().max(1.0) as i32;
+        let (cx, cy) = (x.floor() as i32, y.floor() as i32);
+
+        for dy in -(size - 1) / 2..=size / 2 {
+            for dx in -(size - 1) / 2..=size / 2 {
+                let (px, py) = ((cx + dx) as f32, (cy + dy) as f32);
+
+                if clip.contains(px, py) {
+                    self.blend(px, py, color);
+                }
+            }
+        }
+    }
+
+    /// Lines with a NaN or infinite end are gaps and not drawn.
+    fn draw_line(
+        &mut self,
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: [f32; 4],
+        clip: Viewport,
+    ) {
+        if !finite(from) || !finite(to) {
+            return;
+        }
+
+        let (from, to) = match clip.clip(from, to) {
+            Some(line) => line,
+            None => return,
+        };
+
+        let (mut x, mut y) = (from.0.floor() as i32, from.1.floor() as i32);
+        let (x1, y1) = (to.0.floor() as i32, to.1.floor() as i32);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+
+        loop {
+            self.stamp(x as f32, y as f32, width, color, clip);
+
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Fills the pixels whose centers lie between two corners.
+    fn fill_rect(&mut self, from: (f32, f32), to: (f32, f32), color: Color, clip: Viewport) {
+        for y in clip.rows(from.1, to.1) {
+            for x in clip.columns(from.0, to.0) {
+                self.blend(x as f32, y as f32, color);
+            }
+        }
+    }
+
+    /// Fills the pixels whose centers lie between two polylines sharing their x coordinates.
+    fn fill_band(
+        &mut self,
+        upper: &[(f32, f32)],
+        lower: &[(f32, f32)],
+        color: Color,
+        clip: Viewport,
+    ) {
+        for i in 1..upper.len().min(lower.len()) {
+            let (u0, u1, l0, l1) = (upper[i - 1], upper[i], lower[i - 1], lower[i]);
+
+            if u1.0 <= u0.0 {
+                continue;
+            }
+
+            for x in clip.columns(u0.0, u1.0) {
+                let t = (x as f32 + 0.5 - u0.0) / (u1.0 - u0.0);
+                let top = u0.1 + (u1.1 - u0.1) * t;
+                let bottom = l0.1 + (l1.1 - l0.1) * t;
+
+                for y in clip.rows(top, bottom) {
+                    self.blend(x as f32, y as f32, color);
+                }
+            }
+        }
+    }
+
+    /// Draws text with the bitmap font into the window, glyphs are as high as the cap height of
+    /// `size` pixels.
+    fn draw_text(
+        &mut self,
+        pos: (f32, f32),
+        text: &str,
+        size: f32,
+        anchor: Anchor,
+        baseline: Baseline,
+        color: Color,
+    ) {
+        let clip = self.window();
+        let cell = size * 0.7 / GLYPH_HEIGHT as f32;
+        let advance = (GLYPH_WIDTH + 1) as f32 * cell;
+        let width = text.chars().count() as f32 * advance - cell;
+
+        let left = match anchor {
+            Anchor::Start => pos.0,
+            Anchor::Middle => pos.0 - width / 2.0,
+            Anchor::End => pos.0 - width,
+        };
+        let top = match baseline {
+            Baseline::Top => pos.1,
+            Baseline::Middle => pos.1 + GLYPH_HEIGHT as f32 * cell / 2.0,
+        };
+
+        for (i, rows) in text.chars().map(glyph).enumerate() {
+            let x = left + i as f32 * advance;
+
+            for (row, bits) in rows.iter().flatten().enumerate() {
+                let y = top - (row + 1) as f32 * cell;
+
+                for col in (0..GLYPH_WIDTH).filter(|col| bits >> (GLYPH_WIDTH - 1 - col) & 1 == 1) {
+                    let x = x + col as f32 * cell;
+                    self.fill_rect((x, y), (x + cell, y + cell), color, clip);
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, scene: &Scene) {
+        let window = self.window();
+        let viewport = self.plot_viewport(&scene.plot);
+        let bundles = std::mem::take(&mut self.bundles);
+        let scale = self.scale;
+
+        let project = |space: Space, p: (f32, f32), offset: f32| match space {
+            Space::Screen => (p.0 * scale, p.1 * scale),
+            Space::Plot => {
+                let (x, y) = scene.plot.normalize(p, offset);
+                (
+                    viewport.left + x * viewport.width,
+                    viewport.bottom + y * viewport.height,
+                )
+            }
+        };
+
+        for command in &scene.commands {
+            let space = command.space;
+            let clip = match space {
+                Space::Screen => window,
+                Space::Plot => viewport,
+            };
+
+            match &command.primitive {
+                Primitive::Polyline {
+                    vertices,
+                    stroke,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&bundles, &scene.plot);
+                    let projected: Vec<(f32, f32)> =
+                        points.iter().map(|&p| project(space, p, offset)).collect();
+
+                    for dash in stroke::dashes(&projected, &dash_lengths(stroke, scale)) {
+                        for w in dash.windows(2) {
+                            self.draw_line(w[0], w[1], stroke.width * scale, *color, clip);
+                        }
+                    }
+                }
+                Primitive::Lines {
+                    segments,
+                    stroke,
+                    color,
+                } => {
+                    let pattern = dash_lengths(stroke, scale);
+
+                    for [from, to] in segments {
+                        let line = [project(space, *from, 0.0), project(space, *to, 0.0)];
+
+                        for dash in stroke::dashes(&line, &pattern) {
+                            self.draw_line(dash[0], dash[1], stroke.width * scale, *color, clip);
+                        }
+                    }
+                }
+                Primitive::Markers {
+                    vertices,
+                    size,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&bundles, &scene.plot);
+
+                    for &p in points {
+                        let (x, y) = project(space, p, offset);
+
+                        if finite((x, y)) {
+                            self.stamp(x, y, size * scale, *color, clip);
+                        }
+                    }
+                }
+                Primitive::Band {
+                    upper,
+                    lower,
+                    color,
+                } => {
+                    let upper: Vec<(f32, f32)> =
+                        upper.iter().map(|&p| project(space, p, 0.0)).collect();
+                    let lower: Vec<(f32, f32)> =
+                        lower.iter().map(|&p| project(space, p, 0.0)).collect();
+
+                    self.fill_band(&upper, &lower, *color, clip);
+                }
+                Primitive::Rect { from, to, color } => {
+                    let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                    self.fill_rect(from, to, *color, clip);
+                }
+                Primitive::Rects { rects, color } => {
+                    for [from, to] in rects {
+                        let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                        self.fill_rect(from, to, *color, clip);
+                    }
+                }
+                Primitive::Text {
+                    pos,
+                    text,
+                    size,
+                    anchor,
+                    baseline,
+                    color,
+                } => {
+                    let pos = project(space, *pos, 0.0);
+                    self.draw_text(pos, text, size * scale, *anchor, *baseline, *color);
+                }
+            }
+        }
+
+        self.bundles = bundles;
+    }
+}
+
+/// Dash pattern of a stroke in device pixels.
+fn dash_lengths(stroke: &Stroke, scale: f32) -> Vec<f32> {
+    stroke.dash_lengths().iter().map(|l| l * scale).collect()
+}
+
+impl Renderer for CpuRenderer {
+    fn render(&mut self, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        let scene = Scene::compile(
+            &job,
+            self.width as f32 / self.scale,
+            self.height as f32 / self.scale,
+            &self.bundles,
+        );
+
+        if scene.clear {
+            self.clear();
+        }
+
+        self.draw(&scene);
+
+        Ok(scene.into_result())
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; (width * height * 4) as usize];
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+    ) -> Result<usize, JsValue> {
+        self.bundles.create(from, to, data, |from, to, row| {
+            Ok(load_points(from, to, row))
+        })
+    }
+
+    fn rebundle(
+        &mut self,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles.rebundle(
+            bundle,
+            to_add,
+            to_del,
+            to_mod,
+            |from, to, row| -> Result<_, JsValue> { Ok(load_points(from, to, row)) },
+        )?;
+
+        Ok(())
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,10 @@
+mod bundles;
+mod cpu;
+mod font;
+mod pdf;
+pub mod scene;
+pub mod stroke;
+mod svg;
 mod webgl;
 use std::{convert::TryInto, mem::size_of};
 
@@ -6,25 +13,54 @@ use wasm_bindgen::prelude::*;
 use web_sys::OffscreenCanvas;
 
 use crate::structs::{RangePrec, RenderJob};
+use crate::utils::to_js;
+pub use bundles::{Bundle, BundleRow, Bundles};
+pub use cpu::CpuRenderer;
+pub use pdf::PdfRenderer;
+pub use scene::Scene;
+pub use svg::SvgRenderer;
 pub use webgl::WebGlRenderer;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct BundleEntry {
-    handle: usize,
-    width: u32,
-    color: [u8; 3],
-    points_mode: bool,
+    pub handle: usize,
+    pub width: u32,
+    pub color: [u8; 3],
+    pub points_mode: bool,
+    pub dash: scene::DashPattern,
+    pub fill: scene::Fill,
+    pub bars: Option<scene::Bars>,
+    pub shape: scene::LineShape,
+}
+
+impl BundleEntry {
+    /// Handle and width as big endian `u32`, color, points mode, the dash pattern bytes, the
+    /// fill, the bars and the line shape followed by three reserved bytes, see
+    /// [`scene::Fill::from_row`], [`scene::Bars::from_row`] and [`scene::LineShape::from_byte`].
+    fn from_row(row: &[u8]) -> Self {
+        Self {
+            handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
+            width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
+            color: row[8..11].try_into().unwrap(),
+            points_mode: row[11] > 0,
+            dash: row[12..16].try_into().unwrap(),
+            fill: scene::Fill::from_row(&row[16..24]),
+            bars: scene::Bars::from_row(&row[24..36]),
+            shape: scene::LineShape::from_byte(row[36]),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct AxisTick {
-    val: RangePrec,
-    pos: RangePrec,
+    pub val: RangePrec,
+    pub pos: RangePrec,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct RenderJobResult {
-    x_ticks: Box<[AxisTick]>,
-    y_ticks: Box<[AxisTick]>,
+    pub x_ticks: Box<[AxisTick]>,
+    pub y_ticks: Box<[AxisTick]>,
 }
 
 pub trait Renderer {
@@ -46,12 +82,129 @@ pub trait Renderer {
     fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue>;
 }
 
+/// Ticks at round values for an axis starting at `start`, positions are relative to `width`.
+pub fn calc_ticks(start: RangePrec, width: RangePrec) -> Box<[AxisTick]> {
+    const SIZES: [RangePrec; 4] = [1.0, 2.0, 5.0, 10.0];
+
+    let mut y0: RangePrec = 0.0;
+    let mut dy: RangePrec = 1.0;
+
+    {
+        let order = width.log10().floor() - 1.0;
+
+        for size in SIZES.iter() {
+            dy = (10.0 as RangePrec).powf(order) * size;
+            y0 = (start / dy).floor() * dy;
+
+            if (width + start - y0) / dy < 10.0 {
+                break;
+            }
+        }
+    }
+
+    (1..=((width + start - y0) / dy).floor() as usize)
+        .map(|i| AxisTick {
+            val: y0 + dy * i as RangePrec,
+            pos: (y0 + dy * i as RangePrec - start) / width,
+        })
+        .collect()
+}
+
+/// Label of an x axis tick, `datetime` values are shown as UTC dates like in the graph view.
+pub fn format_x_tick(val: RangePrec, x_type: &str) -> String {
+    match x_type {
+        "datetime" => chrono::DateTime::from_timestamp(val as i64 * 60, 0)
+            .map(|d| d.format("%d.%m. %H:%M UTC").to_string())
+            .unwrap_or_else(|| val.to_string()),
+        _ => val.to_string(),
+    }
+}
+
+/// Label of a y axis tick, with as many decimals as the magnitude of the range needs.
+pub fn format_y_tick(val: RangePrec, y_from: RangePrec, y_to: RangePrec) -> String {
+    let order = (y_to - y_from).abs().log10().floor() - 1.0;
+
+    format!("{:.*}", (-order).max(0.0) as usize, val)
+}
+
+/// Renders the traces of a job into a standalone SVG document, bundles belong to other renderers
+/// and are not drawn.
+#[wasm_bindgen]
+pub fn render_svg(job: RenderJob, width: u32, height: u32) -> Result<String, JsValue> {
+    let mut renderer = SvgRenderer::new(width, height);
+    renderer.render(job)?;
+
+    Ok(renderer.document())
+}
+
+/// Renders a job into a PNG of `width` by `height` CSS pixels at `scale` device pixels each, e.g.
+/// a scale of 3.125 gives 300 DPI. Bundles of other renderers can't be shared, `bundle` has rows
+/// in the stream format of [`RendererContainer::create_bundle_from_stream`] drawn as one more.
+#[wasm_bindgen]
+pub fn export_png(
+    mut job: RenderJob,
+    bundle: &[u8],
+    width: u32,
+    height: u32,
+    scale: f32,
+) -> Result<Vec<u8>, JsValue> {
+    let mut renderer = CpuRenderer::with_scale(
+        (width as f32 * scale).round() as u32,
+        (height as f32 * scale).round() as u32,
+        scale,
+    );
+
+    if !bundle.is_empty() {
+        let rows: Vec<BundleEntry> = bundle
+            .chunks_exact(ROW_LEN)
+            .map(BundleEntry::from_row)
+            .collect();
+        job.add_bundle(renderer.create_bundle(job.x_from, job.x_to, &rows)?);
+    }
+
+    renderer.render(job)?;
+
+    renderer.to_png().map_err(|e| JsValue::from_str(&e))
+}
+
+/// Report of graphs laid out on PDF pages, sizes are in points, e.g. 595 by 842 for A4.
+#[wasm_bindgen]
+pub struct PdfReport {
+    renderer: PdfRenderer,
+}
+
+#[wasm_bindgen]
+impl PdfReport {
+    #[wasm_bindgen(constructor)]
+    pub fn new(page_width: f32, page_height: f32, graph_height: f32) -> Self {
+        Self {
+            renderer: PdfRenderer::new(page_width, page_height, graph_height),
+        }
+    }
+
+    /// Adds a graph of the job traces with its title and a stats table, bundles are not drawn.
+    pub fn add_graph(&mut self, title: &str, job: RenderJob) -> Result<JsValue, JsValue> {
+        self.renderer.set_title(title);
+
+        to_js(&self.renderer.render(job)?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pages(&self) -> usize {
+        self.renderer.page_count()
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.renderer.finish()
+    }
+}
+
 #[wasm_bindgen]
 pub struct RendererContainer {
     renderer: Box<dyn Renderer>,
 }
 
-const ROW_LEN: usize = std::mem::size_of::<u32>() * 2 + 4;
+const ROW_LEN: usize = std::mem::size_of::<u32>() * 2 + 32;
 
 #[wasm_bindgen]
 impl RendererContainer {
@@ -62,7 +215,7 @@ impl RendererContainer {
     }
 
     pub fn render(&mut self, job: RenderJob) -> Result<JsValue, JsValue> {
-        Ok(JsValue::from_serde(&RenderJobResult::from(self.renderer.render(job)?)).unwrap())
+        to_js(&RenderJobResult::from(self.renderer.render(job)?))
     }
 
     pub fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
@@ -78,12 +231,7 @@ impl RendererContainer {
         let mut vec = Vec::with_capacity(stream.len() / ROW_LEN);
 
         for row in stream.chunks_exact(ROW_LEN) {
-            vec.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
+            vec.push(BundleEntry::from_row(row));
         }
 
         self.renderer.create_bundle(from, to, &vec)
@@ -105,21 +253,11 @@ impl RendererContainer {
         }
 
         for row in add.chunks_exact(ROW_LEN) {
-            to_add.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
+            to_add.push(BundleEntry::from_row(row));
         }
 
         for row in modif.chunks_exact(ROW_LEN) {
-            to_mod.push(BundleEntry {
-                handle: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
-                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                color: row[8..11].try_into().unwrap(),
-                points_mode: row[11] > 0,
-            });
+            to_mod.push(BundleEntry::from_row(row));
         }
 
         self.renderer.rebundle(bundle, &to_add, &to_del, &to_mod)
@@ -0,0 +1,583 @@
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression};
+use pdf_writer::{
+    types::{LineCapStyle, LineJoinStyle},
+    Content, Filter, Name, Pdf, Rect, Ref, Str,
+};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    data::{calc_trace_metas, get_trace_ret, DataIdx},
+    structs::{RangePrec, RenderJob},
+};
+
+use super::{
+    bundles::load_points,
+    scene::{rgba, Anchor, Baseline, Color, Layer, LineCap, LineJoin, Primitive, Space},
+    BundleEntry, Bundles, RenderJobResult, Renderer, Scene,
+};
+
+const PAGE_MARGIN: f32 = 36.0;
+const FONT: Name<'static> = Name(b"F1");
+const FONT_SIZE: f32 = 8.0;
+const TITLE_SIZE: f32 = 12.0;
+const TITLE_GAP: f32 = 6.0;
+const ROW_HEIGHT: f32 = 12.0;
+const STAT_COLUMN: f32 = 64.0;
+const GRAPH_GAP: f32 = 24.0;
+const TEXT_COLOR: Color = [0.0, 0.0, 0.0, 1.0];
+
+/// Advance widths of ASCII glyphs in Helvetica, per 1000 units of font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Lays rendered graphs out on PDF pages top to bottom, each with an optional title above it and
+/// a table of trace stats below it. Sizes are in points, the graph spans the page width.
+pub struct PdfRenderer {
+    page_width: f32,
+    page_height: f32,
+    width: f32,
+    height: f32,
+
+    pages: Vec<Content>,
+    cursor: f32,
+    title: Option<String>,
+
+    bundles: Bundles<Vec<(f32, f32)>>,
+}
+
+impl PdfRenderer {
+    pub fn new(page_width: f32, page_height: f32, graph_height: f32) -> Self {
+        Self {
+            page_width,
+            page_height,
+            width: page_width - PAGE_MARGIN * 2.0,
+            height: graph_height,
+
+            pages: vec![],
+            cursor: 0.0,
+            title: None,
+
+            bundles: Bundles::default(),
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Title of the next rendered graph.
+    pub fn set_title(&mut self, title: &str) {
+        self.title = Some(title.to_owned());
+    }
+
+    /// Writes the document, page contents are deflated.
+    pub fn finish(self) -> Vec<u8> {
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let font_id = Ref::new(3);
+        let page_ids: Vec<Ref> = (0..self.pages.len())
+            .map(|i| Ref::new(4 + 2 * i as i32))
+            .collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.type1_font(font_id)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        for (content, &page_id) in self.pages.into_iter().zip(&page_ids) {
+            let content_id = Ref::new(page_id.get() + 1);
+
+            {
+                let mut page = pdf.page(page_id);
+                page.media_box(Rect::new(0.0, 0.0, self.page_width, self.page_height));
+                page.parent(tree_id);
+                page.contents(content_id);
+                page.resources().fonts().pair(FONT, font_id);
+            }
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&content.finish()).unwrap();
+
+            pdf.stream(content_id, &encoder.finish().unwrap())
+                .filter(Filter::FlateDecode);
+        }
+
+        pdf.finish()
+    }
+
+    /// Vertical space a graph with `rows` stats rows takes on a page.
+    fn block_height(&self, rows: usize) -> f32 {
+        let title = if self.title.is_some() {
+            TITLE_SIZE + TITLE_GAP
+        } else {
+            0.0
+        };
+        let table = if rows > 0 {
+            (rows + 1) as f32 * ROW_HEIGHT
+        } else {
+            0.0
+        };
+
+        title + self.height + table + GRAPH_GAP
+    }
+
+    /// Starts a new page unless a block of `height` fits below the cursor.
+    fn reserve(&mut self, height: f32) {
+        let top = self.page_height - PAGE_MARGIN;
+
+        if self.pages.is_empty() || (self.cursor - height < PAGE_MARGIN && self.cursor < top) {
+            self.pages.push(Content::new());
+            self.cursor = top;
+        }
+    }
+
+    /// Adds the title above the graph, moving the cursor below it.
+    fn add_title(&mut self, scene: &mut Scene) {
+        if let Some(title) = self.title.take() {
+            scene.push(
+                Layer::Labels,
+                Space::Screen,
+                Primitive::Text {
+                    pos: (0.0, self.height + TITLE_GAP + TITLE_SIZE),
+                    text: title,
+                    size: TITLE_SIZE,
+                    anchor: Anchor::Start,
+                    baseline: Baseline::Top,
+                    color: TEXT_COLOR,
+                },
+            );
+
+            self.cursor -= TITLE_SIZE + TITLE_GAP;
+        }
+    }
+
+    /// Table of avg, min and max over the job range below the graph, one row per trace.
+    fn add_stats(&self, scene: &mut Scene, job: &RenderJob, traces: &[(DataIdx, [u8; 3])]) {
+        if traces.is_empty() {
+            return;
+        }
+
+        let name_width = self.width - 3.0 * STAT_COLUMN - FONT_SIZE * 2.0;
+        let text = |pos: (f32, f32), text: String, anchor: Anchor| Primitive::Text {
+            pos,
+            text,
+            size: FONT_SIZE,
+            anchor,
+            baseline: Baseline::Top,
+            color: TEXT_COLOR,
+        };
+        let value_x = |i: usize| self.width - (2 - i) as f32 * STAT_COLUMN;
+
+        let mut cells = vec![text(
+            (FONT_SIZE * 1.5, FONT_SIZE - ROW_HEIGHT),
+            "Trace".to_owned(),
+            Anchor::Start,
+        )];
+
+        for (i, header) in ["Avg", "Min", "Max"].iter().enumerate() {
+            cells.push(text(
+                (value_x(i), FONT_SIZE - ROW_HEIGHT),
+                header.to_string(),
+                Anchor::End,
+            ));
+        }
+
+        for (row, &(handle, color)) in traces.iter().enumerate() {
+            let metas = calc_trace_metas(handle, job.x_from, job.x_to);
+            let name = get_trace_ret(handle, |t| t.id.clone());
+            let y = -((row + 2) as f32) * ROW_HEIGHT;
+
+            cells.push(Primitive::Rect {
+                from: (0.0, y),
+                to: (FONT_SIZE, y + FONT_SIZE * 0.75),
+                color: rgba(color),
+            });
+            cells.push(text(
+                (FONT_SIZE * 1.5, y + FONT_SIZE),
+                truncate(&name, FONT_SIZE, name_width),
+                Anchor::Start,
+            ));
+
+            for (i, val) in [metas.avg, metas.min, metas.max].iter().enumerate() {
+                cells.push(text(
+                    (value_x(i), y + FONT_SIZE),
+                    format_stat(*val),
+                    Anchor::End,
+                ));
+            }
+        }
+
+        for cell in cells {
+            scene.push(Layer::Labels, Space::Screen, cell);
+        }
+    }
+
+    /// Draws a scene with its bottom left corner `bottom` points above the page bottom.
+    fn draw(&mut self, scene: &Scene, bottom: f32) {
+        let plot = scene.plot;
+        let (left, plot_bottom) = (PAGE_MARGIN + plot.left, bottom + plot.bottom);
+
+        let project = |space: Space, p: (f32, f32), offset: f32| match space {
+            Space::Screen => (PAGE_MARGIN + p.0, bottom + p.1),
+            Space::Plot => {
+                let (x, y) = plot.normalize(p, offset);
+                (left + x * plot.width, plot_bottom + y * plot.height)
+            }
+        };
+
+        let content = self.pages.last_mut().unwrap();
+        let mut clipped = false;
+
+        for command in &scene.commands {
+            let space = command.space;
+
+            if clipped != (space == Space::Plot) {
+                clipped = !clipped;
+
+                if clipped {
+                    content
+                        .save_state()
+                        .rect(left, plot_bottom, plot.width, plot.height)
+                        .clip_nonzero()
+                        .end_path();
+                } else {
+                    content.restore_state();
+                }
+            }
+
+            match &command.primitive {
+                Primitive::Polyline {
+                    vertices,
+                    stroke,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&self.bundles, &plot);
+
+                    if !points.iter().any(|&p| finite(p)) {
+                        continue;
+                    }
+
+                    let (r, g, b) = over_white(*color);
+                    content
+                        .set_stroke_rgb(r, g, b)
+                        .set_line_width(stroke.width)
+                        .set_line_join(match stroke.join {
+                            LineJoin::Miter => LineJoinStyle::MiterJoin,
+                            LineJoin::Round => LineJoinStyle::RoundJoin,
+                            LineJoin::Bevel => LineJoinStyle::BevelJoin,
+                        })
+                        .set_miter_limit(stroke.miter_limit)
+                        .set_line_cap(line_cap(stroke.cap))
+                        .set_dash_pattern(stroke.dash_lengths(), 0.0);
+
+                    // Non-finite points are gaps, each finite run is its own subpath
+                    for run in points.split(|&p| !finite(p)) {
+                        for (i, &p) in run.iter().enumerate() {
+                            let (x, y) = project(space, p, offset);
+
+                            if i == 0 {
+                                content.move_to(x, y);
+                            } else {
+                                content.line_to(x, y);
+                            }
+                        }
+                    }
+
+                    content.stroke();
+                }
+                Primitive::Lines {
+                    segments,
+                    stroke,
+                    color,
+                } => {
+                    let (r, g, b) = over_white(*color);
+                    content
+                        .set_stroke_rgb(r, g, b)
+                        .set_line_width(stroke.width)
+                        .set_line_cap(line_cap(stroke.cap))
+                        .set_dash_pattern(stroke.dash_lengths(), 0.0);
+
+                    for [from, to] in segments {
+                        let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                        content.move_to(from.0, from.1).line_to(to.0, to.1);
+                    }
+
+                    content.stroke();
+                }
+                Primitive::Markers {
+                    vertices,
+                    size,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&self.bundles, &plot);
+                    let (r, g, b) = over_white(*color);
+                    content.set_fill_rgb(r, g, b);
+
+                    for &p in points.iter().filter(|&&p| finite(p)) {
+                        let (x, y) = project(space, p, offset);
+                        content.rect(x - size / 2.0, y - size / 2.0, *size, *size);
+                    }
+
+                    content.fill_nonzero();
+                }
+                Primitive::Band {
+                    upper,
+                    lower,
+                    color,
+                } => {
+                    let outline: Vec<(f32, f32)> = upper
+                        .iter()
+                        .chain(lower.iter().rev())
+                        .copied()
+                        .filter(|&p| finite(p))
+                        .collect();
+
+                    if outline.is_empty() {
+                        continue;
+                    }
+
+                    let (r, g, b) = over_white(*color);
+                    content.set_fill_rgb(r, g, b);
+
+                    for (i, &p) in outline.iter().enumerate() {
+                        let (x, y) = project(space, p, 0.0);
+
+                        if i == 0 {
+                            content.move_to(x, y);
+                        } else {
+                            content.line_to(x, y);
+                        }
+                    }
+
+                    content.close_path().fill_nonzero();
+                }
+                Primitive::Rect { from, to, color } => {
+                    let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                    let (r, g, b) = over_white(*color);
+
+                    content
+                        .set_fill_rgb(r, g, b)
+                        .rect(from.0, from.1, to.0 - from.0, to.1 - from.1)
+                        .fill_nonzero();
+                }
+                Primitive::Rects { rects, .. } if rects.is_empty() => {}
+                Primitive::Rects { rects, color } => {
+                    let (r, g, b) = over_white(*color);
+                    content.set_fill_rgb(r, g, b);
+
+                    for [from, to] in rects {
+                        let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                        content.rect(from.0, from.1, to.0 - from.0, to.1 - from.1);
+                    }
+
+                    content.fill_nonzero();
+                }
+                Primitive::Text {
+                    pos,
+                    text,
+                    size,
+                    anchor,
+                    baseline,
+                    color,
+                } => {
+                    let (r, g, b) = over_white(*color);
+                    content.set_fill_rgb(r, g, b);
+                    write_text(
+                        content,
+                        project(space, *pos, 0.0),
+                        *size,
+                        text,
+                        *anchor,
+                        *baseline,
+                    );
+                }
+            }
+        }
+
+        if clipped {
+            content.restore_state();
+        }
+    }
+}
+
+impl Renderer for PdfRenderer {
+    /// Adds the graph below the previous one, `job.clear` has no effect.
+    fn render(&mut self, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        let mut stats: Vec<(DataIdx, [u8; 3])> = vec![];
+        for bundle in job
+            .get_bundles()
+            .iter()
+            .filter_map(|&b| self.bundles.get(b))
+        {
+            stats.extend(
+                bundle
+                    .rows
+                    .iter()
+                    .filter(|r| !job.is_blacklisted(r.style.handle))
+                    .map(|r| (r.style.handle, r.style.color)),
+            );
+        }
+        stats.extend(job.get_traces().iter().map(|t| (t.idx, t.color)));
+
+        self.reserve(self.block_height(stats.len()));
+
+        let mut scene = Scene::compile(&job, self.width, self.height, &self.bundles);
+        self.add_title(&mut scene);
+        self.add_stats(&mut scene, &job, &stats);
+
+        let bottom = self.cursor - self.height;
+        self.draw(&scene, bottom);
+
+        self.cursor = bottom - GRAPH_GAP;
+        if !stats.is_empty() {
+            self.cursor -= (stats.len() + 1) as f32 * ROW_HEIGHT;
+        }
+
+        Ok(scene.into_result())
+    }
+
+    /// Sets the page width and the graph height, graphs stay within the page margins.
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.page_width = width as f32;
+        self.width = self.page_width - PAGE_MARGIN * 2.0;
+        self.height = height as f32;
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+    ) -> Result<usize, JsValue> {
+        self.bundles.create(from, to, data, |from, to, row| {
+            Ok(load_points(from, to, row))
+        })
+    }
+
+    fn rebundle(
+        &mut self,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles.rebundle(
+            bundle,
+            to_add,
+            to_del,
+            to_mod,
+            |from, to, row| -> Result<_, JsValue> { Ok(load_points(from, to, row)) },
+        )?;
+
+        Ok(())
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+}
+
+/// Text in the WinAnsi encoding of the standard fonts, other characters become `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7e | 0xa0..=0xff => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn text_width(text: &[u8], size: f32) -> f32 {
+    text.iter()
+        .map(|&c| match c {
+            0x20..=0x7e => HELVETICA_WIDTHS[(c - 0x20) as usize],
+            _ => 556,
+        } as f32)
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+fn write_text(
+    content: &mut Content,
+    pos: (f32, f32),
+    size: f32,
+    text: &str,
+    anchor: Anchor,
+    baseline: Baseline,
+) {
+    let text = encode(text);
+    let x = match anchor {
+        Anchor::Start => pos.0,
+        Anchor::Middle => pos.0 - text_width(&text, size) / 2.0,
+        Anchor::End => pos.0 - text_width(&text, size),
+    };
+    let y = match baseline {
+        Baseline::Top => pos.1 - size,
+        Baseline::Middle => pos.1 - size * 0.35,
+    };
+
+    content
+        .begin_text()
+        .set_font(FONT, size)
+        .next_line(x, y)
+        .show(Str(&text))
+        .end_text();
+}
+
+/// Points with a NaN or infinite coordinate can't be written to the content stream.
+fn finite(p: (f32, f32)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
+}
+
+fn line_cap(cap: LineCap) -> LineCapStyle {
+    match cap {
+        LineCap::Butt => LineCapStyle::ButtCap,
+        LineCap::Round => LineCapStyle::RoundCap,
+        LineCap::Square => LineCapStyle::ProjectingSquareCap,
+    }
+}
+
+/// Color over a white page, opacity would need an extended graphics state.
+fn over_white(color: Color) -> (f32, f32, f32) {
+    let blend = |c: f32| c * color[3] + 1.0 - color[3];
+    (blend(color[0]), blend(color[1]), blend(color[2]))
+}
+
+/// Shortens `text` with an ellipsis to fit into `width`.
+fn truncate(text: &str, size: f32, width: f32) -> String {
+    if text_width(&encode(text), size) <= width {
+        return text.to_owned();
+    }
+
+    let mut text: String = text.to_owned();
+    while !text.is_empty() && text_width(&encode(&text), size) > width - text_width(b"...", size) {
+        text.pop();
+    }
+
+    text + "..."
+}
+
+/// Stats of traces without data in the range are shown as a dash.
+fn format_stat(val: RangePrec) -> String {
+    if val.is_finite() && val.abs() < RangePrec::MAX {
+        format!("{:.2}", val)
+    } else {
+        "-".to_owned()
+    }
+}
@@ -0,0 +1,974 @@
+use std::{collections::HashMap, convert::TryInto};
+
+use crate::{
+    data::{get_trace_ret, DataIdx},
+    stats,
+    structs::{RangePrec, RenderJob},
+};
+
+use super::{calc_ticks, format_x_tick, format_y_tick, AxisTick, Bundles, RenderJobResult};
+
+/// Straight alpha RGBA with channels in `0..=1`.
+pub type Color = [f32; 4];
+
+pub const AXES_COLOR: Color = [0.3, 0.3, 0.3, 1.0];
+pub const GRID_COLOR: Color = [0.35, 0.35, 0.35, 0.65];
+pub const TICK_LEN: f32 = 4.0;
+pub const POINT_SIZE: f32 = 4.0;
+pub const HIGHLIGHT_SIZE: f32 = 8.0;
+pub const FONT_SIZE: f32 = 10.0;
+/// Same as the SVG and canvas default.
+pub const DEFAULT_MITER_LIMIT: f32 = 4.0;
+const LABEL_GAP: f32 = 2.0;
+
+pub fn rgba(color: [u8; 3]) -> Color {
+    [
+        color[0] as f32 / 255.0,
+        color[1] as f32 / 255.0,
+        color[2] as f32 / 255.0,
+        1.0,
+    ]
+}
+
+/// Coordinates a command is given in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Space {
+    /// CSS pixels with the origin at the bottom left of the canvas.
+    Screen,
+    /// Data coordinates with x relative to the start of the plot, clipped to the plot area.
+    Plot,
+}
+
+/// What part of the graph a command draws, backends may style or skip them by it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layer {
+    Axes,
+    Labels,
+    Grid,
+    Traces,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Baseline {
+    /// The text hangs below its position.
+    Top,
+    Middle,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    pub fn parse(join: &str) -> Result<Self, String> {
+        match join {
+            "miter" => Ok(LineJoin::Miter),
+            "round" => Ok(LineJoin::Round),
+            "bevel" => Ok(LineJoin::Bevel),
+            _ => Err(format!("Unknown line join '{}'", join)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineCap {
+    Butt,
+    Round,
+    /// Extends the line by half its width.
+    Square,
+}
+
+impl LineCap {
+    pub fn parse(cap: &str) -> Result<Self, String> {
+        match cap {
+            "butt" => Ok(LineCap::Butt),
+            "round" => Ok(LineCap::Round),
+            "square" => Ok(LineCap::Square),
+            _ => Err(format!("Unknown line cap '{}'", cap)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// How a trace line gets from one sample to the next.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineShape {
+    Linear,
+    /// Vertical at the previous sample, each value holds since the sample before it.
+    StepBefore,
+    /// Vertical at the next sample, each value holds until the sample after it.
+    StepAfter,
+    /// Vertical halfway between the samples.
+    StepMid,
+}
+
+impl LineShape {
+    pub fn parse(shape: &str) -> Result<Self, String> {
+        match shape {
+            "linear" => Ok(LineShape::Linear),
+            "step-before" => Ok(LineShape::StepBefore),
+            "step-after" => Ok(LineShape::StepAfter),
+            "step-mid" => Ok(LineShape::StepMid),
+            _ => Err(format!("Unknown line shape '{}'", shape)),
+        }
+    }
+
+    /// Byte of trace and bundle rows: 0 linear, 1 step before, 2 step after, 3 step mid. Unknown
+    /// bytes are linear.
+    pub fn from_byte(shape: u8) -> Self {
+        match shape {
+            1 => LineShape::StepBefore,
+            2 => LineShape::StepAfter,
+            3 => LineShape::StepMid,
+            _ => LineShape::Linear,
+        }
+    }
+
+    /// Samples with the corners of the steps between them, non-finite points still break the
+    /// line.
+    pub fn apply(self, samples: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+        if self == LineShape::Linear {
+            return samples;
+        }
+
+        let mut out = Vec::with_capacity(samples.len() * 3);
+        let mut prev: Option<(f32, f32)> = None;
+
+        for p in samples {
+            let finite = p.0.is_finite() && p.1.is_finite();
+
+            if let (Some(q), true) = (prev, finite) {
+                match self {
+                    LineShape::Linear => {}
+                    LineShape::StepBefore => out.push((q.0, p.1)),
+                    LineShape::StepAfter => out.push((p.0, q.1)),
+                    LineShape::StepMid => {
+                        let mid = (q.0 + p.0) / 2.0;
+                        out.push((mid, q.1));
+                        out.push((mid, p.1));
+                    }
+                }
+            }
+
+            out.push(p);
+            prev = if finite { Some(p) } else { None };
+        }
+
+        out
+    }
+}
+
+/// Alternating dash and gap lengths in CSS pixels up to the first zero, all zeros is solid.
+pub type DashPattern = [u8; 4];
+
+pub const SOLID: DashPattern = [0; 4];
+
+/// Pattern of up to four lengths, the rest is cut off.
+pub fn dash_pattern(lengths: &[u8]) -> DashPattern {
+    let mut dash = SOLID;
+    let n = lengths.len().min(dash.len());
+    dash[..n].copy_from_slice(&lengths[..n]);
+    dash
+}
+
+/// What the area of a trace is filled to, `alpha` is the opacity of the fill.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Fill {
+    None,
+    /// Down or up to a constant value.
+    Baseline {
+        value: RangePrec,
+        alpha: u8,
+    },
+    /// To another trace, on the timestamps both of them have.
+    Trace {
+        handle: DataIdx,
+        alpha: u8,
+    },
+}
+
+impl Fill {
+    /// Mode (0 none, 1 baseline, 2 trace), alpha, two reserved bytes and the baseline as a big
+    /// endian `f32` or the trace handle as a big endian `u32`. Unknown modes are no fill.
+    pub fn from_row(row: &[u8]) -> Self {
+        let target: [u8; 4] = row[4..8].try_into().unwrap();
+
+        match row[0] {
+            1 => Fill::Baseline {
+                value: f32::from_be_bytes(target) as RangePrec,
+                alpha: row[1],
+            },
+            2 => Fill::Trace {
+                handle: u32::from_be_bytes(target) as DataIdx,
+                alpha: row[1],
+            },
+            _ => Fill::None,
+        }
+    }
+}
+
+/// How bars of the traces in one group share the samples they have at the same x.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BarLayout {
+    /// Each bar spans the whole width, later traces cover earlier ones.
+    Overlap,
+    /// Side by side, splitting the width in the order of the traces.
+    Grouped,
+    /// On top of each other, on the timestamps all of them have.
+    Stacked,
+}
+
+/// Draws a trace as bars centered on its samples instead of a line.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bars {
+    pub layout: BarLayout,
+    /// Traces with the same layout and group are laid out together.
+    pub group: u8,
+    /// Width of the bars at one sample in x units.
+    pub width: RangePrec,
+    /// Value the bars grow from.
+    pub baseline: RangePrec,
+}
+
+impl Bars {
+    /// Layout (0 lines, 1 overlap, 2 grouped, 3 stacked), group, two reserved bytes, then the
+    /// width and baseline as big endian `f32`. Unknown layouts are drawn as lines.
+    pub fn from_row(row: &[u8]) -> Option<Self> {
+        let layout = match row[0] {
+            1 => BarLayout::Overlap,
+            2 => BarLayout::Grouped,
+            3 => BarLayout::Stacked,
+            _ => return None,
+        };
+
+        Some(Self {
+            layout,
+            group: row[1],
+            width: f32::from_be_bytes(row[4..8].try_into().unwrap()) as RangePrec,
+            baseline: f32::from_be_bytes(row[8..12].try_into().unwrap()) as RangePrec,
+        })
+    }
+}
+
+/// How a line is stroked, `width` is in CSS pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Longest miter as a multiple of the width, sharper corners are beveled.
+    pub miter_limit: f32,
+    pub dash: DashPattern,
+}
+
+impl Stroke {
+    /// Thin line for axes and grid, where joins never occur.
+    pub fn plain(width: f32) -> Self {
+        Self {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: DEFAULT_MITER_LIMIT,
+            dash: SOLID,
+        }
+    }
+
+    /// Dash and gap lengths, empty when solid. An odd count repeats as in SVG.
+    pub fn dash_lengths(&self) -> Vec<f32> {
+        let mut lengths: Vec<f32> = self
+            .dash
+            .iter()
+            .take_while(|&&l| l > 0)
+            .map(|&l| l as f32)
+            .collect();
+
+        if lengths.len() % 2 == 1 {
+            lengths.extend(lengths.clone());
+        }
+
+        lengths
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Vertices {
+    Owned(Vec<(f32, f32)>),
+    /// Points a backend keeps in a bundle row, with x relative to the bundle start.
+    Bundled {
+        bundle: usize,
+        row: usize,
+    },
+}
+
+impl Vertices {
+    /// Points of in-memory bundles with the x offset that maps them to the start of the plot.
+    pub fn resolve<'a>(
+        &'a self,
+        bundles: &'a Bundles<Vec<(f32, f32)>>,
+        plot: &PlotArea,
+    ) -> (&'a [(f32, f32)], f32) {
+        match self {
+            Vertices::Owned(points) => (points, 0.0),
+            Vertices::Bundled { bundle, row } => {
+                let bundle = bundles.get(*bundle).unwrap();
+                (&bundle.rows[*row].data, plot.bundle_offset(bundle.from))
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Primitive {
+    /// Connected line segments.
+    Polyline {
+        vertices: Vertices,
+        stroke: Stroke,
+        color: Color,
+    },
+    /// Separate line segments.
+    Lines {
+        segments: Vec<[(f32, f32); 2]>,
+        stroke: Stroke,
+        color: Color,
+    },
+    /// Square markers centered at the vertices, `size` is in CSS pixels.
+    Markers {
+        vertices: Vertices,
+        size: f32,
+        color: Color,
+    },
+    /// Filled area between two polylines sampled at the same x coordinates.
+    Band {
+        upper: Vec<(f32, f32)>,
+        lower: Vec<(f32, f32)>,
+        color: Color,
+    },
+    /// Filled rectangle between two corners.
+    Rect {
+        from: (f32, f32),
+        to: (f32, f32),
+        color: Color,
+    },
+    /// Filled rectangles of one color between pairs of corners.
+    Rects {
+        rects: Vec<[(f32, f32); 2]>,
+        color: Color,
+    },
+    Text {
+        pos: (f32, f32),
+        text: String,
+        size: f32,
+        anchor: Anchor,
+        baseline: Baseline,
+        color: Color,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Command {
+    pub layer: Layer,
+    pub space: Space,
+    pub primitive: Primitive,
+}
+
+/// Plot area in CSS pixels from the bottom left of the canvas and the data range it shows.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PlotArea {
+    pub left: f32,
+    pub bottom: f32,
+    pub width: f32,
+    pub height: f32,
+
+    pub x_from: RangePrec,
+    pub y_from: f32,
+    pub x_size: f32,
+    pub y_size: f32,
+}
+
+impl PlotArea {
+    /// Offset of the x coordinates of a bundle starting at `from` in plot space.
+    pub fn bundle_offset(&self, from: RangePrec) -> f32 {
+        (self.x_from - from) as f32
+    }
+
+    /// Position of a plot point as a fraction of the plot area, from its bottom left corner.
+    pub fn normalize(&self, p: (f32, f32), offset: f32) -> (f32, f32) {
+        (
+            (p.0 - offset) / self.x_size,
+            (p.1 - self.y_from) / self.y_size,
+        )
+    }
+
+    /// Position of a plot point in CSS pixels from the bottom left of the canvas.
+    pub fn to_screen(&self, p: (f32, f32), offset: f32) -> (f32, f32) {
+        let (x, y) = self.normalize(p, offset);
+        (self.left + x * self.width, self.bottom + y * self.height)
+    }
+}
+
+/// Backend independent list of what a render job draws, in drawing order.
+pub struct Scene {
+    pub clear: bool,
+    pub width: f32,
+    pub height: f32,
+    pub plot: PlotArea,
+
+    pub x_ticks: Box<[AxisTick]>,
+    pub y_ticks: Box<[AxisTick]>,
+    pub commands: Vec<Command>,
+}
+
+impl Scene {
+    /// Lays a job out on a `width` by `height` canvas in CSS pixels and fetches its traces.
+    pub fn compile<T>(job: &RenderJob, width: f32, height: f32, bundles: &Bundles<T>) -> Self {
+        let plot = PlotArea {
+            left: (job.margin + job.y_label_space) as f32,
+            bottom: (job.margin + job.x_label_space) as f32,
+            width: width - (job.margin * 2 + job.y_label_space) as f32,
+            height: height - (job.margin * 2 + job.x_label_space) as f32,
+
+            x_from: job.x_from,
+            y_from: job.y_from as f32,
+            x_size: (job.x_to - job.x_from) as f32,
+            y_size: (job.y_to - job.y_from) as f32,
+        };
+
+        let mut scene = Self {
+            clear: job.clear,
+            width,
+            height,
+            plot,
+
+            x_ticks: calc_ticks(job.x_from, job.x_to - job.x_from),
+            y_ticks: calc_ticks(job.y_from, job.y_to - job.y_from),
+            commands: vec![],
+        };
+
+        if job.render_axes {
+            scene.add_axes(job);
+        }
+
+        if job.render_grid {
+            scene.add_grid(job);
+        }
+
+        // Stacked traces are only drawn within their groups
+        let stacked: Vec<DataIdx> = job
+            .get_stack_groups()
+            .iter()
+            .flat_map(|g| g.handles.iter().copied())
+            .collect();
+        let mut stack_styles = HashMap::new();
+        let mut bars = vec![];
+        let mut traces = vec![];
+
+        for &handle in job.get_bundles() {
+            let bundle = match bundles.get(handle) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            for (i, row) in bundle.rows.iter().enumerate() {
+                let style = &row.style;
+
+                if job.is_blacklisted(style.handle) {
+                    continue;
+                }
+
+                let stroke = job.stroke(style.width, style.dash);
+
+                if stacked.contains(&style.handle) {
+                    stack_styles.insert(style.handle, (stroke, style.color, style.points_mode));
+                    continue;
+                }
+
+                if let Some(b) = style.bars {
+                    bars.push((style.handle, b, style.color));
+                    continue;
+                }
+
+                // Bundles keep the corners of steps, markers only go on the samples
+                let line = Vertices::Bundled {
+                    bundle: handle,
+                    row: i,
+                };
+                let markers = match (style.points_mode, style.shape) {
+                    (false, _) => None,
+                    (true, LineShape::Linear) => Some(line.clone()),
+                    (true, _) => Some(Vertices::Owned(load_vertices(job, style.handle))),
+                };
+                traces.push((
+                    style.handle,
+                    line,
+                    markers,
+                    stroke,
+                    style.color,
+                    style.fill,
+                    style.shape,
+                ));
+            }
+        }
+
+        for trace in job.get_traces() {
+            let stroke = job.stroke(trace.width, trace.dash);
+
+            if stacked.contains(&trace.idx) {
+                stack_styles.insert(trace.idx, (stroke, trace.color, trace.points_mode));
+                continue;
+            }
+
+            if let Some(b) = trace.bars {
+                bars.push((trace.idx, b, trace.color));
+                continue;
+            }
+
+            let points = load_vertices(job, trace.idx);
+            let markers = if trace.points_mode {
+                Some(Vertices::Owned(points.clone()))
+            } else {
+                None
+            };
+            traces.push((
+                trace.idx,
+                Vertices::Owned(trace.shape.apply(points)),
+                markers,
+                stroke,
+                trace.color,
+                trace.fill,
+                trace.shape,
+            ));
+        }
+
+        scene.add_stacks(job, &stack_styles);
+        scene.add_bars(job, &bars);
+
+        for (handle, line, markers, stroke, color, fill, shape) in traces {
+            scene.add_fill(job, handle, fill, color, shape);
+            scene.add_trace(line, markers, stroke, color);
+        }
+
+        scene.add_highlights(job);
+
+        scene
+    }
+
+    pub fn push(&mut self, layer: Layer, space: Space, primitive: Primitive) {
+        self.commands.push(Command {
+            layer,
+            space,
+            primitive,
+        });
+    }
+
+    pub fn into_result(self) -> RenderJobResult {
+        RenderJobResult {
+            x_ticks: self.x_ticks,
+            y_ticks: self.y_ticks,
+        }
+    }
+
+    /// Axis lines just outside of the plot area, with tick marks and labels pointing away from it.
+    fn add_axes(&mut self, job: &RenderJob) {
+        let p = self.plot;
+        let (right, top) = (p.left + p.width, p.bottom + p.height);
+        let x_pos = |tick: &AxisTick| p.left + tick.pos as f32 * p.width;
+        let y_pos = |tick: &AxisTick| p.bottom + tick.pos as f32 * p.height;
+
+        for (from, to) in [
+            ((p.left - 1.0, p.bottom - 1.0), (p.left, top)),
+            ((p.left - 1.0, p.bottom - 1.0), (right, p.bottom)),
+        ] {
+            self.push(
+                Layer::Axes,
+                Space::Screen,
+                Primitive::Rect {
+                    from,
+                    to,
+                    color: AXES_COLOR,
+                },
+            );
+        }
+
+        let segments = self
+            .x_ticks
+            .iter()
+            .map(|t| [(x_pos(t), p.bottom), (x_pos(t), p.bottom - TICK_LEN)])
+            .chain(
+                self.y_ticks
+                    .iter()
+                    .map(|t| [(p.left, y_pos(t)), (p.left - TICK_LEN, y_pos(t))]),
+            )
+            .collect();
+
+        self.push(
+            Layer::Axes,
+            Space::Screen,
+            Primitive::Lines {
+                segments,
+                stroke: Stroke::plain(1.0),
+                color: AXES_COLOR,
+            },
+        );
+
+        let mut labels = vec![];
+
+        for tick in self.x_ticks.iter() {
+            labels.push(Primitive::Text {
+                pos: (x_pos(tick), p.bottom - TICK_LEN),
+                text: format_x_tick(tick.val, job.get_x_type()),
+                size: FONT_SIZE,
+                anchor: Anchor::Middle,
+                baseline: Baseline::Top,
+                color: AXES_COLOR,
+            });
+        }
+
+        for tick in self.y_ticks.iter() {
+            labels.push(Primitive::Text {
+                pos: (p.left - TICK_LEN - LABEL_GAP, y_pos(tick)),
+                text: format_y_tick(tick.val, job.y_from, job.y_to),
+                size: FONT_SIZE,
+                anchor: Anchor::End,
+                baseline: Baseline::Middle,
+                color: AXES_COLOR,
+            });
+        }
+
+        for label in labels {
+            self.push(Layer::Labels, Space::Screen, label);
+        }
+    }
+
+    /// Markers on the traces at the highlighted x values within the job range.
+    fn add_highlights(&mut self, job: &RenderJob) {
+        for highlight in job.get_highlights() {
+            let vertices: Vec<(f32, f32)> = get_trace_ret(highlight.handle, |t| {
+                highlight
+                    .xs
+                    .iter()
+                    .filter(|&&x| x >= job.x_from && x < job.x_to)
+                    .filter_map(|&x| {
+                        let y = t.get_data_at_with(x, t.interpolation)?;
+                        Some(((x - job.x_from) as f32, y as f32))
+                    })
+                    .collect()
+            });
+
+            if vertices.is_empty() {
+                continue;
+            }
+
+            self.push(
+                Layer::Traces,
+                Space::Plot,
+                Primitive::Markers {
+                    vertices: Vertices::Owned(vertices),
+                    size: HIGHLIGHT_SIZE,
+                    color: rgba(highlight.color),
+                },
+            );
+        }
+    }
+
+    fn add_grid(&mut self, job: &RenderJob) {
+        let (y_from, y_to) = (job.y_from as f32, job.y_to as f32);
+        let x_to = self.plot.x_size;
+        let x = |tick: &AxisTick| (tick.val - job.x_from) as f32;
+
+        let segments = self
+            .x_ticks
+            .iter()
+            .map(|t| [(x(t), y_from), (x(t), y_to)])
+            .chain(
+                self.y_ticks
+                    .iter()
+                    .map(|t| [(0.0, t.val as f32), (x_to, t.val as f32)]),
+            )
+            .collect();
+
+        self.push(
+            Layer::Grid,
+            Space::Plot,
+            Primitive::Lines {
+                segments,
+                stroke: Stroke::plain(1.0),
+                color: GRID_COLOR,
+            },
+        );
+    }
+
+    /// Bands of every stack group in order, topped by the lines of their traces.
+    fn add_stacks(&mut self, job: &RenderJob, styles: &HashMap<DataIdx, (Stroke, [u8; 3], bool)>) {
+        for group in job.get_stack_groups() {
+            let members: Vec<DataIdx> = group
+                .handles
+                .iter()
+                .copied()
+                .filter(|h| styles.contains_key(h))
+                .collect();
+
+            let series: Vec<_> = members.iter().map(|&h| load_samples(job, h)).collect();
+            let (xs, ys) = stats::align(&series);
+
+            let mut lower = vec![0.0; xs.len()];
+            let mut lines = vec![];
+
+            for (handle, ys) in members.iter().zip(ys) {
+                let upper: Vec<RangePrec> = lower.iter().zip(ys).map(|(l, y)| l + y).collect();
+                let (stroke, color, points_mode) = styles[handle];
+
+                self.push(
+                    Layer::Traces,
+                    Space::Plot,
+                    Primitive::Band {
+                        upper: to_plot(job, &xs, &upper),
+                        lower: to_plot(job, &xs, &lower),
+                        color: with_alpha(color, group.alpha),
+                    },
+                );
+
+                lines.push((to_plot(job, &xs, &upper), stroke, color, points_mode));
+                lower = upper;
+            }
+
+            for (points, stroke, color, points_mode) in lines {
+                let markers = if points_mode {
+                    Some(Vertices::Owned(points.clone()))
+                } else {
+                    None
+                };
+                self.add_trace(Vertices::Owned(points), markers, stroke, color);
+            }
+        }
+    }
+
+    /// Bars of the traces grouped by layout and group, in the order of their first traces.
+    fn add_bars(&mut self, job: &RenderJob, traces: &[(DataIdx, Bars, [u8; 3])]) {
+        let mut groups: Vec<Vec<&(DataIdx, Bars, [u8; 3])>> = vec![];
+
+        for trace in traces {
+            let key = (trace.1.layout, trace.1.group);
+
+            match groups
+                .iter_mut()
+                .find(|g| (g[0].1.layout, g[0].1.group) == key)
+            {
+                Some(group) => group.push(trace),
+                None => groups.push(vec![trace]),
+            }
+        }
+
+        for group in groups {
+            // Grouped and stacked bars share the width of the first trace, stacks its baseline
+            let first = group[0].1;
+            let half = first.width / 2.0;
+            let series: Vec<_> = group
+                .iter()
+                .map(|t| load_samples_around(job, t.0, t.1.width.max(first.width) / 2.0))
+                .collect();
+
+            let rects: Vec<Vec<[(RangePrec, RangePrec); 2]>> = match first.layout {
+                BarLayout::Overlap => group
+                    .iter()
+                    .zip(&series)
+                    .map(|(t, samples)| {
+                        let (half, base) = (t.1.width / 2.0, t.1.baseline);
+                        samples
+                            .iter()
+                            .map(|&(x, y)| [(x - half, base), (x + half, y)])
+                            .collect()
+                    })
+                    .collect(),
+                BarLayout::Grouped => {
+                    let slot = first.width / group.len() as RangePrec;
+
+                    group
+                        .iter()
+                        .zip(&series)
+                        .enumerate()
+                        .map(|(i, (t, samples))| {
+                            let left = i as RangePrec * slot - half;
+                            samples
+                                .iter()
+                                .map(|&(x, y)| [(x + left, t.1.baseline), (x + left + slot, y)])
+                                .collect()
+                        })
+                        .collect()
+                }
+                BarLayout::Stacked => {
+                    let (xs, ys) = stats::align(&series);
+                    let mut lower = vec![first.baseline; xs.len()];
+
+                    ys.into_iter()
+                        .map(|ys| {
+                            let mut bars = Vec::with_capacity(xs.len());
+
+                            for ((&x, l), y) in xs.iter().zip(lower.iter_mut()).zip(ys) {
+                                bars.push([(x - half, *l), (x + half, *l + y)]);
+                                *l += y;
+                            }
+
+                            bars
+                        })
+                        .collect()
+                }
+            };
+
+            let plot = |(x, y): (RangePrec, RangePrec)| ((x - job.x_from) as f32, y as f32);
+
+            for (trace, rects) in group.iter().zip(rects) {
+                self.push(
+                    Layer::Traces,
+                    Space::Plot,
+                    Primitive::Rects {
+                        rects: rects.iter().map(|r| [plot(r[0]), plot(r[1])]).collect(),
+                        color: rgba(trace.2),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Band under a trace, with the same steps as its line. It is split where either edge has no
+    /// value, so both edges of each part get the same corners.
+    fn add_fill(
+        &mut self,
+        job: &RenderJob,
+        handle: DataIdx,
+        fill: Fill,
+        color: [u8; 3],
+        shape: LineShape,
+    ) {
+        let (xs, upper, lower, alpha) = match fill {
+            Fill::None => return,
+            Fill::Baseline { value, alpha } => {
+                let (xs, ys): (Vec<_>, Vec<_>) = load_samples(job, handle).into_iter().unzip();
+                let lower = vec![value; xs.len()];
+                (xs, ys, lower, alpha)
+            }
+            Fill::Trace { handle: to, alpha } => {
+                let (xs, mut ys) =
+                    stats::align(&[load_samples(job, handle), load_samples(job, to)]);
+                let lower = ys.pop().unwrap();
+                (xs, ys.pop().unwrap(), lower, alpha)
+            }
+        };
+
+        let color = with_alpha(color, alpha);
+        let indices: Vec<usize> = (0..xs.len()).collect();
+
+        for run in indices.split(|&i| !(upper[i].is_finite() && lower[i].is_finite())) {
+            if run.is_empty() {
+                continue;
+            }
+
+            let (from, to) = (run[0], run[run.len() - 1] + 1);
+
+            self.push(
+                Layer::Traces,
+                Space::Plot,
+                Primitive::Band {
+                    upper: shape.apply(to_plot(job, &xs[from..to], &upper[from..to])),
+                    lower: shape.apply(to_plot(job, &xs[from..to], &lower[from..to])),
+                    color,
+                },
+            );
+        }
+    }
+
+    fn add_trace(
+        &mut self,
+        line: Vertices,
+        markers: Option<Vertices>,
+        stroke: Stroke,
+        color: [u8; 3],
+    ) {
+        let color = rgba(color);
+
+        self.push(
+            Layer::Traces,
+            Space::Plot,
+            Primitive::Polyline {
+                vertices: line,
+                stroke,
+                color,
+            },
+        );
+
+        if let Some(vertices) = markers {
+            self.push(
+                Layer::Traces,
+                Space::Plot,
+                Primitive::Markers {
+                    vertices,
+                    size: POINT_SIZE,
+                    color,
+                },
+            );
+        }
+    }
+}
+
+fn with_alpha(color: [u8; 3], alpha: u8) -> Color {
+    let mut color = rgba(color);
+    color[3] = alpha as f32 / 255.0;
+    color
+}
+
+/// Points of a trace in the range of a job, with x relative to its start.
+fn load_vertices(job: &RenderJob, handle: DataIdx) -> Vec<(f32, f32)> {
+    get_trace_ret(handle, |t| {
+        t.get_data_with_origin(job.x_from, job.x_to, job.x_from, 0.0)
+            .collect()
+    })
+}
+
+/// Samples of a trace in the range of a job.
+fn load_samples(job: &RenderJob, handle: DataIdx) -> Vec<(RangePrec, RangePrec)> {
+    load_samples_around(job, handle, 0.0)
+}
+
+/// Samples of a trace in the range of a job widened by `margin` on both sides.
+fn load_samples_around(
+    job: &RenderJob,
+    handle: DataIdx,
+    margin: RangePrec,
+) -> Vec<(RangePrec, RangePrec)> {
+    get_trace_ret(handle, |t| {
+        t.get_data_high_prec(job.x_from - margin, job.x_to + margin)
+            .collect()
+    })
+}
+
+/// Aligned samples in plot space.
+fn to_plot(job: &RenderJob, xs: &[RangePrec], ys: &[RangePrec]) -> Vec<(f32, f32)> {
+    xs.iter()
+        .zip(ys)
+        .map(|(&x, &y)| ((x - job.x_from) as f32, y as f32))
+        .collect()
+}
@@ -0,0 +1,274 @@
+use std::f32::consts::PI;
+
+use super::scene::{LineCap, LineJoin, Stroke};
+
+/// Pixels a stroke is widened by on each side so that its edges can fade out.
+pub const FRINGE: f32 = 1.0;
+/// Floats per vertex: position, distance across the line from its center and distance past
+/// the end of a butt or square cap.
+pub const VERTEX_LEN: usize = 4;
+
+/// Cap distance of vertices away from line ends, fully covered.
+const INSIDE: f32 = -FRINGE;
+/// Largest angle spanned by one triangle of a round join or cap.
+const ROUND_STEP: f32 = PI / 8.0;
+
+type Point = (f32, f32);
+
+/// Appends a triangle list covering a polyline given in pixels, see [`VERTEX_LEN`].
+///
+/// A fragment is covered by `clamp(width / 2 + 0.5 - abs(across), 0, 1) * clamp(0.5 - past, 0, 1)`
+/// of its interpolated distances. Non-finite points split the line, the inner sides of joins
+/// overlap.
+pub fn tessellate(points: &[Point], stroke: &Stroke, out: &mut Vec<f32>) {
+    let half = stroke.width / 2.0;
+    let mut tess = Tessellator {
+        stroke,
+        half,
+        edge: half + FRINGE,
+        out,
+    };
+
+    for run in points.split(|p| !p.0.is_finite() || !p.1.is_finite()) {
+        let mut line: Vec<Point> = Vec::with_capacity(run.len() + 1);
+
+        for &p in run {
+            match line.last() {
+                Some(&last) if length(sub(p, last)) < 1e-3 => {}
+                _ => line.push(p),
+            }
+        }
+
+        // Each end segment carries one cap, so a lone segment is split in two
+        if line.len() == 2 {
+            line.insert(1, mul(add(line[0], line[1]), 0.5));
+        }
+
+        if line.len() > 2 {
+            tess.polyline(&line);
+        }
+    }
+}
+
+/// Splits a polyline in pixels into its dashes, see [`Stroke::dash_lengths`]. The pattern runs
+/// on over vertices and breaks, so its phase is continuous along the whole line.
+pub fn dashes(points: &[Point], pattern: &[f32]) -> Vec<Vec<Point>> {
+    if pattern.is_empty() {
+        return vec![points.to_vec()];
+    }
+
+    let mut out = vec![];
+    let mut dash: Vec<Point> = vec![];
+    // Position in the pattern, even indices are dashes
+    let (mut index, mut left) = (0, pattern[0]);
+    let mut prev: Option<Point> = None;
+
+    for &p in points {
+        if !p.0.is_finite() || !p.1.is_finite() {
+            if dash.len() > 1 {
+                out.push(std::mem::take(&mut dash));
+            } else {
+                dash.clear();
+            }
+            prev = None;
+            continue;
+        }
+
+        if let Some(mut from) = prev {
+            let mut len = length(sub(p, from));
+
+            while len >= left {
+                let cut = add(from, mul(sub(p, from), left / len));
+                dash.push(cut);
+
+                if index % 2 == 0 {
+                    out.push(std::mem::take(&mut dash));
+                }
+
+                len -= left;
+                from = cut;
+                index = (index + 1) % pattern.len();
+                left = pattern[index];
+            }
+
+            left -= len;
+        }
+
+        if index % 2 == 0 && dash.last() != Some(&p) {
+            dash.push(p);
+        }
+        prev = Some(p);
+    }
+
+    if dash.len() > 1 {
+        out.push(dash);
+    }
+
+    out
+}
+
+struct Tessellator<'a> {
+    stroke: &'a Stroke,
+    half: f32,
+    /// Distance of the outline from the center, including the fringe.
+    edge: f32,
+    out: &'a mut Vec<f32>,
+}
+
+impl Tessellator<'_> {
+    fn polyline(&mut self, line: &[Point]) {
+        let last = line.len() - 2;
+        let extent = match self.stroke.cap {
+            LineCap::Butt => Some(FRINGE),
+            LineCap::Square => Some(self.half + FRINGE),
+            LineCap::Round => None,
+        };
+
+        for i in 0..=last {
+            let (mut from, mut to) = (line[i], line[i + 1]);
+            let dir = normalize(sub(to, from));
+            let normal = (-dir.1, dir.0);
+
+            let mut capped = (false, false);
+
+            if i == 0 {
+                match extent {
+                    Some(e) => {
+                        from = sub(from, mul(dir, e));
+                        capped.0 = true;
+                    }
+                    None => self.fan(from, normal, PI),
+                }
+            }
+
+            if i == last {
+                match extent {
+                    Some(e) => {
+                        to = add(to, mul(dir, e));
+                        capped.1 = true;
+                    }
+                    None => self.fan(to, mul(normal, -1.0), PI),
+                }
+            }
+
+            // Distance past the cap is linear along the segment
+            let len = length(sub(to, from));
+            let past = match capped {
+                (true, _) => (FRINGE, FRINGE - len),
+                (_, true) => (FRINGE - len, FRINGE),
+                _ => (INSIDE, INSIDE),
+            };
+
+            let side = mul(normal, self.edge);
+            let (e, ne) = (self.edge, -self.edge);
+            self.vertex(add(from, side), e, past.0);
+            self.vertex(sub(from, side), ne, past.0);
+            self.vertex(add(to, side), e, past.1);
+            self.vertex(sub(from, side), ne, past.0);
+            self.vertex(sub(to, side), ne, past.1);
+            self.vertex(add(to, side), e, past.1);
+
+            if i < last {
+                self.join(line[i + 1], dir, normalize(sub(line[i + 2], line[i + 1])));
+            }
+        }
+    }
+
+    /// Fills the gap on the outer side of a corner between two directions.
+    fn join(&mut self, p: Point, d1: Point, d2: Point) {
+        let turn = cross(d1, d2);
+        let cos = dot(d1, d2);
+
+        if turn.abs() < 1e-6 && cos > 0.0 {
+            return;
+        }
+
+        // Turning left opens the corner on the right
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let o1 = mul((-d1.1, d1.0), side);
+        let o2 = mul((-d2.1, d2.0), side);
+
+        match self.stroke.join {
+            LineJoin::Round => {
+                // Sweeps through the direction of the first segment, also when it reverses
+                let angle = cos.clamp(-1.0, 1.0).acos();
+                self.fan(p, o1, -side * angle);
+            }
+            LineJoin::Miter => {
+                let bisector = add(o1, o2);
+                let half_cos = dot(normalize(bisector), o1);
+
+                if length(bisector) < 1e-6 || 1.0 / half_cos > self.stroke.miter_limit {
+                    self.bevel(p, o1, o2);
+                } else {
+                    let tip = add(p, mul(normalize(bisector), self.edge / half_cos));
+                    let (a, b) = (add(p, mul(o1, self.edge)), add(p, mul(o2, self.edge)));
+
+                    self.triangle([(p, 0.0), (a, self.edge), (tip, self.edge)]);
+                    self.triangle([(p, 0.0), (tip, self.edge), (b, self.edge)]);
+                }
+            }
+            LineJoin::Bevel => self.bevel(p, o1, o2),
+        }
+    }
+
+    fn bevel(&mut self, p: Point, o1: Point, o2: Point) {
+        let (a, b) = (add(p, mul(o1, self.edge)), add(p, mul(o2, self.edge)));
+        self.triangle([(p, 0.0), (a, self.edge), (b, self.edge)]);
+    }
+
+    /// Circular sector around `center` from the unit vector `from`, counterclockwise by `angle`.
+    fn fan(&mut self, center: Point, from: Point, angle: f32) {
+        let steps = (angle.abs() / ROUND_STEP).ceil().max(1.0) as usize;
+        let edge = self.edge;
+        let rim = |i: usize| {
+            let (sin, cos) = (angle * i as f32 / steps as f32).sin_cos();
+            let v = (from.0 * cos - from.1 * sin, from.0 * sin + from.1 * cos);
+            add(center, mul(v, edge))
+        };
+
+        for i in 0..steps {
+            let (a, b) = (rim(i), rim(i + 1));
+            self.triangle([(center, 0.0), (a, edge), (b, edge)]);
+        }
+    }
+
+    /// Triangle of join or cap vertices with their distances across the line.
+    fn triangle(&mut self, vertices: [(Point, f32); 3]) {
+        for (p, across) in vertices {
+            self.vertex(p, across, INSIDE);
+        }
+    }
+
+    fn vertex(&mut self, p: Point, across: f32, past: f32) {
+        self.out.extend_from_slice(&[p.0, p.1, across, past]);
+    }
+}
+
+fn add(a: Point, b: Point) -> Point {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn mul(a: Point, k: f32) -> Point {
+    (a.0 * k, a.1 * k)
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn length(a: Point) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Point) -> Point {
+    mul(a, 1.0 / length(a))
+}
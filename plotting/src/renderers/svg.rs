@@ -0,0 +1,406 @@
+use std::fmt::Write;
+
+use wasm_bindgen::JsValue;
+
+use crate::{
+    data::DataIdx,
+    structs::{RangePrec, RenderJob},
+};
+
+use super::{
+    bundles::load_points,
+    scene::{
+        Anchor, Baseline, Color, Layer, LineCap, LineJoin, Primitive, Space, Stroke,
+        DEFAULT_MITER_LIMIT,
+    },
+    BundleEntry, Bundles, RenderJobResult, Renderer, Scene,
+};
+
+/// Graph area of a job in SVG user units, with the origin at the top left.
+#[derive(Clone, Copy)]
+struct Area {
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Renders into an SVG document, with a `<path>` per trace.
+pub struct SvgRenderer {
+    width: u32,
+    height: u32,
+    body: String,
+
+    bundles: Bundles<Vec<(f32, f32)>>,
+}
+
+impl SvgRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+
+            bundles: Bundles::default(),
+        }
+    }
+
+    /// The whole document with everything rendered since the last clear.
+    pub fn document(&self) -> String {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{}</svg>"#,
+            self.body,
+            w = self.width,
+            h = self.height,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        self.body.clear();
+    }
+
+    fn draw(&mut self, scene: &Scene) {
+        let plot = scene.plot;
+        let area = Area {
+            left: plot.left,
+            top: scene.height - plot.bottom - plot.height,
+            width: plot.width,
+            height: plot.height,
+        };
+
+        let project = |space: Space, p: (f32, f32), offset: f32| match space {
+            Space::Screen => (p.0, scene.height - p.1),
+            Space::Plot => {
+                let (x, y) = plot.normalize(p, offset);
+                (
+                    area.left + x * area.width,
+                    area.top + area.height - y * area.height,
+                )
+            }
+        };
+
+        // Every render gets its own clip path, since the document may hold several of them
+        let clip = format!("graph-{}", self.body.len());
+        write!(
+            self.body,
+            r#"<clipPath id="{}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+            clip, area.left, area.top, area.width, area.height
+        )
+        .unwrap();
+
+        let mut clipped = false;
+
+        for command in &scene.commands {
+            let space = command.space;
+
+            if clipped != (space == Space::Plot) {
+                clipped = !clipped;
+
+                if clipped {
+                    write!(self.body, r#"<g clip-path="url(#{})">"#, clip).unwrap();
+                } else {
+                    self.body.push_str("</g>");
+                }
+            }
+
+            let class = match (command.layer, &command.primitive) {
+                (Layer::Axes, _) => r#" class="axes""#,
+                (Layer::Labels, _) => r#" class="ticks""#,
+                (Layer::Grid, _) => r#" class="grid""#,
+                (Layer::Traces, Primitive::Markers { .. }) => r#" class="markers""#,
+                (Layer::Traces, Primitive::Band { .. }) => r#" class="area""#,
+                (Layer::Traces, Primitive::Rects { .. }) => r#" class="bars""#,
+                (Layer::Traces, _) => "",
+            };
+
+            match &command.primitive {
+                Primitive::Polyline {
+                    vertices,
+                    stroke,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&self.bundles, &plot);
+                    let mut d = String::new();
+                    let mut start = true;
+
+                    // Non-finite points are gaps, the line picks up again in a new subpath
+                    for &p in points {
+                        if !finite(p) {
+                            start = true;
+                            continue;
+                        }
+                        let (x, y) = project(space, p, offset);
+                        write!(d, "{}{:.2} {:.2}", if start { 'M' } else { 'L' }, x, y).unwrap();
+                        start = false;
+                    }
+
+                    write!(
+                        self.body,
+                        r#"<path{} d="{}" fill="none"{}{}/>"#,
+                        class,
+                        d,
+                        paint("stroke", *color),
+                        outline(stroke, true)
+                    )
+                    .unwrap();
+                }
+                Primitive::Lines {
+                    segments,
+                    stroke,
+                    color,
+                } => {
+                    let mut d = String::new();
+
+                    for [from, to] in segments {
+                        let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                        write!(d, "M{:.2} {:.2}L{:.2} {:.2}", from.0, from.1, to.0, to.1).unwrap();
+                    }
+
+                    write!(
+                        self.body,
+                        r#"<path{} d="{}" fill="none"{}{}/>"#,
+                        class,
+                        d,
+                        paint("stroke", *color),
+                        outline(stroke, false)
+                    )
+                    .unwrap();
+                }
+                Primitive::Markers {
+                    vertices,
+                    size,
+                    color,
+                } => {
+                    let (points, offset) = vertices.resolve(&self.bundles, &plot);
+                    let mut d = String::new();
+
+                    for &p in points.iter().filter(|&&p| finite(p)) {
+                        let (x, y) = project(space, p, offset);
+                        write!(
+                            d,
+                            "M{:.2} {:.2}h{s}v{s}h-{s}z",
+                            x - size / 2.0,
+                            y - size / 2.0,
+                            s = size
+                        )
+                        .unwrap();
+                    }
+
+                    write!(
+                        self.body,
+                        r#"<path{} d="{}"{}/>"#,
+                        class,
+                        d,
+                        paint("fill", *color)
+                    )
+                    .unwrap();
+                }
+                Primitive::Band {
+                    upper,
+                    lower,
+                    color,
+                } => {
+                    let mut d = String::new();
+
+                    let outline = upper.iter().chain(lower.iter().rev());
+                    for (i, &p) in outline.filter(|&&p| finite(p)).enumerate() {
+                        let (x, y) = project(space, p, 0.0);
+                        write!(d, "{}{:.2} {:.2}", if i == 0 { 'M' } else { 'L' }, x, y).unwrap();
+                    }
+
+                    if !d.is_empty() {
+                        write!(
+                            self.body,
+                            r#"<path{} d="{}z"{}/>"#,
+                            class,
+                            d,
+                            paint("fill", *color)
+                        )
+                        .unwrap();
+                    }
+                }
+                Primitive::Rect { from, to, color } => {
+                    let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+
+                    write!(
+                        self.body,
+                        r#"<rect{} x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"{}/>"#,
+                        class,
+                        from.0.min(to.0),
+                        from.1.min(to.1),
+                        (to.0 - from.0).abs(),
+                        (to.1 - from.1).abs(),
+                        paint("fill", *color)
+                    )
+                    .unwrap();
+                }
+                Primitive::Rects { rects, color } => {
+                    let mut d = String::new();
+
+                    for [from, to] in rects {
+                        let (from, to) = (project(space, *from, 0.0), project(space, *to, 0.0));
+                        write!(
+                            d,
+                            "M{:.2} {:.2}H{:.2}V{:.2}H{:.2}z",
+                            from.0, from.1, to.0, to.1, from.0
+                        )
+                        .unwrap();
+                    }
+
+                    if !d.is_empty() {
+                        write!(
+                            self.body,
+                            r#"<path{} d="{}"{}/>"#,
+                            class,
+                            d,
+                            paint("fill", *color)
+                        )
+                        .unwrap();
+                    }
+                }
+                Primitive::Text {
+                    pos,
+                    text,
+                    size,
+                    anchor,
+                    baseline,
+                    color,
+                } => {
+                    let (x, y) = project(space, *pos, 0.0);
+                    let (y, baseline) = match baseline {
+                        Baseline::Top => (y + size, ""),
+                        Baseline::Middle => (y, r#" dominant-baseline="middle""#),
+                    };
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+
+                    write!(
+                        self.body,
+                        r#"<text{} x="{:.2}" y="{:.2}" font-family="sans-serif" font-size="{}"{} text-anchor="{}"{}>{}</text>"#,
+                        class,
+                        x,
+                        y,
+                        size,
+                        paint("fill", *color),
+                        anchor,
+                        baseline,
+                        text
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        if clipped {
+            self.body.push_str("</g>");
+        }
+    }
+}
+
+/// Points with a NaN or infinite coordinate can't be written to a path.
+fn finite(p: (f32, f32)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
+}
+
+/// `fill` or `stroke` attributes of a color, with an opacity if it is translucent.
+fn paint(attr: &str, color: Color) -> String {
+    let channel = |c: f32| (c * 255.0).round() as u8;
+    let mut out = format!(
+        r#" {}="rgb({},{},{})""#,
+        attr,
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2])
+    );
+
+    if color[3] < 1.0 {
+        write!(out, r#" {}-opacity="{}""#, attr, color[3]).unwrap();
+    }
+
+    out
+}
+
+/// Stroke width, dash, cap and, for connected lines, join attributes that differ from the
+/// defaults.
+fn outline(stroke: &Stroke, joined: bool) -> String {
+    let mut out = format!(r#" stroke-width="{}""#, stroke.width);
+
+    if joined {
+        write!(out, r#" stroke-linejoin="{}""#, stroke.join.name()).unwrap();
+
+        if stroke.join == LineJoin::Miter && stroke.miter_limit != DEFAULT_MITER_LIMIT {
+            write!(out, r#" stroke-miterlimit="{}""#, stroke.miter_limit).unwrap();
+        }
+    }
+
+    let dash = stroke.dash_lengths();
+    if !dash.is_empty() {
+        let lengths: Vec<String> = dash.iter().map(|l| l.to_string()).collect();
+        write!(out, r#" stroke-dasharray="{}""#, lengths.join(" ")).unwrap();
+    }
+
+    if stroke.cap != LineCap::Butt {
+        write!(out, r#" stroke-linecap="{}""#, stroke.cap.name()).unwrap();
+    }
+
+    out
+}
+
+impl Renderer for SvgRenderer {
+    fn render(&mut self, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        let scene = Scene::compile(&job, self.width as f32, self.height as f32, &self.bundles);
+
+        if scene.clear {
+            self.clear();
+        }
+
+        self.draw(&scene);
+
+        Ok(scene.into_result())
+    }
+
+    fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
+    fn create_bundle(
+        &mut self,
+        from: RangePrec,
+        to: RangePrec,
+        data: &[BundleEntry],
+    ) -> Result<usize, JsValue> {
+        self.bundles.create(from, to, data, |from, to, row| {
+            Ok(load_points(from, to, row))
+        })
+    }
+
+    fn rebundle(
+        &mut self,
+        bundle: usize,
+        to_add: &[BundleEntry],
+        to_del: &[DataIdx],
+        to_mod: &[BundleEntry],
+    ) -> Result<(), JsValue> {
+        self.bundles.rebundle(
+            bundle,
+            to_add,
+            to_del,
+            to_mod,
+            |from, to, row| -> Result<_, JsValue> { Ok(load_points(from, to, row)) },
+        )?;
+
+        Ok(())
+    }
+
+    fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
+        self.bundles.dispose(bundle);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
 
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{
@@ -8,24 +8,31 @@ use web_sys::{
 use crate::{
     data::DataIdx,
     structs::{RangePrec, RenderJob},
+    utils::to_js,
 };
 
-use super::{AxisTick, RenderJobResult, Renderer};
+use super::{
+    bundles::load_points,
+    scene::{Color, Primitive, Space, Stroke, Vertices},
+    stroke, BundleEntry, Bundles, RenderJobResult, Renderer, Scene,
+};
 use serde::{Deserialize, Serialize};
 
-struct BufferEntry {
-    points: i32,
-    handle: DataIdx,
+/// Points of a bundle row uploaded to the GPU, with its stroke once it was drawn.
+struct GlRow {
+    /// Plot space points relative to the bundle start, kept to tessellate the stroke again.
+    points: Vec<(f32, f32)>,
     buffer: WebGlBuffer,
-    width: f32,
-    color: [f32; 3],
-    points_mode: bool,
+    stroke: RefCell<Option<GlStroke>>,
 }
 
-struct BufferBundle {
-    from: RangePrec,
-    to: RangePrec,
-    buffers: Vec<BufferEntry>,
+/// Stroke triangles in pixels relative to the bundle start and `y = 0`. Panning only moves
+/// them, they are tessellated again when the scale or the stroke changes.
+struct GlStroke {
+    scale: (f32, f32),
+    stroke: Stroke,
+    vertices: i32,
+    buffer: WebGlBuffer,
 }
 
 pub struct WebGlRenderer {
@@ -46,8 +53,15 @@ pub struct WebGlRenderer {
     ap_color_pos: WebGlUniformLocation,
     axes_program: WebGlProgram,
 
-    bundles_counter: usize,
-    bundles: HashMap<usize, BufferBundle>,
+    sp_position: u32,
+    sp_edge: u32,
+    sp_resolution_pos: WebGlUniformLocation,
+    sp_color_pos: WebGlUniformLocation,
+    sp_half_width_pos: WebGlUniformLocation,
+    sp_translation_pos: WebGlUniformLocation,
+    stroke_program: WebGlProgram,
+
+    bundles: Bundles<GlRow>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -59,7 +73,7 @@ impl WebGlRenderer {
     pub fn new(elem: OffscreenCanvas) -> Result<Self, JsValue> {
         crate::utils::set_panic_hook();
 
-        let opts = JsValue::from_serde(&ContextOpts { antialias: true }).unwrap();
+        let opts = to_js(&ContextOpts { antialias: true }).unwrap();
 
         let context = elem
             .get_context_with_context_options("webgl2", &opts)
@@ -130,6 +144,56 @@ impl WebGlRenderer {
             webgl_utils::link_program(&context, &vert_shader, &frag_shader)?
         };
 
+        let stroke_program = {
+            let vert_shader = webgl_utils::compile_shader(
+                &context,
+                WebGl2RenderingContext::VERTEX_SHADER,
+                r#"
+                attribute vec2 aVertexPosition;
+                attribute vec2 aEdge;
+
+                uniform vec2 resolution;
+                uniform vec2 translation;
+
+                varying vec2 vEdge;
+
+                void main() {
+                    vEdge = aEdge;
+                    gl_Position = vec4(vec2(-1, -1) + vec2(2, 2) * (aVertexPosition + translation) / resolution, 0, 1);
+                }
+                "#,
+            )?;
+
+            // Coverage by the distances across the line and past its caps, see `stroke::tessellate`
+            let frag_shader = webgl_utils::compile_shader(
+                &context,
+                WebGl2RenderingContext::FRAGMENT_SHADER,
+                r#"
+                precision mediump float;
+                uniform vec4 color;
+                uniform float halfWidth;
+
+                varying vec2 vEdge;
+
+                void main() {
+                    float coverage = clamp(halfWidth + 0.5 - abs(vEdge.x), 0.0, 1.0) * clamp(0.5 - vEdge.y, 0.0, 1.0);
+                    gl_FragColor = vec4(color.rgb, color.a * coverage);
+                }
+                "#,
+            )?;
+
+            webgl_utils::link_program(&context, &vert_shader, &frag_shader)?
+        };
+
+        // Antialiased edges are blended, keeping the canvas premultiplied
+        context.enable(WebGl2RenderingContext::BLEND);
+        context.blend_func_separate(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
         Ok(WebGlRenderer {
             width: elem.width(),
             height: elem.height(),
@@ -149,11 +213,26 @@ impl WebGlRenderer {
                 .unwrap(),
             axes_program,
 
+            sp_position: context.get_attrib_location(&stroke_program, "aVertexPosition") as u32,
+            sp_edge: context.get_attrib_location(&stroke_program, "aEdge") as u32,
+            sp_resolution_pos: context
+                .get_uniform_location(&stroke_program, "resolution")
+                .unwrap(),
+            sp_color_pos: context
+                .get_uniform_location(&stroke_program, "color")
+                .unwrap(),
+            sp_half_width_pos: context
+                .get_uniform_location(&stroke_program, "halfWidth")
+                .unwrap(),
+            sp_translation_pos: context
+                .get_uniform_location(&stroke_program, "translation")
+                .unwrap(),
+            stroke_program,
+
             trace_buffer: context.create_buffer().unwrap(),
             context,
 
-            bundles_counter: 0,
-            bundles: HashMap::new(),
+            bundles: Bundles::default(),
         })
     }
 
@@ -162,40 +241,17 @@ impl WebGlRenderer {
         self.context.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
     }
 
-    pub fn render_axes(&self, job: &RenderJob, x_ticks: &[AxisTick], y_ticks: &[AxisTick]) {
-        let gl = &self.context;
-
-        gl.viewport(0, 0, self.width as i32, self.height as i32);
-
-        gl.use_program(Some(&self.axes_program));
-        gl.uniform2f(
-            Some(&self.ap_resolution_pos),
-            self.width as f32,
-            self.height as f32,
-        );
-        gl.uniform4f(Some(&self.ap_color_pos), 0.3, 0.3, 0.3, 1.0);
-        gl.bind_buffer(
-            WebGl2RenderingContext::ARRAY_BUFFER,
-            Some(&self.trace_buffer),
-        );
-        gl.line_width(2.0);
+    /// Uploads vertices into the shared trace buffer and binds it.
+    fn upload(&self, data: &[f32]) {
+        WebGlRenderer::upload_to(&self.context, &self.trace_buffer, data);
+    }
 
-        let graph_left = (job.y_label_space + job.margin) as f32;
-        let graph_bottom = (job.x_label_space + job.margin) as f32;
-        let graph_top = (self.height - job.margin) as f32;
-        let graph_right = (self.width - job.margin) as f32;
+    /// Uploads vertices into a buffer and binds it.
+    fn upload_to(gl: &WebGl2RenderingContext, buffer: &WebGlBuffer, data: &[f32]) {
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
 
         unsafe {
-            let data: Vec<f32> = vec![
-                graph_left - 1.0,
-                graph_top,
-                graph_left - 1.0,
-                graph_bottom - 1.0,
-                graph_right,
-                graph_bottom - 1.0,
-            ];
-
-            let vert_array = js_sys::Float32Array::view(&data);
+            let vert_array = js_sys::Float32Array::view(data);
 
             gl.buffer_data_with_array_buffer_view(
                 WebGl2RenderingContext::ARRAY_BUFFER,
@@ -203,279 +259,396 @@ impl WebGlRenderer {
                 WebGl2RenderingContext::STATIC_DRAW,
             );
         }
+    }
+
+    /// Sets up the viewport, program and uniforms for drawing the bound buffer in a space.
+    fn prepare(&self, scene: &Scene, space: Space, offset: f32, color: Color) {
+        let gl = &self.context;
+        let plot = &scene.plot;
 
         gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
         gl.enable_vertex_attrib_array(0);
-        gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, 3);
 
-        const TICK_LEN: f32 = 4.0;
-        let points = (x_ticks.len() + y_ticks.len()) * 2;
+        match space {
+            Space::Screen => {
+                gl.viewport(0, 0, self.width as i32, self.height as i32);
 
-        fn lerp(from: f32, to: f32, val: f32) -> f32 {
-            from + (to - from) * val
-        }
-
-        unsafe {
-            let mut data: Vec<f32> = Vec::with_capacity(2 * points);
-
-            for tick in x_ticks {
-                data.push(lerp(graph_left, graph_right, tick.pos as f32));
-                data.push(graph_bottom);
-                data.push(lerp(graph_left, graph_right, tick.pos as f32));
-                data.push(graph_bottom - TICK_LEN);
+                gl.use_program(Some(&self.axes_program));
+                gl.uniform2f(
+                    Some(&self.ap_resolution_pos),
+                    self.width as f32,
+                    self.height as f32,
+                );
+                gl.uniform4f(
+                    Some(&self.ap_color_pos),
+                    color[0],
+                    color[1],
+                    color[2],
+                    color[3],
+                );
             }
+            Space::Plot => {
+                gl.viewport(
+                    plot.left as i32,
+                    plot.bottom as i32,
+                    plot.width as i32,
+                    plot.height as i32,
+                );
 
-            for tick in y_ticks {
-                data.push(graph_left);
-                data.push(lerp(graph_bottom, graph_top, tick.pos as f32));
-                data.push(graph_left - TICK_LEN);
-                data.push(lerp(graph_bottom, graph_top, tick.pos as f32));
+                gl.use_program(Some(&self.trace_program));
+                gl.uniform2f(Some(&self.tp_origin_pos), offset, plot.y_from);
+                gl.uniform2f(Some(&self.tp_size_pos), plot.x_size, plot.y_size);
+                gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
+                gl.uniform4f(
+                    Some(&self.tp_color_pos),
+                    color[0],
+                    color[1],
+                    color[2],
+                    color[3],
+                );
             }
-
-            let vert_array = js_sys::Float32Array::view(&data);
-
-            gl.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
         }
-
-        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, points as i32);
     }
 
-    pub fn render_grid(&self, job: &RenderJob, x_ticks: &[AxisTick], y_ticks: &[AxisTick]) {
-        let gl = &self.context;
-
-        let x_from = job.x_from as f32;
-        let x_to = job.x_to as f32;
-        let y_from = job.y_from as f32;
-        let y_to = job.y_to as f32;
-
-        gl.viewport(
-            (job.margin + job.y_label_space) as i32,
-            (job.margin + job.x_label_space) as i32,
-            (self.width - job.margin * 2 - job.y_label_space) as i32,
-            (self.height - job.margin * 2 - job.x_label_space) as i32,
-        );
-
-        gl.use_program(Some(&self.trace_program));
-        gl.uniform2f(Some(&self.tp_origin_pos), x_from, y_from);
-        gl.uniform2f(Some(&self.tp_size_pos), x_to - x_from, y_to - y_from);
-        gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
-        gl.uniform4f(Some(&self.tp_color_pos), 0.35, 0.35, 0.35, 0.65);
-        gl.line_width(1.0);
-
-        gl.bind_buffer(
-            WebGl2RenderingContext::ARRAY_BUFFER,
-            Some(&self.trace_buffer),
-        );
-        let points = (x_ticks.len() + y_ticks.len()) * 2;
-
-        unsafe {
-            let mut data: Vec<f32> = Vec::with_capacity(2 * points);
-
-            for tick in x_ticks {
-                data.push(tick.val as f32);
-                data.push(y_from);
-                data.push(tick.val as f32);
-                data.push(y_to);
-            }
-
-            for tick in y_ticks {
-                data.push(x_from);
-                data.push(tick.val as f32);
-                data.push(x_to);
-                data.push(tick.val as f32);
-            }
-
-            let vert_array = js_sys::Float32Array::view(&data);
-
-            gl.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
+    /// Clips to the plot area for commands in plot space that are drawn in screen coordinates.
+    fn clip(&self, scene: &Scene, space: Space) {
+        let plot = &scene.plot;
+
+        if space == Space::Plot {
+            self.context.enable(WebGl2RenderingContext::SCISSOR_TEST);
+            self.context.scissor(
+                plot.left as i32,
+                plot.bottom as i32,
+                plot.width as i32,
+                plot.height as i32,
             );
         }
+    }
 
-        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(0);
-        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, points as i32);
+    /// Draws triangles from `stroke::tessellate`, clipped to the plot area in plot space.
+    fn draw_stroke(
+        &self,
+        scene: &Scene,
+        space: Space,
+        data: &[f32],
+        stroke: &Stroke,
+        color: Color,
+    ) {
+        self.upload(data);
+        self.draw_bound_stroke(
+            scene,
+            space,
+            (0.0, 0.0),
+            (data.len() / stroke::VERTEX_LEN) as i32,
+            stroke,
+            color,
+        );
     }
 
-    fn allocate_bundle_entry(
-        context: &WebGl2RenderingContext,
+    /// Draws the stroke of a bundle row from its buffer, tessellating it first if the scale or
+    /// the stroke changed since it was last drawn.
+    fn draw_bundled_stroke(
+        &self,
+        scene: &Scene,
         from: RangePrec,
-        to: RangePrec,
-        entry: &super::BundleEntry,
-    ) -> Result<BufferEntry, JsValue> {
-        let buffer =
-            match context.create_buffer() {
-                Some(b) => b,
-                _ => return Result::Err(JsValue::from_str(
-                    "Failed to allocate a buffer, perhaps the WebGL context has been destroyed.",
-                )),
-            };
-
-        context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        let points;
+        row: &GlRow,
+        stroke: &Stroke,
+        color: Color,
+    ) -> Result<(), JsValue> {
+        let plot = &scene.plot;
+        let scale = (plot.width / plot.x_size, plot.height / plot.y_size);
+        let mut cached = row.stroke.borrow_mut();
+
+        if !matches!(&*cached, Some(s) if s.scale == scale && s.stroke == *stroke) {
+            let pixels: Vec<(f32, f32)> = row
+                .points
+                .iter()
+                .map(|p| (p.0 * scale.0, p.1 * scale.1))
+                .collect();
+            let mut data = vec![];
+
+            for dash in stroke::dashes(&pixels, &stroke.dash_lengths()) {
+                stroke::tessellate(&dash, stroke, &mut data);
+            }
 
-        unsafe {
-            use std::iter::once;
+            let buffer = match cached.take() {
+                Some(s) => s.buffer,
+                None => WebGlRenderer::create_buffer(&self.context)?,
+            };
+            WebGlRenderer::upload_to(&self.context, &buffer, &data);
 
-            let data: Vec<f32> = crate::data::get_trace_ret(entry.handle, |t| {
-                t.get_data_with_origin(from, to, from, 0.0)
-                    .flat_map(|p| once(p.0).chain(once(p.1)))
-                    .collect()
+            *cached = Some(GlStroke {
+                scale,
+                stroke: *stroke,
+                vertices: (data.len() / stroke::VERTEX_LEN) as i32,
+                buffer,
             });
+        }
 
-            let vert_array = js_sys::Float32Array::view(&data);
-
-            points = data.len() as i32 / 2;
+        let cached = cached.as_ref().unwrap();
+        let translation = (
+            plot.left - plot.bundle_offset(from) * scale.0,
+            plot.bottom - plot.y_from * scale.1,
+        );
 
-            context.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &vert_array,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
-        }
+        self.context
+            .bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&cached.buffer));
+        self.draw_bound_stroke(
+            scene,
+            Space::Plot,
+            translation,
+            cached.vertices,
+            stroke,
+            color,
+        );
 
-        Ok(BufferEntry {
-            points,
-            handle: entry.handle,
-            buffer,
-            width: entry.width as f32,
-            color: [
-                entry.color[0] as f32 / 255.0,
-                entry.color[1] as f32 / 255.0,
-                entry.color[2] as f32 / 255.0,
-            ],
-            points_mode: entry.points_mode,
-        })
+        Ok(())
     }
-}
 
-impl Renderer for WebGlRenderer {
-    fn render(&mut self, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+    /// Draws stroke triangles from the bound buffer moved by `translation` pixels.
+    fn draw_bound_stroke(
+        &self,
+        scene: &Scene,
+        space: Space,
+        translation: (f32, f32),
+        vertices: i32,
+        stroke: &Stroke,
+        color: Color,
+    ) {
         let gl = &self.context;
+        let stride = (stroke::VERTEX_LEN * std::mem::size_of::<f32>()) as i32;
+
+        gl.vertex_attrib_pointer_with_i32(
+            self.sp_position,
+            2,
+            WebGl2RenderingContext::FLOAT,
+            false,
+            stride,
+            0,
+        );
+        gl.enable_vertex_attrib_array(self.sp_position);
+        gl.vertex_attrib_pointer_with_i32(
+            self.sp_edge,
+            2,
+            WebGl2RenderingContext::FLOAT,
+            false,
+            stride,
+            stride / 2,
+        );
+        gl.enable_vertex_attrib_array(self.sp_edge);
 
-        let x_from = job.x_from as f32;
-        let x_to = job.x_to as f32;
-        let y_from = job.y_from as f32;
-        let y_to = job.y_to as f32;
-
-        let x_ticks = webgl_utils::calc_ticks(job.x_from, job.x_to - job.x_from);
-        let y_ticks = webgl_utils::calc_ticks(job.y_from, job.y_to - job.y_from);
+        gl.viewport(0, 0, self.width as i32, self.height as i32);
+        self.clip(scene, space);
 
-        // TODO:
+        gl.use_program(Some(&self.stroke_program));
+        gl.uniform2f(
+            Some(&self.sp_resolution_pos),
+            self.width as f32,
+            self.height as f32,
+        );
+        gl.uniform4f(
+            Some(&self.sp_color_pos),
+            color[0],
+            color[1],
+            color[2],
+            color[3],
+        );
+        gl.uniform1f(Some(&self.sp_half_width_pos), stroke.width / 2.0);
+        gl.uniform2f(Some(&self.sp_translation_pos), translation.0, translation.1);
 
-        if job.clear {
-            self.clear();
-        }
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, vertices);
 
-        if job.render_axes {
-            self.render_axes(&job, &x_ticks[..], &y_ticks[..]);
-        }
+        gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.disable_vertex_attrib_array(self.sp_edge);
+        gl.disable_vertex_attrib_array(self.sp_position);
+    }
 
-        if job.render_grid {
-            self.render_grid(&job, &x_ticks[..], &y_ticks[..]);
-        }
+    /// Draws a scene, text is left to the page since the ticks are returned with the result.
+    fn draw(&self, scene: &Scene) -> Result<(), JsValue> {
+        let gl = &self.context;
+        let plot = &scene.plot;
 
-        gl.viewport(
-            (job.margin + job.y_label_space) as i32,
-            (job.margin + job.x_label_space) as i32,
-            (self.width - job.margin * 2 - job.y_label_space) as i32,
-            (self.height - job.margin * 2 - job.x_label_space) as i32,
-        );
+        let project = |space: Space, p: (f32, f32), offset: f32| match space {
+            Space::Screen => p,
+            Space::Plot => plot.to_screen(p, offset),
+        };
 
-        gl.use_program(Some(&self.trace_program));
-        gl.uniform2f(Some(&self.tp_size_pos), x_to - x_from, y_to - y_from);
-        gl.uniform2f(Some(&self.tp_transform_pos), 1.0, 0.0);
+        for command in &scene.commands {
+            let space = command.space;
 
-        if job.get_bundles().len() > 0 {
-            for (_, bundle) in &self.bundles {
-                gl.uniform2f(
-                    Some(&self.tp_origin_pos),
-                    (job.x_from - bundle.from) as f32,
-                    y_from,
-                );
+            match &command.primitive {
+                Primitive::Polyline {
+                    vertices: Vertices::Bundled { bundle, row },
+                    stroke,
+                    color,
+                } => {
+                    let bundle = self.bundles.get(*bundle).unwrap();
+                    let row = &bundle.rows[*row].data;
 
-                for row in &bundle.buffers {
-                    if job.is_blacklisted(row.handle) {
-                        continue;
+                    self.draw_bundled_stroke(scene, bundle.from, row, stroke, *color)?;
+                }
+                Primitive::Polyline {
+                    vertices: Vertices::Owned(points),
+                    stroke,
+                    color,
+                } => {
+                    let projected: Vec<(f32, f32)> =
+                        points.iter().map(|&p| project(space, p, 0.0)).collect();
+
+                    let mut data = vec![];
+
+                    for dash in stroke::dashes(&projected, &stroke.dash_lengths()) {
+                        stroke::tessellate(&dash, stroke, &mut data);
                     }
 
-                    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&row.buffer));
-                    gl.uniform4f(
-                        Some(&self.tp_color_pos),
-                        row.color[0],
-                        row.color[1],
-                        row.color[2],
-                        1.0,
-                    );
-                    gl.line_width(row.width);
+                    self.draw_stroke(scene, space, &data, stroke, *color);
+                }
+                Primitive::Lines {
+                    segments,
+                    stroke,
+                    color,
+                } => {
+                    let pattern = stroke.dash_lengths();
+                    let mut data = vec![];
+
+                    for [from, to] in segments {
+                        let line = [project(space, *from, 0.0), project(space, *to, 0.0)];
+
+                        for dash in stroke::dashes(&line, &pattern) {
+                            stroke::tessellate(&dash, stroke, &mut data);
+                        }
+                    }
 
-                    gl.vertex_attrib_pointer_with_i32(
-                        0,
-                        2,
-                        WebGl2RenderingContext::FLOAT,
-                        false,
-                        0,
+                    self.draw_stroke(scene, space, &data, stroke, *color);
+                }
+                Primitive::Markers {
+                    vertices, color, ..
+                } => {
+                    // Point size is fixed in the trace shader
+                    let (points, offset) = self.bind_vertices(scene, vertices);
+
+                    self.prepare(scene, space, offset, *color);
+                    gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, points);
+                }
+                Primitive::Band {
+                    upper,
+                    lower,
+                    color,
+                } => {
+                    let data: Vec<f32> = upper
+                        .iter()
+                        .zip(lower)
+                        .flat_map(|(&u, &l)| {
+                            let (u, l) = (project(space, u, 0.0), project(space, l, 0.0));
+                            vec![u.0, u.1, l.0, l.1]
+                        })
+                        .collect();
+
+                    self.upload(&data);
+                    self.prepare(scene, Space::Screen, 0.0, *color);
+                    self.clip(scene, space);
+                    gl.draw_arrays(
+                        WebGl2RenderingContext::TRIANGLE_STRIP,
                         0,
+                        upper.len().min(lower.len()) as i32 * 2,
                     );
-                    gl.enable_vertex_attrib_array(0);
-                    gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, row.points);
-                    if row.points_mode { gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, row.points); }
+                    gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+                }
+                Primitive::Rect { from, to, color } => {
+                    self.upload(&[from.0, from.1, to.0, from.1, from.0, to.1, to.0, to.1]);
+                    self.prepare(scene, space, 0.0, *color);
+                    gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4);
+                }
+                Primitive::Rects { rects, color } => {
+                    let data: Vec<f32> = rects
+                        .iter()
+                        .flat_map(|[a, b]| {
+                            vec![a.0, a.1, b.0, a.1, a.0, b.1, a.0, b.1, b.0, a.1, b.0, b.1]
+                        })
+                        .collect();
+
+                    self.upload(&data);
+                    self.prepare(scene, space, 0.0, *color);
+                    gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, rects.len() as i32 * 6);
                 }
+                Primitive::Text { .. } => {}
             }
         }
 
-        gl.uniform2f(Some(&self.tp_origin_pos), 0.0, y_from);
+        Ok(())
+    }
 
-        if job.get_traces().len() > 0 {
-            gl.bind_buffer(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                Some(&self.trace_buffer),
-            );
+    /// Binds a buffer with the points, returns their count and x offset.
+    fn bind_vertices(&self, scene: &Scene, vertices: &Vertices) -> (i32, f32) {
+        match vertices {
+            Vertices::Owned(points) => {
+                let data: Vec<f32> = points.iter().flat_map(|p| vec![p.0, p.1]).collect();
+                self.upload(&data);
 
-            for trace in job.get_traces() {
-                let n;
+                (points.len() as i32, 0.0)
+            }
+            Vertices::Bundled { bundle, row } => {
+                let bundle = self.bundles.get(*bundle).unwrap();
+                let row = &bundle.rows[*row].data;
 
-                gl.uniform4f(
-                    Some(&self.tp_color_pos),
-                    trace.color[0] as f32 / 255.0,
-                    trace.color[1] as f32 / 255.0,
-                    trace.color[2] as f32 / 255.0,
-                    1.0,
-                );
-                gl.line_width(trace.width as f32);
+                self.context
+                    .bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&row.buffer));
+
+                (
+                    row.points.len() as i32,
+                    scene.plot.bundle_offset(bundle.from),
+                )
+            }
+        }
+    }
 
-                unsafe {
-                    use std::iter::once;
+    fn create_buffer(context: &WebGl2RenderingContext) -> Result<WebGlBuffer, JsValue> {
+        context.create_buffer().ok_or_else(|| {
+            JsValue::from_str(
+                "Failed to allocate a buffer, perhaps the WebGL context has been destroyed.",
+            )
+        })
+    }
 
-                    let data: Vec<f32> = crate::data::get_trace_ret(trace.idx, |t| {
-                        t.get_data_with_origin(job.x_from, job.x_to, job.x_from, 0.0)
-                            .flat_map(|p| once(p.0).chain(once(p.1)))
-                            .collect()
-                    });
+    fn allocate_bundle_entry(
+        context: &WebGl2RenderingContext,
+        from: RangePrec,
+        to: RangePrec,
+        entry: &BundleEntry,
+    ) -> Result<GlRow, JsValue> {
+        let buffer = WebGlRenderer::create_buffer(context)?;
+        let points = load_points(from, to, entry);
+        let data: Vec<f32> = points.iter().flat_map(|p| vec![p.0, p.1]).collect();
 
-                    n = data.len() / 2;
-                    let vert_array = js_sys::Float32Array::view(&data);
+        WebGlRenderer::upload_to(context, &buffer, &data);
 
-                    gl.buffer_data_with_array_buffer_view(
-                        WebGl2RenderingContext::ARRAY_BUFFER,
-                        &vert_array,
-                        WebGl2RenderingContext::STATIC_DRAW,
-                    );
-                }
+        Ok(GlRow {
+            points,
+            buffer,
+            stroke: RefCell::new(None),
+        })
+    }
 
-                gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, 0, 0);
-                gl.enable_vertex_attrib_array(0);
-                gl.draw_arrays(WebGl2RenderingContext::LINE_STRIP, 0, n as i32);
-            }
+    fn delete_row(context: &WebGl2RenderingContext, row: GlRow) {
+        context.delete_buffer(Some(&row.buffer));
+
+        if let Some(stroke) = row.stroke.into_inner() {
+            context.delete_buffer(Some(&stroke.buffer));
         }
+    }
+}
 
-        Ok(RenderJobResult { x_ticks, y_ticks })
+impl Renderer for WebGlRenderer {
+    fn render(&mut self, job: RenderJob) -> Result<RenderJobResult, JsValue> {
+        let scene = Scene::compile(&job, self.width as f32, self.height as f32, &self.bundles);
+
+        if scene.clear {
+            self.clear();
+        }
+
+        self.draw(&scene)?;
+
+        Ok(scene.into_result())
     }
 
     fn size_changed(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
@@ -491,38 +664,20 @@ impl Renderer for WebGlRenderer {
         &mut self,
         from: RangePrec,
         to: RangePrec,
-        data: &[super::BundleEntry],
+        data: &[BundleEntry],
     ) -> Result<usize, JsValue> {
-        let mut vec = Vec::with_capacity(data.len());
-
-        for row in data {
-            vec.push(WebGlRenderer::allocate_bundle_entry(
-                &self.context,
-                from,
-                to,
-                row,
-            )?);
-        }
+        let context = &self.context;
 
-        let handle = self.bundles_counter;
-        self.bundles_counter += 1;
-        self.bundles.insert(
-            handle,
-            BufferBundle {
-                from,
-                to,
-                buffers: vec,
-            },
-        );
-
-        Ok(handle)
+        self.bundles.create(from, to, data, |from, to, row| {
+            WebGlRenderer::allocate_bundle_entry(context, from, to, row)
+        })
     }
 
     fn dispose_bundle(&mut self, bundle: usize) -> Result<(), JsValue> {
-        let bundle = self.bundles.remove(&bundle).unwrap();
-
-        for row in bundle.buffers {
-            self.context.delete_buffer(Some(&row.buffer));
+        if let Some(bundle) = self.bundles.dispose(bundle) {
+            for row in bundle.rows {
+                WebGlRenderer::delete_row(&self.context, row.data);
+            }
         }
 
         Ok(())
@@ -531,55 +686,29 @@ impl Renderer for WebGlRenderer {
     fn rebundle(
         &mut self,
         bundle: usize,
-        to_add: &[super::BundleEntry],
+        to_add: &[BundleEntry],
         to_del: &[DataIdx],
-        to_mod: &[super::BundleEntry],
+        to_mod: &[BundleEntry],
     ) -> Result<(), JsValue> {
-        let b = self.bundles.get_mut(&bundle).unwrap();
-
-        for row in to_add {
-            b.buffers.push(WebGlRenderer::allocate_bundle_entry(
-                &self.context,
-                b.from,
-                b.to,
-                row,
-            )?);
-        }
+        let context = &self.context;
 
-        b.buffers.retain(|e| !to_del.iter().any(|t| *t == e.handle));
-
-        for row in to_mod {
-            if let Some(buffer) = b.buffers.iter_mut().find(|e| e.handle == row.handle) {
-                buffer.width = row.width as f32;
-                buffer.color = [
-                    row.color[0] as f32 / 255.0,
-                    row.color[1] as f32 / 255.0,
-                    row.color[2] as f32 / 255.0,
-                ];
-                buffer.points_mode = row.points_mode;
-            }
-        }
-
-        Result::Ok(())
-    }
-}
-
-impl Drop for WebGlRenderer {
-    fn drop(&mut self) {
-        let bundles: Vec<usize> = self.bundles.keys().cloned().collect();
+        let removed = self
+            .bundles
+            .rebundle(bundle, to_add, to_del, to_mod, |from, to, row| {
+                WebGlRenderer::allocate_bundle_entry(context, from, to, row)
+            })?;
 
-        for handle in bundles {
-            self.dispose_bundle(handle)
-                .expect("Failed to dispose a bundle");
+        for row in removed {
+            WebGlRenderer::delete_row(&self.context, row);
         }
+
+        Ok(())
     }
 }
 
 mod webgl_utils {
     use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader};
 
-    use crate::{renderers::AxisTick, structs::RangePrec};
-
     pub fn compile_shader(
         context: &WebGl2RenderingContext,
         shader_type: u32,
@@ -629,31 +758,4 @@ mod webgl_utils {
                 .unwrap_or_else(|| String::from("Unknown error creating program object")))
         }
     }
-
-    pub fn calc_ticks(start: RangePrec, width: RangePrec) -> Box<[AxisTick]> {
-        const SIZES: [RangePrec; 4] = [1.0, 2.0, 5.0, 10.0];
-
-        let mut y0: RangePrec = 0.0;
-        let mut dy: RangePrec = 1.0;
-
-        {
-            let order = width.log10().floor() - 1.0;
-
-            for size in SIZES.iter() {
-                dy = (10.0 as RangePrec).powf(order) * size;
-                y0 = (start / dy).floor() * dy;
-
-                if (width + start - y0) / dy < 10.0 {
-                    break;
-                }
-            }
-        }
-
-        (1..=((width + start - y0) / dy).floor() as usize)
-            .map(|i| AxisTick {
-                val: y0 + dy * i as RangePrec,
-                pos: (y0 + dy * i as RangePrec - start) / width,
-            })
-            .collect()
-    }
 }
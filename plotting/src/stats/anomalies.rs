@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::structs::RangePrec;
+
+use super::{median, nominal_interval, DATETIME_DAY};
+
+/// Scale factor making MAD a consistent estimator of standard deviation for normal data.
+const MAD_SCALE: RangePrec = 1.4826;
+
+pub enum AnomalyMethod {
+    /// Distance from the mean of the preceding `window` samples in standard deviations.
+    ZScore,
+    /// Distance from the median of the preceding `window` samples in scaled MADs.
+    Mad,
+    /// Robust score of the residual after subtracting the per-phase median of `period`.
+    Seasonal,
+}
+
+impl AnomalyMethod {
+    /// Parses `zscore`, `mad` or `seasonal`.
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "zscore" => Ok(AnomalyMethod::ZScore),
+            "mad" => Ok(AnomalyMethod::Mad),
+            "seasonal" => Ok(AnomalyMethod::Seasonal),
+            _ => Err(format!("Unknown anomaly detection method '{}'", method)),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct AnomalyParams {
+    pub window: usize,
+    pub threshold: RangePrec,
+    /// Season length in x units, defaults to a day of `datetime` minutes.
+    pub period: RangePrec,
+}
+
+#[wasm_bindgen]
+impl AnomalyParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            window: 30,
+            threshold: 3.0,
+            period: DATETIME_DAY,
+        }
+    }
+}
+
+impl Default for AnomalyParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Anomaly {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    /// Position of the highest scoring sample.
+    pub peak: RangePrec,
+    pub score: RangePrec,
+}
+
+/// Score of every sample, NaN and infinite samples score zero and are left out of the windows and
+/// medians.
+pub fn anomaly_scores(
+    points: &[(RangePrec, RangePrec)],
+    method: &AnomalyMethod,
+    params: &AnomalyParams,
+) -> Vec<RangePrec> {
+    let ys: Vec<RangePrec> = points.iter().map(|p| p.1).collect();
+    let window = params.window.max(2);
+
+    match method {
+        AnomalyMethod::ZScore | AnomalyMethod::Mad => {
+            // The preceding `window` finite samples
+            let mut recent: VecDeque<RangePrec> = VecDeque::with_capacity(window + 1);
+            let (mut sum, mut sum_sq) = (0.0, 0.0);
+
+            ys.iter()
+                .map(|&y| {
+                    if !y.is_finite() {
+                        return 0.0;
+                    }
+
+                    let n = recent.len();
+                    let score = match method {
+                        _ if n < 2 => 0.0,
+                        AnomalyMethod::ZScore => {
+                            let mean = sum / n as RangePrec;
+                            let var = (sum_sq / n as RangePrec - mean * mean).max(0.0);
+
+                            robust_ratio(y - mean, var.sqrt())
+                        }
+                        _ => {
+                            let (med, mad) = median_mad(recent.make_contiguous());
+                            robust_ratio(y - med, mad * MAD_SCALE)
+                        }
+                    };
+
+                    recent.push_back(y);
+                    sum += y;
+                    sum_sq += y * y;
+
+                    if recent.len() > window {
+                        let old = recent.pop_front().unwrap();
+                        sum -= old;
+                        sum_sq -= old * old;
+                    }
+
+                    score
+                })
+                .collect()
+        }
+        AnomalyMethod::Seasonal => {
+            let residuals = seasonal_residuals(points, params.period);
+            let (med, mad) = median_mad(&residuals);
+
+            residuals
+                .iter()
+                .map(|r| {
+                    if r.is_finite() {
+                        robust_ratio(r - med, mad * MAD_SCALE)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Groups consecutive samples scoring at least `params.threshold` into intervals.
+pub fn detect_anomalies(
+    points: &[(RangePrec, RangePrec)],
+    method: &AnomalyMethod,
+    params: &AnomalyParams,
+) -> Vec<Anomaly> {
+    let mut result: Vec<Anomaly> = vec![];
+    let mut open = false;
+
+    for (&(x, _), score) in points.iter().zip(anomaly_scores(points, method, params)) {
+        if score < params.threshold {
+            open = false;
+            continue;
+        }
+
+        match result.last_mut() {
+            Some(last) if open => {
+                last.to = x;
+
+                if score > last.score {
+                    last.score = score;
+                    last.peak = x;
+                }
+            }
+            _ => result.push(Anomaly {
+                from: x,
+                to: x,
+                peak: x,
+                score,
+            }),
+        }
+
+        open = true;
+    }
+
+    result
+}
+
+/// Residuals of `points` after subtracting the median of all samples sharing the same phase of `period`.
+pub fn seasonal_residuals(points: &[(RangePrec, RangePrec)], period: RangePrec) -> Vec<RangePrec> {
+    let baseline = seasonal_baseline(points, period);
+
+    points
+        .iter()
+        .zip(baseline.iter())
+        .map(|(p, b)| p.1 - b)
+        .collect()
+}
+
+/// Per-sample value of the seasonal profile, which is the median of all samples sharing the same phase of `period`.
+pub fn seasonal_baseline(points: &[(RangePrec, RangePrec)], period: RangePrec) -> Vec<RangePrec> {
+    let xs: Vec<RangePrec> = points.iter().map(|p| p.0).collect();
+    let interval = nominal_interval(&xs);
+
+    let periodic = interval > 0.0 && period >= interval;
+
+    if points.is_empty() || !periodic {
+        let med = median(&points.iter().map(|p| p.1).collect::<Vec<_>>());
+        return vec![med; points.len()];
+    }
+
+    let slots = (period / interval).round().max(1.0) as usize;
+    let slot_of = |x: RangePrec| {
+        (((x - xs[0]).rem_euclid(period) / period * slots as RangePrec).round() as usize) % slots
+    };
+
+    let mut phases = vec![vec![]; slots];
+
+    for &(x, y) in points {
+        phases[slot_of(x)].push(y);
+    }
+
+    let profile: Vec<RangePrec> = phases.iter().map(|p| median(p)).collect();
+
+    points.iter().map(|&(x, _)| profile[slot_of(x)]).collect()
+}
+
+/// Median and median absolute deviation of the finite values.
+fn median_mad(values: &[RangePrec]) -> (RangePrec, RangePrec) {
+    let med = median(values);
+    let deviations: Vec<RangePrec> = values.iter().map(|v| (v - med).abs()).collect();
+
+    (med, median(&deviations))
+}
+
+fn robust_ratio(deviation: RangePrec, spread: RangePrec) -> RangePrec {
+    // A flat history would make any change infinitely anomalous, keep the score finite
+    deviation.abs() / spread.max(RangePrec::EPSILON.sqrt())
+}
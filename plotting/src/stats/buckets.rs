@@ -0,0 +1,202 @@
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+};
+use chrono_tz::Tz;
+
+use crate::structs::RangePrec;
+
+use super::RankStat;
+
+/// `datetime` values are minutes since the UNIX epoch.
+const SECONDS_PER_UNIT: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BucketSize {
+    Minutes(u32),
+    Hours(u32),
+    Days(u32),
+    /// Weeks start on Monday.
+    Weeks(u32),
+    Months(u32),
+}
+
+impl BucketSize {
+    /// Parses sizes like `5m`, `1h`, `1d`, `1w` or `3M`, as well as `hourly`, `daily`, `weekly` and `monthly`.
+    /// Buckets restart every hour, day and year, so minutes, hours and months have to divide them.
+    pub fn parse(size: &str) -> Result<Self, String> {
+        match size {
+            "hourly" => return Ok(BucketSize::Hours(1)),
+            "daily" => return Ok(BucketSize::Days(1)),
+            "weekly" => return Ok(BucketSize::Weeks(1)),
+            "monthly" => return Ok(BucketSize::Months(1)),
+            _ => {}
+        }
+
+        let invalid = || format!("Invalid bucket size '{}'", size);
+
+        let unit = size.chars().last().ok_or_else(invalid)?;
+        let count: u32 = size[..size.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+
+        if count == 0 {
+            return Err(invalid());
+        }
+
+        match unit {
+            'm' if 60 % count == 0 => Ok(BucketSize::Minutes(count)),
+            'h' if 24 % count == 0 => Ok(BucketSize::Hours(count)),
+            'd' => Ok(BucketSize::Days(count)),
+            'w' => Ok(BucketSize::Weeks(count)),
+            'M' if 12 % count == 0 => Ok(BucketSize::Months(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Statistic of the samples in one bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregate {
+    Avg,
+    Max,
+    Sum,
+    /// Percentile, `p95` is the 95th percentile.
+    Percentile(RangePrec),
+}
+
+impl Aggregate {
+    /// Parses `avg`, `max`, `sum` or `pN`.
+    pub fn parse(aggregate: &str) -> Result<Self, String> {
+        match aggregate {
+            "avg" => Ok(Aggregate::Avg),
+            "max" => Ok(Aggregate::Max),
+            "sum" => Ok(Aggregate::Sum),
+            _ => match aggregate.strip_prefix('p').map(|p| p.parse::<RangePrec>()) {
+                Some(Ok(p)) if (0.0..=100.0).contains(&p) => Ok(Aggregate::Percentile(p)),
+                _ => Err(format!("Unknown aggregate '{}'", aggregate)),
+            },
+        }
+    }
+
+    fn stat(self) -> RankStat {
+        match self {
+            Aggregate::Avg => RankStat::Avg,
+            Aggregate::Max => RankStat::Max,
+            Aggregate::Sum => RankStat::Sum,
+            Aggregate::Percentile(p) => RankStat::Percentile(p),
+        }
+    }
+}
+
+pub fn parse_tz(tz: &str) -> Result<Tz, String> {
+    tz.parse()
+        .map_err(|_| format!("Unknown time zone '{}'", tz))
+}
+
+/// Start of the calendar bucket containing `x`, both as `datetime` values.
+pub fn bucket_start(x: RangePrec, size: BucketSize, tz: Tz) -> RangePrec {
+    let secs = (x * SECONDS_PER_UNIT as RangePrec).floor() as i64;
+    let local = tz.timestamp_opt(secs, 0).unwrap().naive_local();
+
+    let date = local.date();
+    let truncated = match size {
+        BucketSize::Minutes(n) => date.and_hms_opt(local.hour(), local.minute() / n * n, 0),
+        BucketSize::Hours(n) => date.and_hms_opt(local.hour() / n * n, 0, 0),
+        BucketSize::Days(n) => {
+            let day = date.num_days_from_ce();
+            NaiveDate::from_num_days_from_ce_opt(day - (day - 1).rem_euclid(n as i32))
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        }
+        BucketSize::Weeks(n) => {
+            let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            let week = (monday.num_days_from_ce() - 1) / 7;
+            let start = monday - Duration::weeks(week.rem_euclid(n as i32) as i64);
+
+            start.and_hms_opt(0, 0, 0)
+        }
+        BucketSize::Months(n) => {
+            let month = date.month0() / n * n;
+            NaiveDate::from_ymd_opt(date.year(), month + 1, 1).and_then(|d| d.and_hms_opt(0, 0, 0))
+        }
+    }
+    .unwrap();
+
+    to_utc(truncated, tz, secs) as RangePrec / SECONDS_PER_UNIT as RangePrec
+}
+
+/// Resolves a local bucket start into a UTC timestamp in seconds not after `secs`.
+fn to_utc(local: NaiveDateTime, tz: Tz, secs: i64) -> i64 {
+    let mut local = local;
+
+    loop {
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(t) => return t.timestamp(),
+            // Repeated wall time when clocks go back, the bucket is the occurrence preceding the sample
+            LocalResult::Ambiguous(early, late) => {
+                return if late.timestamp() <= secs {
+                    late.timestamp()
+                } else {
+                    early.timestamp()
+                };
+            }
+            // Skipped wall time when clocks go forward, the bucket starts once the clocks are valid again
+            LocalResult::None => local += Duration::minutes(15),
+        }
+    }
+}
+
+/// Aggregates x-sorted `datetime` points per calendar bucket, yielding one point at every bucket start.
+pub fn bucketize(
+    points: &[(RangePrec, RangePrec)],
+    size: BucketSize,
+    tz: Tz,
+    aggregate: Aggregate,
+) -> Vec<(RangePrec, RangePrec)> {
+    let stat = aggregate.stat();
+    let mut result = vec![];
+    let mut start = 0;
+
+    while start < points.len() {
+        let bucket = bucket_start(points[start].0, size, tz);
+        let next = next_bucket(bucket, size, tz);
+
+        let end = start + points[start..].iter().take_while(|p| p.0 < next).count();
+
+        if let Some(value) = stat.evaluate(points[start..end].iter().cloned()) {
+            result.push((bucket, value));
+        }
+
+        start = end;
+    }
+
+    result
+}
+
+fn next_bucket(bucket: RangePrec, size: BucketSize, tz: Tz) -> RangePrec {
+    // Probe into the following bucket and truncate, which copes with days and months of any length
+    let secs = (bucket * SECONDS_PER_UNIT as RangePrec) as i64;
+    let local: DateTime<Tz> = tz.timestamp_opt(secs, 0).unwrap();
+
+    let probe = match size {
+        BucketSize::Minutes(n) => local + Duration::minutes(n as i64),
+        BucketSize::Hours(n) => local + Duration::hours(n as i64),
+        BucketSize::Days(n) => local + Duration::days(n as i64) + Duration::hours(3),
+        BucketSize::Weeks(n) => local + Duration::weeks(n as i64) + Duration::hours(3),
+        BucketSize::Months(n) => {
+            let months = local.month0() + n;
+            let mid_month =
+                NaiveDate::from_ymd_opt(local.year() + (months / 12) as i32, months % 12 + 1, 15)
+                    .and_then(|d| d.and_hms_opt(12, 0, 0))
+                    .unwrap();
+
+            tz.timestamp_opt(to_utc(mid_month, tz, i64::MAX), 0)
+                .unwrap()
+        }
+    };
+
+    bucket_start(
+        probe.timestamp() as RangePrec / SECONDS_PER_UNIT as RangePrec,
+        size,
+        tz,
+    )
+}
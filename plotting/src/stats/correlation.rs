@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::structs::RangePrec;
+
+pub enum Correlation {
+    Pearson,
+    /// Pearson correlation of ranks, ties get their average rank.
+    Spearman,
+}
+
+impl Correlation {
+    /// Parses `pearson` or `spearman`.
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "pearson" => Ok(Correlation::Pearson),
+            "spearman" => Ok(Correlation::Spearman),
+            _ => Err(format!("Unknown correlation method '{}'", method)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorrelationMatrix {
+    pub coefficients: Vec<Vec<RangePrec>>,
+    /// Best `(lag, coefficient)` for every pair, lag is in aligned samples of the column series
+    /// relative to the row series. Present only when a lag search was requested.
+    pub lags: Option<Vec<Vec<(isize, RangePrec)>>>,
+}
+
+impl CorrelationMatrix {
+    /// Expects series already aligned on common timestamps, see [`super::align`]. Timestamps
+    /// where any of the series is not finite are left out.
+    pub fn new(series: &[Vec<RangePrec>], method: &Correlation, max_lag: usize) -> Self {
+        let len = series.iter().map(|s| s.len()).min().unwrap_or(0);
+        let finite: Vec<usize> = (0..len)
+            .filter(|&i| series.iter().all(|s| s[i].is_finite()))
+            .collect();
+        let series: Vec<Vec<RangePrec>> = series
+            .iter()
+            .map(|s| finite.iter().map(|&i| s[i]).collect())
+            .collect();
+
+        let series: Vec<Vec<RangePrec>> = match method {
+            Correlation::Pearson => series,
+            Correlation::Spearman => series.iter().map(|s| ranks(s)).collect(),
+        };
+
+        let n = series.len();
+        let mut coefficients = vec![vec![RangePrec::NAN; n]; n];
+
+        for i in 0..n {
+            for j in i..n {
+                let r = pearson(&series[i], &series[j]);
+                coefficients[i][j] = r;
+                coefficients[j][i] = r;
+            }
+        }
+
+        let lags = if max_lag > 0 {
+            Some(
+                (0..n)
+                    .map(|i| {
+                        (0..n)
+                            .map(|j| best_lag(&series[i], &series[j], max_lag))
+                            .collect()
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        Self { coefficients, lags }
+    }
+}
+
+pub fn pearson(a: &[RangePrec], b: &[RangePrec]) -> RangePrec {
+    let n = a.len().min(b.len());
+
+    if n < 2 {
+        return RangePrec::NAN;
+    }
+
+    let mean_a = a[..n].iter().sum::<RangePrec>() / n as RangePrec;
+    let mean_b = b[..n].iter().sum::<RangePrec>() / n as RangePrec;
+
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+
+    for (x, y) in a[..n].iter().zip(b[..n].iter()) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a) * (x - mean_a);
+        var_b += (y - mean_b) * (y - mean_b);
+    }
+
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Ranks starting at 1, NaN values sort after everything else.
+pub fn ranks(values: &[RangePrec]) -> Vec<RangePrec> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut result = vec![0.0; values.len()];
+    let mut start = 0;
+
+    while start < order.len() {
+        let mut end = start + 1;
+
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+
+        let rank = (start + end - 1) as RangePrec / 2.0 + 1.0;
+
+        for &idx in &order[start..end] {
+            result[idx] = rank;
+        }
+
+        start = end;
+    }
+
+    result
+}
+
+fn best_lag(a: &[RangePrec], b: &[RangePrec], max_lag: usize) -> (isize, RangePrec) {
+    let max_lag = max_lag.min(a.len().saturating_sub(2)) as isize;
+
+    (-max_lag..=max_lag)
+        .map(|lag| {
+            let r = if lag >= 0 {
+                pearson(&a[..a.len() - lag as usize], &b[lag as usize..])
+            } else {
+                pearson(&a[(-lag) as usize..], &b[..b.len() - (-lag) as usize])
+            };
+
+            (lag, r)
+        })
+        .filter(|(_, r)| r.is_finite())
+        .fold((0, RangePrec::NAN), |best, cur| {
+            if best.1.is_nan() || cur.1.abs() > best.1.abs() {
+                cur
+            } else {
+                best
+            }
+        })
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::structs::RangePrec;
+
+use super::nominal_interval;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum GapKind {
+    /// No segment covers the range.
+    Unloaded,
+    /// The range is loaded but contains no samples.
+    Missing,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Gap {
+    pub from: RangePrec,
+    pub to: RangePrec,
+    pub kind: GapKind,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GapReport {
+    /// Inferred sampling interval, `NaN` with less than two samples.
+    pub interval: RangePrec,
+    pub gaps: Vec<Gap>,
+    /// Received samples as a percentage of those expected at `interval` over the whole range.
+    pub completeness: RangePrec,
+}
+
+/// Lists parts of `from..to` not covered by the sorted, disjoint `loaded` ranges and spaces
+/// longer than `k` sampling intervals between the `points` inside them. Samples with a NaN or
+/// infinite value count as missing.
+pub fn gap_report(
+    loaded: &[(RangePrec, RangePrec)],
+    points: &[(RangePrec, RangePrec)],
+    from: RangePrec,
+    to: RangePrec,
+    k: RangePrec,
+) -> GapReport {
+    let xs: Vec<RangePrec> = points
+        .iter()
+        .filter(|p| p.1.is_finite())
+        .map(|p| p.0)
+        .collect();
+    let interval = nominal_interval(&xs);
+    let max_space = interval * k;
+
+    let mut gaps = vec![];
+    let mut cursor = from;
+
+    for &(seg_from, seg_to) in loaded {
+        let (seg_from, seg_to) = (seg_from.max(from), seg_to.min(to));
+
+        if seg_to <= seg_from {
+            continue;
+        }
+
+        if seg_from > cursor {
+            gaps.push(Gap {
+                from: cursor,
+                to: seg_from,
+                kind: GapKind::Unloaded,
+            });
+        }
+
+        let inside = xs
+            .iter()
+            .cloned()
+            .filter(|x| *x >= seg_from && *x <= seg_to);
+        let mut prev = seg_from;
+
+        for x in inside.chain(std::iter::once(seg_to)) {
+            // Without a known interval, only an entirely empty segment is a gap
+            let missing = if interval.is_nan() {
+                xs.is_empty() && x > prev
+            } else {
+                x - prev > max_space
+            };
+
+            if missing {
+                gaps.push(Gap {
+                    from: prev,
+                    to: x,
+                    kind: GapKind::Missing,
+                });
+            }
+
+            prev = x;
+        }
+
+        cursor = seg_to;
+    }
+
+    if to > cursor {
+        gaps.push(Gap {
+            from: cursor,
+            to,
+            kind: GapKind::Unloaded,
+        });
+    }
+
+    let completeness = if interval > 0.0 {
+        (xs.len() as RangePrec / ((to - from) / interval).max(1.0) * 100.0).min(100.0)
+    } else {
+        0.0
+    };
+
+    GapReport {
+        interval,
+        gaps,
+        completeness,
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use crate::structs::RangePrec;
+
+use super::{percentile, sort_values};
+
+const MAX_AUTO_BINS: usize = 512;
+
+pub enum Binning {
+    Fixed(usize),
+    Log(usize),
+    /// Freedman–Diaconis rule, falls back to Sturges' formula for data with zero IQR.
+    Auto,
+}
+
+impl Binning {
+    /// Parses `fixed`, `log` or `auto`, `bins` is ignored by the latter.
+    pub fn parse(mode: &str, bins: usize) -> Result<Self, String> {
+        match mode {
+            "fixed" => Ok(Binning::Fixed(bins.max(1))),
+            "log" => Ok(Binning::Log(bins.max(1))),
+            "auto" => Ok(Binning::Auto),
+            _ => Err(format!("Unknown binning '{}'", mode)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Histogram {
+    pub edges: Vec<RangePrec>,
+    pub counts: Vec<usize>,
+    pub cdf: Vec<RangePrec>,
+}
+
+impl Histogram {
+    /// Non-finite values are ignored, logarithmic binning also ignores values `<= 0`.
+    pub fn new(values: &[RangePrec], binning: &Binning) -> Self {
+        let mut values: Vec<RangePrec> = values
+            .iter()
+            .cloned()
+            .filter(|v| v.is_finite())
+            .filter(|v| !matches!(binning, Binning::Log(_)) || *v > 0.0)
+            .collect();
+
+        if values.is_empty() {
+            return Self {
+                edges: vec![],
+                counts: vec![],
+                cdf: vec![],
+            };
+        }
+
+        sort_values(&mut values);
+
+        let min = values[0];
+        let max = values[values.len() - 1];
+
+        let edges: Vec<RangePrec> = match binning {
+            Binning::Fixed(bins) => linear_edges(min, max, *bins),
+            Binning::Auto => {
+                let iqr = percentile(&values, 75.0) - percentile(&values, 25.0);
+                let n = values.len() as RangePrec;
+
+                let bins = if iqr > 0.0 {
+                    let width = 2.0 * iqr / n.cbrt();
+                    ((max - min) / width).ceil() as usize
+                } else {
+                    n.log2().ceil() as usize + 1
+                };
+
+                linear_edges(min, max, bins.clamp(1, MAX_AUTO_BINS))
+            }
+            Binning::Log(bins) => linear_edges(min.log10(), max.log10(), *bins)
+                .into_iter()
+                .map(|e| RangePrec::powf(10.0, e))
+                .collect(),
+        };
+
+        let bins = edges.len() - 1;
+        let mut counts = vec![0; bins];
+
+        let (lo, hi) = match binning {
+            Binning::Log(_) => (edges[0].log10(), edges[bins].log10()),
+            _ => (edges[0], edges[bins]),
+        };
+
+        for v in values.iter() {
+            let v = match binning {
+                Binning::Log(_) => v.log10(),
+                _ => *v,
+            };
+
+            let idx = ((v - lo) / (hi - lo) * bins as RangePrec).floor() as usize;
+            counts[idx.min(bins - 1)] += 1;
+        }
+
+        let total = values.len() as RangePrec;
+        let cdf = counts
+            .iter()
+            .scan(0, |acc, c| {
+                *acc += c;
+                Some(*acc as RangePrec / total)
+            })
+            .collect();
+
+        Self { edges, counts, cdf }
+    }
+}
+
+fn linear_edges(min: RangePrec, max: RangePrec, bins: usize) -> Vec<RangePrec> {
+    // Constant data would produce a zero width histogram, center a unit bin around it instead
+    let (min, max) = if max > min {
+        (min, max)
+    } else {
+        (min - 0.5, max + 0.5)
+    };
+
+    (0..=bins)
+        .map(|i| min + (max - min) * i as RangePrec / bins as RangePrec)
+        .collect()
+}
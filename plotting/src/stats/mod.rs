@@ -0,0 +1,145 @@
+mod anomalies;
+mod buckets;
+mod correlation;
+mod gaps;
+mod histogram;
+mod periodicity;
+mod ranking;
+mod thresholds;
+mod trend;
+
+pub use anomalies::{
+    anomaly_scores, detect_anomalies, seasonal_baseline, seasonal_residuals, Anomaly,
+    AnomalyMethod, AnomalyParams,
+};
+pub use buckets::{bucket_start, bucketize, parse_tz, Aggregate, BucketSize};
+pub use correlation::{pearson, ranks, Correlation, CorrelationMatrix};
+pub use gaps::{gap_report, Gap, GapKind, GapReport};
+pub use histogram::{Binning, Histogram};
+pub use periodicity::{deseasonalize, find_periods, Period, PeriodMethod};
+pub use ranking::{select_top, RankStat};
+pub use thresholds::{threshold_intervals, ThresholdParams, ThresholdReport, Violation};
+pub use trend::{forecast, Forecast, TrendMethod, TrendParams};
+
+use crate::structs::RangePrec;
+
+/// Length of a day in `datetime` units, which are minutes.
+pub const DATETIME_DAY: RangePrec = 1440.0;
+
+/// Linearly interpolated percentile of an already sorted slice, `p` is in range `0..=100`.
+pub fn percentile(sorted: &[RangePrec], p: RangePrec) -> RangePrec {
+    if sorted.is_empty() {
+        return RangePrec::NAN;
+    }
+
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as RangePrec;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as RangePrec)
+}
+
+/// Sorts values ascending, NaN and infinite values are dropped.
+pub fn sort_values(values: &mut Vec<RangePrec>) {
+    values.retain(|v| v.is_finite());
+    values.sort_by(|a, b| a.total_cmp(b));
+}
+
+pub fn median(values: &[RangePrec]) -> RangePrec {
+    let mut sorted = values.to_vec();
+    sort_values(&mut sorted);
+
+    percentile(&sorted, 50.0)
+}
+
+/// Typical sampling interval of sorted timestamps, the median of their positive differences.
+pub fn nominal_interval(xs: &[RangePrec]) -> RangePrec {
+    let diffs: Vec<RangePrec> = xs
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|d| *d > 0.0)
+        .collect();
+
+    median(&diffs)
+}
+
+/// Joins x-sorted series on their common timestamps, returning the timestamps
+/// and the y values of every series at them.
+pub fn align(series: &[Vec<(RangePrec, RangePrec)>]) -> (Vec<RangePrec>, Vec<Vec<RangePrec>>) {
+    let mut xs = vec![];
+    let mut ys = vec![vec![]; series.len()];
+
+    if series.is_empty() {
+        return (xs, ys);
+    }
+
+    let mut cursors = vec![0; series.len()];
+
+    'outer: loop {
+        let mut x = RangePrec::MIN;
+
+        for (s, &c) in series.iter().zip(cursors.iter()) {
+            match s.get(c) {
+                Some(p) => x = x.max(p.0),
+                None => break 'outer,
+            }
+        }
+
+        let mut matched = true;
+
+        for (s, c) in series.iter().zip(cursors.iter_mut()) {
+            while *c < s.len() && s[*c].0 < x {
+                *c += 1;
+            }
+
+            match s.get(*c) {
+                Some(p) if p.0 == x => {}
+                Some(_) => matched = false,
+                None => break 'outer,
+            }
+        }
+
+        if matched {
+            xs.push(x);
+
+            for ((s, c), y) in series.iter().zip(cursors.iter_mut()).zip(ys.iter_mut()) {
+                y.push(s[*c].1);
+                *c += 1;
+            }
+        }
+    }
+
+    (xs, ys)
+}
+
+/// Linearly interpolates x-sorted points onto a regular grid of `step` starting at the first point.
+pub fn resample(points: &[(RangePrec, RangePrec)], step: RangePrec) -> Vec<(RangePrec, RangePrec)> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.0, last.0),
+        _ => return vec![],
+    };
+
+    let mut result = Vec::with_capacity(((last - first) / step) as usize + 1);
+    let mut idx = 0;
+    let mut x = first;
+
+    while x <= last {
+        while idx + 1 < points.len() && points[idx + 1].0 < x {
+            idx += 1;
+        }
+
+        let left = points[idx];
+        let right = points[(idx + 1).min(points.len() - 1)];
+
+        let y = if right.0 > left.0 && x > left.0 {
+            left.1 + (right.1 - left.1) * (x - left.0) / (right.0 - left.0)
+        } else {
+            left.1
+        };
+
+        result.push((x, y));
+        x = first + step * result.len() as RangePrec;
+    }
+
+    result
+}
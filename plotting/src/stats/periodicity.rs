@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+use crate::structs::RangePrec;
+
+use super::{nominal_interval, resample, seasonal_baseline};
+
+pub enum PeriodMethod {
+    /// Peaks of the power spectrum, strength is the share of the signal variance.
+    Fft,
+    /// Peaks of the autocorrelation function, strength is the correlation at the lag.
+    Autocorrelation,
+}
+
+impl PeriodMethod {
+    /// Parses `fft` or `acf`.
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "fft" => Ok(PeriodMethod::Fft),
+            "acf" => Ok(PeriodMethod::Autocorrelation),
+            _ => Err(format!("Unknown periodicity method '{}'", method)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Period {
+    pub period: RangePrec,
+    pub strength: RangePrec,
+}
+
+/// Strongest periods of the points, strongest first. Only periods repeating at least twice in the data are reported.
+/// NaN and infinite values are interpolated over.
+pub fn find_periods(
+    points: &[(RangePrec, RangePrec)],
+    method: &PeriodMethod,
+    count: usize,
+) -> Vec<Period> {
+    let points: Vec<(RangePrec, RangePrec)> =
+        points.iter().copied().filter(|p| p.1.is_finite()).collect();
+    let xs: Vec<RangePrec> = points.iter().map(|p| p.0).collect();
+    let step = nominal_interval(&xs);
+
+    if points.len() < 4 || step.is_nan() || step <= 0.0 {
+        return vec![];
+    }
+
+    let mut ys: Vec<RangePrec> = resample(&points, step).iter().map(|p| p.1).collect();
+    let n = ys.len();
+    let mean = ys.iter().sum::<RangePrec>() / n as RangePrec;
+    ys.iter_mut().for_each(|y| *y -= mean);
+
+    // Zero padding to twice the length keeps the circular correlation from wrapping around
+    let size = (2 * n).next_power_of_two();
+    let mut re = ys.clone();
+    re.resize(size, 0.0);
+    let mut im = vec![0.0; size];
+
+    fft(&mut re, &mut im, false);
+
+    let power: Vec<RangePrec> = re
+        .iter()
+        .zip(im.iter())
+        .map(|(r, i)| r * r + i * i)
+        .collect();
+
+    let mut peaks: Vec<Period> = match method {
+        PeriodMethod::Fft => {
+            let total: RangePrec = power[1..size / 2].iter().sum();
+            let min_k = (2.0 * size as RangePrec / n as RangePrec).ceil() as usize;
+
+            local_maxima(&power[..size / 2], min_k.max(1))
+                .into_iter()
+                .map(|k| Period {
+                    period: size as RangePrec / k as RangePrec * step,
+                    strength: (power[k - 1] + power[k] + power[k + 1]) / total,
+                })
+                .collect()
+        }
+        PeriodMethod::Autocorrelation => {
+            let mut re = power;
+            let mut im = vec![0.0; size];
+            fft(&mut re, &mut im, true);
+
+            let acf: Vec<RangePrec> = re[..n / 2 + 1].iter().map(|r| r / re[0]).collect();
+
+            // Skip the main lobe around lag zero
+            let first_dip = acf.iter().position(|r| *r < 0.0).unwrap_or(acf.len());
+
+            local_maxima(&acf, first_dip.max(1))
+                .into_iter()
+                .filter(|&lag| acf[lag] > 0.0)
+                .map(|lag| Period {
+                    period: lag as RangePrec * step,
+                    strength: acf[lag],
+                })
+                .collect()
+        }
+    };
+
+    peaks.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+    peaks.truncate(count);
+
+    peaks
+}
+
+/// Removes the seasonal component of `period`, keeping the level of the data.
+pub fn deseasonalize(
+    points: &[(RangePrec, RangePrec)],
+    period: RangePrec,
+) -> Vec<(RangePrec, RangePrec)> {
+    let baseline = seasonal_baseline(points, period);
+    let level = baseline.iter().sum::<RangePrec>() / baseline.len().max(1) as RangePrec;
+
+    points
+        .iter()
+        .zip(baseline.iter())
+        .map(|(p, b)| (p.0, p.1 - (b - level)))
+        .collect()
+}
+
+fn local_maxima(values: &[RangePrec], from: usize) -> Vec<usize> {
+    (from.max(1)..values.len().saturating_sub(1))
+        .filter(|&i| values[i] > values[i - 1] && values[i] >= values[i + 1])
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT, the length has to be a power of two.
+fn fft(re: &mut [RangePrec], im: &mut [RangePrec], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+
+    for i in 1..n {
+        let mut bit = n >> 1;
+
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+
+    while len <= n {
+        let angle = sign * 2.0 * std::f64::consts::PI / len as RangePrec;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0, 0.0);
+
+            for k in 0..len / 2 {
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+
+        len <<= 1;
+    }
+
+    if inverse {
+        re.iter_mut().for_each(|r| *r /= n as RangePrec);
+        im.iter_mut().for_each(|i| *i /= n as RangePrec);
+    }
+}
@@ -0,0 +1,132 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::structs::RangePrec;
+
+use super::{percentile, sort_values};
+
+pub enum RankStat {
+    Avg,
+    Max,
+    Sum,
+    /// Percentile, `p95` ranks by the 95th percentile.
+    Percentile(RangePrec),
+    /// Time spent at or above the threshold, each sample counts until the next one.
+    TimeAbove(RangePrec),
+}
+
+impl RankStat {
+    /// Parses `avg`, `max`, `sum`, `pN` or `above`, the latter uses `threshold`.
+    pub fn parse(stat: &str, threshold: RangePrec) -> Result<Self, String> {
+        match stat {
+            "avg" => Ok(RankStat::Avg),
+            "max" => Ok(RankStat::Max),
+            "sum" => Ok(RankStat::Sum),
+            "above" => Ok(RankStat::TimeAbove(threshold)),
+            _ => match stat.strip_prefix('p').map(|p| p.parse::<RangePrec>()) {
+                Some(Ok(p)) if (0.0..=100.0).contains(&p) => Ok(RankStat::Percentile(p)),
+                _ => Err(format!("Unknown statistic '{}'", stat)),
+            },
+        }
+    }
+
+    /// Value of the statistic for the points of a trace, `None` when there are no points. NaN and
+    /// infinite values are skipped.
+    pub fn evaluate<I: Iterator<Item = (RangePrec, RangePrec)>>(
+        &self,
+        points: I,
+    ) -> Option<RangePrec> {
+        let mut n = 0;
+        let mut acc = match self {
+            RankStat::Max => RangePrec::MIN,
+            _ => 0.0,
+        };
+        let mut values = vec![];
+        let mut prev: Option<(RangePrec, RangePrec)> = None;
+
+        for (x, y) in points.filter(|p| p.1.is_finite()) {
+            match self {
+                RankStat::Avg | RankStat::Sum => acc += y,
+                RankStat::Max => acc = acc.max(y),
+                RankStat::Percentile(_) => values.push(y),
+                RankStat::TimeAbove(threshold) => {
+                    if let Some((px, py)) = prev {
+                        if py >= *threshold {
+                            acc += x - px;
+                        }
+                    }
+
+                    prev = Some((x, y));
+                }
+            }
+
+            n += 1;
+        }
+
+        if n == 0 {
+            return None;
+        }
+
+        Some(match self {
+            RankStat::Avg => acc / n as RangePrec,
+            RankStat::Percentile(p) => {
+                sort_values(&mut values);
+                percentile(&values, *p)
+            }
+            _ => acc,
+        })
+    }
+}
+
+struct Ranked<T> {
+    key: RangePrec,
+    item: T,
+    value: RangePrec,
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.partial_cmp(&other.key).unwrap()
+    }
+}
+
+/// Keeps the `n` highest (or lowest, if not `top`) valued items, ordered from the best one.
+pub fn select_top<T, I: Iterator<Item = (T, RangePrec)>>(
+    items: I,
+    n: usize,
+    top: bool,
+) -> Vec<(T, RangePrec)> {
+    // The heap root is the worst of the kept items, so that it can be replaced cheaply
+    let sign = if top { -1.0 } else { 1.0 };
+    let mut heap = BinaryHeap::with_capacity(n + 1);
+
+    for (item, value) in items.filter(|(_, v)| !v.is_nan()) {
+        heap.push(Ranked {
+            key: value * sign,
+            item,
+            value,
+        });
+
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|r| (r.item, r.value))
+        .collect()
+}
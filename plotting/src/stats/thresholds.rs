@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::structs::RangePrec;
+
+#[wasm_bindgen]
+pub struct ThresholdParams {
+    /// Look for values at or below the threshold instead of at or above it.
+    pub below: bool,
+    /// Distance back across the threshold a value has to reach to end a violation.
+    pub hysteresis: RangePrec,
+    /// Violations shorter than this are dropped.
+    pub min_duration: RangePrec,
+}
+
+#[wasm_bindgen]
+impl ThresholdParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            below: false,
+            hysteresis: 0.0,
+            min_duration: 0.0,
+        }
+    }
+}
+
+impl Default for ThresholdParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Violation {
+    pub from: RangePrec,
+    /// First sample back within limits, or the last sample if the violation lasts till the end of the range.
+    pub to: RangePrec,
+    pub duration: RangePrec,
+    pub peak: RangePrec,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ThresholdReport {
+    pub intervals: Vec<Violation>,
+    pub total: RangePrec,
+}
+
+/// Non-finite values neither start, extend nor end a violation.
+pub fn threshold_intervals(
+    points: &[(RangePrec, RangePrec)],
+    threshold: RangePrec,
+    params: &ThresholdParams,
+) -> ThresholdReport {
+    // Mirror the values for `below`, so that the rest only deals with crossing upwards
+    let sign = if params.below { -1.0 } else { 1.0 };
+    let enter = threshold * sign;
+    let leave = enter - params.hysteresis.abs();
+
+    let mut intervals = vec![];
+    let mut current: Option<Violation> = None;
+
+    for &(x, y) in points.iter().filter(|(_, y)| y.is_finite()) {
+        let y = y * sign;
+
+        current = match current.take() {
+            None if y >= enter => Some(Violation {
+                from: x,
+                to: x,
+                duration: 0.0,
+                peak: y,
+            }),
+            Some(mut v) if y < leave => {
+                v.to = x;
+                intervals.push(v);
+                None
+            }
+            Some(mut v) => {
+                v.to = x;
+                v.peak = v.peak.max(y);
+                Some(v)
+            }
+            None => None,
+        };
+    }
+
+    intervals.extend(current);
+
+    let intervals: Vec<Violation> = intervals
+        .into_iter()
+        .map(|v| Violation {
+            duration: v.to - v.from,
+            peak: v.peak * sign,
+            ..v
+        })
+        .filter(|v| v.duration >= params.min_duration)
+        .collect();
+
+    ThresholdReport {
+        total: intervals.iter().map(|v| v.duration).sum(),
+        intervals,
+    }
+}
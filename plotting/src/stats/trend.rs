@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::structs::RangePrec;
+
+use super::{nominal_interval, resample, DATETIME_DAY};
+
+pub enum TrendMethod {
+    /// Least squares line with prediction intervals.
+    Linear,
+    /// Additive Holt-Winters with a season of `period`, degrades to Holt's linear method
+    /// when there are fewer than two seasons of data.
+    HoltWinters,
+}
+
+impl TrendMethod {
+    /// Parses `linear` or `holt_winters`.
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method {
+            "linear" => Ok(TrendMethod::Linear),
+            "holt_winters" => Ok(TrendMethod::HoltWinters),
+            _ => Err(format!("Unknown trend method '{}'", method)),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct TrendParams {
+    /// Forecast length in days of `datetime` minutes.
+    pub days: RangePrec,
+    /// Value whose crossing is estimated, `NaN` to skip the estimate.
+    pub threshold: RangePrec,
+    /// Half-width of the confidence band in residual standard deviations.
+    pub z: RangePrec,
+
+    pub period: RangePrec,
+    pub alpha: RangePrec,
+    pub beta: RangePrec,
+    pub gamma: RangePrec,
+}
+
+#[wasm_bindgen]
+impl TrendParams {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            days: 30.0,
+            threshold: RangePrec::NAN,
+            z: 1.96,
+
+            period: DATETIME_DAY,
+            alpha: 0.5,
+            beta: 0.1,
+            gamma: 0.3,
+        }
+    }
+}
+
+impl Default for TrendParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Forecast {
+    /// Fitted values over the input followed by the forecast, as `(x, y, lower, upper)`.
+    pub points: Vec<(RangePrec, RangePrec, RangePrec, RangePrec)>,
+    /// Change of the trend per x unit at the end of the input.
+    pub slope: RangePrec,
+    pub residual_std: RangePrec,
+    /// First x after the input where the forecast reaches the threshold.
+    pub crossing: Option<RangePrec>,
+}
+
+/// NaN and infinite values are left out of the fit, the seasonal model interpolates over them.
+pub fn forecast(
+    points: &[(RangePrec, RangePrec)],
+    method: &TrendMethod,
+    params: &TrendParams,
+) -> Forecast {
+    let points: Vec<(RangePrec, RangePrec)> =
+        points.iter().copied().filter(|p| p.1.is_finite()).collect();
+    let xs: Vec<RangePrec> = points.iter().map(|p| p.0).collect();
+    let step = nominal_interval(&xs);
+
+    if points.len() < 3 || step.is_nan() || step <= 0.0 {
+        return Forecast {
+            points: vec![],
+            slope: RangePrec::NAN,
+            residual_std: RangePrec::NAN,
+            crossing: None,
+        };
+    }
+
+    let last = xs[xs.len() - 1];
+    let steps = (params.days * DATETIME_DAY / step).floor() as usize;
+
+    let mut result = match method {
+        TrendMethod::Linear => linear(&points, last, step, steps, params),
+        TrendMethod::HoltWinters => holt_winters(&resample(&points, step), step, steps, params),
+    };
+
+    if !params.threshold.is_nan() {
+        let future = result.points.iter().skip_while(|p| p.0 <= last);
+        let mut prev = result
+            .points
+            .iter()
+            .rev()
+            .find(|p| p.0 <= last)
+            .map(|p| p.1);
+
+        for p in future {
+            if let Some(prev) = prev {
+                if (prev - params.threshold) * (p.1 - params.threshold) <= 0.0
+                    && prev != params.threshold
+                {
+                    // Interpolate between the forecast steps
+                    let t = (params.threshold - prev) / (p.1 - prev);
+                    result.crossing = Some(p.0 - step + t * step);
+                    break;
+                }
+            }
+
+            prev = Some(p.1);
+        }
+    }
+
+    result
+}
+
+fn linear(
+    points: &[(RangePrec, RangePrec)],
+    last: RangePrec,
+    step: RangePrec,
+    steps: usize,
+    params: &TrendParams,
+) -> Forecast {
+    let n = points.len() as RangePrec;
+    let x_mean = points.iter().map(|p| p.0).sum::<RangePrec>() / n;
+    let y_mean = points.iter().map(|p| p.1).sum::<RangePrec>() / n;
+
+    let sxx: RangePrec = points.iter().map(|p| (p.0 - x_mean).powi(2)).sum();
+    let sxy: RangePrec = points.iter().map(|p| (p.0 - x_mean) * (p.1 - y_mean)).sum();
+
+    let slope = sxy / sxx;
+    let fit = |x: RangePrec| y_mean + slope * (x - x_mean);
+
+    let sse: RangePrec = points.iter().map(|p| (p.1 - fit(p.0)).powi(2)).sum();
+    let residual_std = (sse / (n - 2.0)).sqrt();
+
+    let band = |x: RangePrec| {
+        params.z * residual_std * (1.0 + 1.0 / n + (x - x_mean).powi(2) / sxx).sqrt()
+    };
+
+    let future = (1..=steps).map(|i| last + step * i as RangePrec);
+
+    Forecast {
+        points: points
+            .iter()
+            .map(|p| p.0)
+            .chain(future)
+            .map(|x| (x, fit(x), fit(x) - band(x), fit(x) + band(x)))
+            .collect(),
+        slope,
+        residual_std,
+        crossing: None,
+    }
+}
+
+fn holt_winters(
+    points: &[(RangePrec, RangePrec)],
+    step: RangePrec,
+    steps: usize,
+    params: &TrendParams,
+) -> Forecast {
+    let ys: Vec<RangePrec> = points.iter().map(|p| p.1).collect();
+    let m = (params.period / step).round().max(1.0) as usize;
+    let seasonal = m > 1 && ys.len() >= 2 * m;
+    let m = if seasonal { m } else { 1 };
+
+    let mean = |s: &[RangePrec]| s.iter().sum::<RangePrec>() / s.len() as RangePrec;
+
+    // Classical initialization, trend from the first and last complete seasons and season
+    // components averaged over all complete seasons
+    let seasons = ys.len() / m;
+    let means: Vec<RangePrec> = ys.chunks_exact(m).map(mean).collect();
+
+    let mut level = means[0];
+    let mut trend = if seasonal {
+        (means[seasons - 1] - means[0]) / ((seasons - 1) * m) as RangePrec
+    } else {
+        ys[1] - ys[0]
+    };
+    let mut season: Vec<RangePrec> = if seasonal {
+        (0..m)
+            .map(|i| {
+                (0..seasons)
+                    .map(|j| {
+                        ys[j * m + i]
+                            - means[j]
+                            - trend * (i as RangePrec - (m - 1) as RangePrec / 2.0)
+                    })
+                    .sum::<RangePrec>()
+                    / seasons as RangePrec
+            })
+            .collect()
+    } else {
+        vec![0.0]
+    };
+
+    // The first season mean describes the middle of the season, move it to the series start
+    level -= trend * (m - 1) as RangePrec / 2.0;
+
+    let mut fitted = Vec::with_capacity(ys.len() + steps);
+    let mut sse = 0.0;
+
+    for (i, &y) in ys.iter().enumerate() {
+        let s = season[i % m];
+        let prediction = level + trend + s;
+
+        if i > 0 {
+            sse += (y - prediction).powi(2);
+        }
+
+        fitted.push(if i > 0 { prediction } else { y });
+
+        let prev_level = level;
+        level = params.alpha * (y - s) + (1.0 - params.alpha) * (level + trend);
+        trend = params.beta * (level - prev_level) + (1.0 - params.beta) * trend;
+
+        if seasonal {
+            season[i % m] = params.gamma * (y - level) + (1.0 - params.gamma) * s;
+        }
+    }
+
+    let residual_std = (sse / (ys.len() - 1) as RangePrec).sqrt();
+    let last = points[points.len() - 1].0;
+
+    let history = points.iter().zip(fitted.iter()).map(|(p, &f)| {
+        (
+            p.0,
+            f,
+            f - params.z * residual_std,
+            f + params.z * residual_std,
+        )
+    });
+
+    // The error of an h-step forecast grows roughly with the square root of h
+    let future = (1..=steps).map(|h| {
+        let y = level + trend * h as RangePrec + season[(ys.len() + h - 1) % m];
+        let band = params.z * residual_std * (h as RangePrec).sqrt();
+
+        (last + step * h as RangePrec, y, y - band, y + band)
+    });
+
+    Forecast {
+        points: history.chain(future).collect(),
+        slope: trend / step,
+        residual_std,
+        crossing: None,
+    }
+}
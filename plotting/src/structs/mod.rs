@@ -1,5 +1,5 @@
 mod render_job;
 mod tracedata;
 
-pub use render_job::RenderJob;
-pub use tracedata::{DataPrec, DataSegment, RangePrec, Segment, TraceData};
+pub use render_job::{Highlight, RenderJob, StackGroup, TraceStyle};
+pub use tracedata::{DataPrec, DataSegment, Interpolation, RangePrec, Segment, TraceData};
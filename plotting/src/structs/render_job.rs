@@ -2,7 +2,13 @@ use std::{collections::HashSet, convert::TryInto, mem::size_of};
 
 use wasm_bindgen::prelude::*;
 
-use crate::data::DataIdx;
+use crate::{
+    data::DataIdx,
+    renderers::scene::{
+        dash_pattern, Bars, DashPattern, Fill, LineCap, LineJoin, LineShape, Stroke,
+        DEFAULT_MITER_LIMIT,
+    },
+};
 
 use super::RangePrec;
 
@@ -24,9 +30,17 @@ pub struct RenderJob {
     pub x_label_space: u32,
     pub y_label_space: u32,
 
+    /// Longest miter join of traces as a multiple of their width.
+    pub miter_limit: f32,
+
+    line_join: LineJoin,
+    line_cap: LineCap,
+
     traces: Vec<TraceStyle>,
     bundles: Vec<usize>,
     bundle_blacklist: HashSet<usize>,
+    stack_groups: Vec<StackGroup>,
+    highlights: Vec<Highlight>,
 }
 
 #[wasm_bindgen]
@@ -50,19 +64,68 @@ impl RenderJob {
             x_label_space: 0,
             y_label_space: 0,
 
+            miter_limit: DEFAULT_MITER_LIMIT,
+
+            line_join: LineJoin::Round,
+            line_cap: LineCap::Butt,
+
             traces: Vec::with_capacity(trace_count),
             bundles: Vec::with_capacity(bundle_count),
             bundle_blacklist: HashSet::new(),
+            stack_groups: vec![],
+            highlights: vec![],
+        }
+    }
+
+    /// Joins of trace lines, `miter`, `round` or `bevel`. Other values are ignored.
+    #[wasm_bindgen(getter)]
+    pub fn line_join(&self) -> String {
+        self.line_join.name().to_owned()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_line_join(&mut self, join: &str) {
+        if let Ok(join) = LineJoin::parse(join) {
+            self.line_join = join;
         }
     }
 
-    pub fn add_trace(&mut self, idx: DataIdx, color: &[u8], width: u32, points_mode: bool) {
-        self.traces.push(TraceStyle {
+    /// Caps of trace lines, `butt`, `round` or `square`. Other values are ignored.
+    #[wasm_bindgen(getter)]
+    pub fn line_cap(&self) -> String {
+        self.line_cap.name().to_owned()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_line_cap(&mut self, cap: &str) {
+        if let Ok(cap) = LineCap::parse(cap) {
+            self.line_cap = cap;
+        }
+    }
+
+    /// `dash` holds up to four alternating dash and gap lengths in CSS pixels, empty for solid.
+    /// `shape` is one of `linear`, `step-before`, `step-after` or `step-mid`.
+    pub fn add_trace(
+        &mut self,
+        idx: DataIdx,
+        color: &[u8],
+        width: u32,
+        points_mode: bool,
+        dash: &[u8],
+        shape: &str,
+    ) -> Result<(), JsValue> {
+        self.add_trace_style(TraceStyle {
             idx,
             color: color.try_into().unwrap(),
             width,
             points_mode,
+            dash: dash_pattern(dash),
+            fill: Fill::None,
+            bars: None,
+            shape: LineShape::parse(shape)?,
         });
+
+        Ok(())
     }
 
     pub fn add_bundle(&mut self, idx: usize) {
@@ -73,16 +136,39 @@ impl RenderJob {
         self.bundle_blacklist.insert(handle);
     }
 
+    /// Stacks the traces in the given order on their common timestamps, bands between the
+    /// cumulative sums are filled with `alpha`. The traces keep their styles from the job.
+    pub fn add_stack_group(&mut self, handles: &[DataIdx], alpha: u8) {
+        self.stack_groups.push(StackGroup {
+            handles: handles.to_vec(),
+            alpha,
+        });
+    }
+
+    /// Marks the trace at the given x values, e.g. the peaks of anomalies, with larger markers
+    /// above all traces. The trace doesn't have to be drawn by the job.
+    pub fn add_highlights(&mut self, handle: DataIdx, xs: &[RangePrec], color: &[u8]) {
+        self.highlights.push(Highlight {
+            handle,
+            xs: xs.to_vec(),
+            color: color.try_into().unwrap(),
+        });
+    }
+
     pub fn deserialize_traces(&mut self, data: &[u8]) {
-        const TRACE_ROW_SIZE: usize = 2 * size_of::<u32>() + 4;
+        const TRACE_ROW_SIZE: usize = 2 * size_of::<u32>() + 32;
 
         for row in data.chunks_exact(TRACE_ROW_SIZE) {
-            self.add_trace(
-                usize::from_be_bytes(row[0..4].try_into().unwrap()),
-                &row[8..11],
-                u32::from_be_bytes(row[4..8].try_into().unwrap()),
-                row[11] > 0,
-            );
+            self.add_trace_style(TraceStyle {
+                idx: u32::from_be_bytes(row[0..4].try_into().unwrap()) as usize,
+                color: row[8..11].try_into().unwrap(),
+                width: u32::from_be_bytes(row[4..8].try_into().unwrap()),
+                points_mode: row[11] > 0,
+                dash: dash_pattern(&row[12..16]),
+                fill: Fill::from_row(&row[16..24]),
+                bars: Bars::from_row(&row[24..36]),
+                shape: LineShape::from_byte(row[36]),
+            });
         }
     }
 
@@ -90,13 +176,17 @@ impl RenderJob {
         const ROW_SIZE: usize = size_of::<u32>();
 
         for row in data.chunks_exact(ROW_SIZE) {
-            self.blacklist_trace(usize::from_be_bytes(row.try_into().unwrap()));
+            self.blacklist_trace(u32::from_be_bytes(row.try_into().unwrap()) as usize);
         }
     }
 }
 
 // unbound methods
 impl RenderJob {
+    pub fn add_trace_style(&mut self, style: TraceStyle) {
+        self.traces.push(style);
+    }
+
     pub fn get_traces(&self) -> &Vec<TraceStyle> {
         &self.traces
     }
@@ -105,10 +195,29 @@ impl RenderJob {
         &self.bundles
     }
 
+    pub fn get_stack_groups(&self) -> &Vec<StackGroup> {
+        &self.stack_groups
+    }
+
+    pub fn get_highlights(&self) -> &Vec<Highlight> {
+        &self.highlights
+    }
+
     pub fn get_x_type(&self) -> &String {
         &self.x_type
     }
 
+    /// How traces of the given width and dash pattern are stroked.
+    pub fn stroke(&self, width: u32, dash: DashPattern) -> Stroke {
+        Stroke {
+            width: width as f32,
+            join: self.line_join,
+            cap: self.line_cap,
+            miter_limit: self.miter_limit,
+            dash,
+        }
+    }
+
     pub fn is_blacklisted(&self, handle: DataIdx) -> bool {
         self.bundle_blacklist.contains(&handle)
     }
@@ -120,4 +229,21 @@ pub struct TraceStyle {
     pub color: [u8; 3],
     pub width: u32,
     pub points_mode: bool,
+    pub dash: DashPattern,
+    pub fill: Fill,
+    /// Drawn as bars instead of a line when set.
+    pub bars: Option<Bars>,
+    /// Steps of the line and its fill, stacks and bars ignore it.
+    pub shape: LineShape,
+}
+
+pub struct StackGroup {
+    pub handles: Vec<DataIdx>,
+    pub alpha: u8,
+}
+
+pub struct Highlight {
+    pub handle: DataIdx,
+    pub xs: Vec<RangePrec>,
+    pub color: [u8; 3],
 }
@@ -1,9 +1,60 @@
 pub type RangePrec = f64;
 pub type DataPrec = f32;
 
+type Point = (RangePrec, RangePrec);
+
+/// How values between samples are read out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Interpolation {
+    Linear,
+    /// Each sample's value holds since the previous sample.
+    StepBefore,
+    /// Each sample's value holds until the next sample, i.e. last observation carried forward.
+    StepAfter,
+    Nearest,
+}
+
+impl Interpolation {
+    /// Parses `linear`, `step-before`, `step-after` or `nearest`.
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "linear" => Ok(Interpolation::Linear),
+            "step-before" => Ok(Interpolation::StepBefore),
+            "step-after" => Ok(Interpolation::StepAfter),
+            "nearest" => Ok(Interpolation::Nearest),
+            _ => Err(format!("Unknown interpolation '{}'", mode)),
+        }
+    }
+
+    pub fn interpolate(
+        self,
+        x: RangePrec,
+        left: Option<Point>,
+        right: Option<Point>,
+    ) -> Option<RangePrec> {
+        match (self, left, right) {
+            (_, Some(l), _) if l.0 == x => Some(l.1),
+            (_, _, Some(r)) if r.0 == x => Some(r.1),
+            (Interpolation::Linear, Some(l), Some(r)) => {
+                Some(((r.0 - x) * l.1 + (x - l.0) * r.1) / (r.0 - l.0))
+            }
+            (Interpolation::StepBefore, _, Some(r)) => Some(r.1),
+            (Interpolation::StepAfter, Some(l), _) => Some(l.1),
+            (Interpolation::Nearest, Some(l), Some(r)) => {
+                Some(if x - l.0 <= r.0 - x { l.1 } else { r.1 })
+            }
+            (Interpolation::Nearest, Some(p), None) | (Interpolation::Nearest, None, Some(p)) => {
+                Some(p.1)
+            }
+            _ => None,
+        }
+    }
+}
+
 pub struct TraceData {
     pub id: String,
     pub x_type: String,
+    pub interpolation: Interpolation,
 
     pub segments: Vec<Box<dyn Segment>>,
 }
@@ -13,6 +64,11 @@ impl TraceData {
         &self.x_type
     }
 
+    /// Type of the stored values, `None` until a segment is loaded.
+    pub fn get_y_type(&self) -> Option<&'static str> {
+        self.segments.first().map(|s| s.y_type())
+    }
+
     // fn get_segments(&self) -> &Vec<Box<dyn Segment>> {
     //     &self.segments
     // }
@@ -27,6 +83,52 @@ impl TraceData {
             .filter(move |&x| x.intersects(from, to))
     }
 
+    /// Loaded x ranges, adjacent and overlapping segments are merged.
+    pub fn get_loaded_ranges(&self) -> Vec<(RangePrec, RangePrec)> {
+        let mut ranges: Vec<(RangePrec, RangePrec)> = vec![];
+
+        for seg in &self.segments {
+            match ranges.last_mut() {
+                Some(last) if seg.from() <= last.1 => last.1 = last.1.max(seg.to()),
+                _ => ranges.push((seg.from(), seg.to())),
+            }
+        }
+
+        ranges
+    }
+
+    /// Parts of `from..to` not covered by any segment, none for a range with a `NaN` bound.
+    pub fn get_missing_ranges(&self, from: RangePrec, to: RangePrec) -> Vec<(RangePrec, RangePrec)> {
+        if from.is_nan() || to.is_nan() {
+            return vec![];
+        }
+
+        let mut ranges = vec![];
+        let mut cursor = from;
+
+        for (seg_from, seg_to) in self.get_loaded_ranges() {
+            if seg_to <= cursor {
+                continue;
+            }
+
+            if seg_from >= to {
+                break;
+            }
+
+            if seg_from > cursor {
+                ranges.push((cursor, seg_from));
+            }
+
+            cursor = seg_to;
+        }
+
+        if cursor < to {
+            ranges.push((cursor, to));
+        }
+
+        ranges
+    }
+
     pub fn get_data_in<'a>(
         &'a self,
         from: RangePrec,
@@ -46,17 +148,31 @@ impl TraceData {
     }
 
     pub fn get_data_at(&self, x: RangePrec) -> Option<RangePrec> {
-        let seg = self
-            .segments
-            .iter()
-            .find(|s| s.contains(x))
-            .map(|s| s.value_at(x));
+        self.get_data_at_with(x, self.interpolation)
+    }
+
+    /// Value at `x` if it lies in a loaded range, neighbouring samples are looked up
+    /// across all segments touching the range. `NaN` samples are gaps without a value.
+    pub fn get_data_at_with(&self, x: RangePrec, mode: Interpolation) -> Option<RangePrec> {
+        let idx = self.segments.iter().position(|s| s.contains(x))?;
 
-        if seg.is_none() {
-            None
-        } else {
-            seg.unwrap()
+        let mut first = idx;
+        while first > 0 && self.segments[first - 1].to() >= self.segments[first].from() {
+            first -= 1;
         }
+
+        let mut last = idx;
+        while last + 1 < self.segments.len()
+            && self.segments[last].to() >= self.segments[last + 1].from()
+        {
+            last += 1;
+        }
+
+        let run = &self.segments[first..=last];
+        let left = run.iter().rev().find_map(|s| s.neighbours(x).0);
+        let right = run.iter().find_map(|s| s.neighbours(x).1);
+
+        mode.interpolate(x, left, right).filter(|y| !y.is_nan())
     }
 
     pub fn get_data_with_origin<'a>(
@@ -115,13 +231,17 @@ pub struct DataSegment<X, Y> {
 }
 
 pub trait SegmentNumeric {
+    const TYPE_NAME: &'static str;
+
     fn to_rangeprec(self) -> RangePrec;
     fn to_dataprec(self) -> DataPrec;
 }
 
 macro_rules! impl_segment {
-    ($t:ty) => {
+    ($t:ty, $n:expr) => {
         impl SegmentNumeric for $t {
+            const TYPE_NAME: &'static str = $n;
+
             fn to_rangeprec(self) -> RangePrec {
                 self as RangePrec
             }
@@ -132,21 +252,22 @@ macro_rules! impl_segment {
     };
 }
 
-impl_segment!(f32);
-impl_segment!(f64);
+impl_segment!(f32, "float");
+impl_segment!(f64, "double");
 
-impl_segment!(i16);
-impl_segment!(i32);
-impl_segment!(i64);
+impl_segment!(i16, "short");
+impl_segment!(i32, "int");
+impl_segment!(i64, "long");
 
-impl_segment!(u8);
-impl_segment!(u16);
-impl_segment!(u32);
-impl_segment!(u64);
+impl_segment!(u8, "byte");
+impl_segment!(u16, "ushort");
+impl_segment!(u32, "uint");
+impl_segment!(u64, "ulong");
 
 pub trait Segment {
     fn from(&self) -> RangePrec;
     fn to(&self) -> RangePrec;
+    fn y_type(&self) -> &'static str;
 
     fn contains(&self, point: RangePrec) -> bool;
     fn intersects(&self, from: RangePrec, to: RangePrec) -> bool;
@@ -168,7 +289,8 @@ pub trait Segment {
         to: RangePrec,
     ) -> Box<dyn Iterator<Item = (RangePrec, RangePrec)> + 'a>;
 
-    fn value_at(&self, x: RangePrec) -> Option<RangePrec>;
+    /// Last sample at or before `x` and first sample at or after it.
+    fn neighbours(&self, x: RangePrec) -> (Option<Point>, Option<Point>);
 
     fn shrink(&mut self, from: RangePrec, to: RangePrec);
 }
@@ -231,25 +353,17 @@ impl<X: SegmentNumeric + Copy, Y: SegmentNumeric + Copy> Segment for DataSegment
         )
     }
 
-    fn value_at(&self, x: RangePrec) -> Option<RangePrec> {
-        if !self.contains(x) {
-            return None;
-        }
-
-        for window in self.data.windows(2) {
-            let left = window[0];
-            let right = window[1];
+    fn neighbours(&self, x: RangePrec) -> (Option<Point>, Option<Point>) {
+        let split = self.data.partition_point(|(px, _)| px.to_rangeprec() < x);
+        let point = |p: &(X, Y)| (p.0.to_rangeprec(), p.1.to_rangeprec());
 
-            if left.0.to_rangeprec() <= x && right.0.to_rangeprec() >= x {
-                return Some(
-                    ((right.0.to_rangeprec() - x) * left.1.to_rangeprec()
-                        + (x - left.0.to_rangeprec()) * right.1.to_rangeprec())
-                        / (right.0.to_rangeprec() - left.0.to_rangeprec()),
-                );
-            }
-        }
+        let right = self.data.get(split).map(point);
+        let left = match right {
+            Some(r) if r.0 == x => right,
+            _ => split.checked_sub(1).map(|i| point(&self.data[i])),
+        };
 
-        None
+        (left, right)
     }
 
     fn from(&self) -> RangePrec {
@@ -260,6 +374,10 @@ impl<X: SegmentNumeric + Copy, Y: SegmentNumeric + Copy> Segment for DataSegment
         self.to
     }
 
+    fn y_type(&self) -> &'static str {
+        Y::TYPE_NAME
+    }
+
     fn shrink(&mut self, from: RangePrec, to: RangePrec) {
         self.from = from;
         self.to = to;
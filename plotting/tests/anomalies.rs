@@ -0,0 +1,152 @@
+mod common;
+
+use plotting::data;
+use plotting::renderers::scene::{Primitive, Vertices, HIGHLIGHT_SIZE};
+use plotting::renderers::{Bundles, Scene};
+use plotting::stats::{AnomalyMethod, AnomalyParams};
+use plotting::structs::RenderJob;
+
+fn noisy(i: i32) -> f64 {
+    10.0 + ((i * 7919) % 13) as f64 * 0.1
+}
+
+#[test]
+fn spikes_by_rolling_methods() {
+    let ptr = common::load_sampled("spiky", 300, 60, |i| {
+        if (150..153).contains(&i) {
+            50.0
+        } else {
+            noisy(i)
+        }
+    });
+
+    for method in &[AnomalyMethod::ZScore, AnomalyMethod::Mad] {
+        let found = data::calc_anomalies(ptr, 0.0, 18_000.0, method, &AnomalyParams::new());
+
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(found[0].from, 9000.0);
+        assert!(found[0].to >= 9000.0 && found[0].to <= 9120.0);
+    }
+}
+
+#[test]
+fn seasonal_residual() {
+    let period = 24;
+    let ptr = common::load_sampled("seasonal", period * 10, 60, |i| {
+        let season = if i % period < 8 { 100.0 } else { 0.0 };
+        let spike = if i == period * 5 + 12 { 60.0 } else { 0.0 };
+
+        season + spike + noisy(i)
+    });
+
+    let params = AnomalyParams::new();
+
+    let found = data::calc_anomalies(ptr, 0.0, 1e9, &AnomalyMethod::Seasonal, &params);
+
+    assert_eq!(found.len(), 1, "{:?}", found);
+    assert_eq!(found[0].peak, ((period * 5 + 12) * 60) as f64);
+}
+
+#[test]
+fn gaps_are_skipped() {
+    let ptr = common::load_sampled("spiky_gaps", 300, 60, |i| match i {
+        40 | 200..=202 => f64::NAN,
+        220 => f64::INFINITY,
+        150 => 50.0,
+        _ => noisy(i),
+    });
+
+    let mut params = AnomalyParams::new();
+    params.period = 600.0;
+
+    for method in &[
+        AnomalyMethod::ZScore,
+        AnomalyMethod::Mad,
+        AnomalyMethod::Seasonal,
+    ] {
+        let found = data::calc_anomalies(ptr, 0.0, 18_000.0, method, &params);
+
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(found[0].peak, 9000.0);
+    }
+
+    let empty = common::load_trace("spiky_empty", &[]);
+    assert!(data::calc_anomalies(empty, 0.0, 1e9, &AnomalyMethod::Mad, &params).is_empty());
+}
+
+#[test]
+fn flat_and_out_of_range() {
+    let flat = common::load_sampled("flat", 300, 60, |_| 10.0);
+    let spiky = common::load_sampled("spiky_range", 300, 60, |i| {
+        if i == 150 {
+            50.0
+        } else {
+            noisy(i)
+        }
+    });
+
+    for method in &[
+        AnomalyMethod::ZScore,
+        AnomalyMethod::Mad,
+        AnomalyMethod::Seasonal,
+    ] {
+        let params = AnomalyParams::new();
+
+        assert!(data::calc_anomalies(flat, 0.0, 18_000.0, method, &params).is_empty());
+        assert!(data::calc_anomalies(spiky, 20_000.0, 40_000.0, method, &params).is_empty());
+        assert!(data::calc_anomalies(spiky, 9000.0, 9000.0, method, &params).is_empty());
+    }
+}
+
+#[test]
+fn parse_method() {
+    assert!(matches!(
+        AnomalyMethod::parse("mad"),
+        Ok(AnomalyMethod::Mad)
+    ));
+    assert!(AnomalyMethod::parse("iqr").is_err());
+}
+
+#[test]
+fn peaks_as_markers() {
+    let ptr = common::load_sampled("highlighted", 300, 60, |i| {
+        if i == 150 {
+            50.0
+        } else {
+            noisy(i)
+        }
+    });
+
+    let found = data::calc_anomalies(
+        ptr,
+        0.0,
+        18_000.0,
+        &AnomalyMethod::Mad,
+        &AnomalyParams::new(),
+    );
+    let mut peaks: Vec<f64> = found.iter().map(|a| a.peak).collect();
+    assert_eq!(peaks, vec![9000.0]);
+
+    // Peaks outside of the job range are left out
+    peaks.push(20_000.0);
+
+    let mut job = RenderJob::new("datetime".to_owned(), 0, 0);
+    job.x_from = 6000.0;
+    job.x_to = 12_000.0;
+    job.y_from = 0.0;
+    job.y_to = 60.0;
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_highlights(ptr, &peaks, &[255, 0, 0]);
+
+    let scene = Scene::compile(&job, 200.0, 100.0, &Bundles::<()>::default());
+    assert_eq!(scene.commands.len(), 1);
+
+    match &scene.commands[0].primitive {
+        Primitive::Markers { vertices, size, .. } => {
+            assert_eq!(vertices, &Vertices::Owned(vec![(3000.0, 50.0)]));
+            assert_eq!(*size, HIGHLIGHT_SIZE);
+        }
+        other => panic!("expected markers, got {:?}", other),
+    }
+}
@@ -4,7 +4,7 @@ use plotting::data;
 fn add_segments() {
     let ptr = data::create_trace("test", "datetime");
 
-    &data::get_trace_once(ptr, |deref| {
+    data::get_trace_once(ptr, |deref| {
         assert_eq!(deref.id, "test");
         // assert_eq!(deref.get_segments().is_empty(), true);
     });
@@ -0,0 +1,113 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use plotting::data;
+use plotting::stats::{bucket_start, bucketize, parse_tz, Aggregate, BucketSize};
+
+fn minutes(y: i32, m: u32, d: u32, h: u32, min: u32) -> f64 {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0)
+        .unwrap()
+        .timestamp() as f64
+        / 60.0
+}
+
+#[test]
+fn parse_sizes() {
+    assert_eq!(BucketSize::parse("5m"), Ok(BucketSize::Minutes(5)));
+    assert_eq!(BucketSize::parse("weekly"), Ok(BucketSize::Weeks(1)));
+    assert_eq!(BucketSize::parse("3M"), Ok(BucketSize::Months(3)));
+    assert!(BucketSize::parse("0d").is_err());
+    assert!(BucketSize::parse("d").is_err());
+
+    // Buckets that would straddle the hour, day or year
+    assert_eq!(BucketSize::parse("15m"), Ok(BucketSize::Minutes(15)));
+    assert!(BucketSize::parse("90m").is_err());
+    assert!(BucketSize::parse("7m").is_err());
+    assert!(BucketSize::parse("5h").is_err());
+    assert!(BucketSize::parse("5M").is_err());
+}
+
+#[test]
+fn calendar_starts() {
+    let prague = parse_tz("Europe/Prague").unwrap();
+    let x = minutes(2021, 7, 15, 13, 47);
+
+    assert_eq!(
+        bucket_start(x, BucketSize::Minutes(5), prague),
+        minutes(2021, 7, 15, 13, 45)
+    );
+    assert_eq!(
+        bucket_start(x, BucketSize::Days(1), prague),
+        minutes(2021, 7, 14, 22, 0)
+    );
+    assert_eq!(
+        bucket_start(x, BucketSize::Weeks(1), prague),
+        minutes(2021, 7, 11, 22, 0)
+    );
+    assert_eq!(
+        bucket_start(x, BucketSize::Months(1), prague),
+        minutes(2021, 6, 30, 22, 0)
+    );
+}
+
+#[test]
+fn daily_sums_across_dst() {
+    // Quarter-hourly samples from March 27th to March 29th local time, clocks go forward on the 28th
+    let from = minutes(2021, 3, 26, 23, 0);
+    let to = minutes(2021, 3, 29, 22, 0);
+    let rows: Vec<(i32, f64)> = (0..)
+        .map(|i| from as i32 + i * 15)
+        .take_while(|x| (*x as f64) < to)
+        .map(|x| (x, 1.0))
+        .collect();
+
+    let ptr = common::load_trace("quarters", &rows);
+    let out = data::create_trace("daily", "datetime");
+
+    data::bucket_trace(out, ptr, from, to, "1d", "Europe/Prague", "sum").unwrap();
+
+    let days: Vec<(f64, f64)> =
+        data::get_trace_ret(out, |t| t.get_data_high_prec(from, to).collect());
+
+    assert_eq!(
+        days,
+        vec![
+            (minutes(2021, 3, 26, 23, 0), 96.0),
+            (minutes(2021, 3, 27, 23, 0), 92.0),
+            (minutes(2021, 3, 28, 22, 0), 96.0),
+        ]
+    );
+}
+
+#[test]
+fn aggregates() {
+    assert_eq!(Aggregate::parse("max"), Ok(Aggregate::Max));
+    assert_eq!(Aggregate::parse("p95"), Ok(Aggregate::Percentile(95.0)));
+
+    // Ranking statistics have no meaning per bucket
+    assert!(Aggregate::parse("above").is_err());
+    assert!(Aggregate::parse("p").is_err());
+    assert!(Aggregate::parse("p120").is_err());
+}
+
+#[test]
+fn non_finite_and_empty() {
+    let utc = parse_tz("UTC").unwrap();
+    let points = [
+        (0.0, 1.0),
+        (1.0, f64::NAN),
+        (2.0, 3.0),
+        (5.0, f64::NAN),
+        (6.0, f64::INFINITY),
+        (10.0, 4.0),
+    ];
+
+    // Buckets with no finite value are left out
+    assert_eq!(
+        bucketize(&points, BucketSize::Minutes(5), utc, Aggregate::Avg),
+        vec![(0.0, 2.0), (10.0, 4.0)]
+    );
+    assert!(bucketize(&[], BucketSize::Days(1), utc, Aggregate::Sum).is_empty());
+
+    assert!(parse_tz("Europe/Atlantis").is_err());
+}
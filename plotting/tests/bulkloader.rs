@@ -0,0 +1,172 @@
+use plotting::data;
+use plotting::ingest::{BulkLoader, DedupPolicy};
+
+fn payload(rows: &[(i32, f32)]) -> Vec<u8> {
+    rows.iter()
+        .flat_map(|(x, y)| {
+            x.to_le_bytes()
+                .iter()
+                .chain(y.to_le_bytes().iter())
+                .cloned()
+                .collect::<Vec<u8>>()
+        })
+        .collect()
+}
+
+fn points(ptr: data::DataIdx) -> Vec<(f64, f64)> {
+    data::get_trace_ret(ptr, |t| t.get_data_high_prec(-1000.0, 1000.0).collect())
+}
+
+#[test]
+fn chunks_match_bulkload() {
+    let rows: Vec<(i32, f32)> = (0..100).map(|i| (i, (i * 2) as f32)).collect();
+    let data = payload(&rows);
+
+    let whole = data::create_trace("loader::whole", "datetime");
+    let expected = data::bulkload(
+        &[whole],
+        "datetime",
+        "float",
+        &data,
+        DedupPolicy::Last,
+        None,
+    )
+    .unwrap();
+
+    let streamed = data::create_trace("loader::streamed", "datetime");
+    let mut loader = BulkLoader::new(
+        &[streamed],
+        "datetime",
+        "float",
+        Some(data.len()),
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    for (i, chunk) in data.chunks(7).enumerate() {
+        loader.write(chunk).unwrap();
+
+        if i % 10 == 9 {
+            loader.flush();
+        }
+    }
+
+    assert_eq!(loader.received(), data.len());
+    assert_eq!(loader.rows(), 100);
+    assert_eq!(loader.progress(), 1.0);
+
+    let report = loader.close().unwrap();
+
+    assert_eq!(report, expected);
+    assert_eq!(points(streamed), points(whole));
+    assert_eq!(
+        data::get_trace_ret(streamed, |t| t.get_loaded_ranges()),
+        vec![(0.0, 99.0)]
+    );
+}
+
+#[test]
+fn partial_rows_and_late_data() {
+    let ptr = data::create_trace("loader::late", "datetime");
+    let mut loader = BulkLoader::new(
+        &[ptr],
+        "datetime",
+        "float",
+        None,
+        Some("max".to_owned()),
+        None,
+        None,
+    )
+    .unwrap();
+
+    let first = payload(&[(0, 1.0), (1, 2.0), (2, 3.0)]);
+    assert!(!loader.write(&first[..10]).unwrap());
+    assert_eq!(loader.rows(), 1);
+    assert!(loader.progress().is_nan());
+
+    loader.write(&first[10..]).unwrap();
+    assert!(loader.flush());
+
+    // Row 2 was held back, so its duplicate is still merged, row 1 was already pushed
+    let mut second = payload(&[(2, 5.0), (1, 7.0), (3, 4.0)]);
+    second.extend_from_slice(&[1, 2, 3]);
+    loader.write(&second).unwrap();
+
+    let report = loader.close().unwrap();
+
+    assert_eq!(report.rows, 6);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.late, 1);
+    assert_eq!(report.trailing_bytes, 3);
+    assert_eq!(
+        points(ptr),
+        vec![(0.0, 1.0), (1.0, 2.0), (2.0, 5.0), (3.0, 4.0)]
+    );
+}
+
+#[test]
+fn requested_range() {
+    let ptr = data::create_trace("loader::range", "datetime");
+    let mut loader = BulkLoader::new(
+        &[ptr],
+        "datetime",
+        "float",
+        None,
+        None,
+        Some(-10.0),
+        Some(10.0),
+    )
+    .unwrap();
+
+    loader
+        .write(&payload(&[(0, 1.0), (1, 2.0), (2, 3.0)]))
+        .unwrap();
+    loader.flush();
+    loader.close().unwrap();
+
+    let mut empty = BulkLoader::new(
+        &[ptr],
+        "datetime",
+        "float",
+        None,
+        None,
+        Some(10.0),
+        Some(20.0),
+    )
+    .unwrap();
+    assert_eq!(empty.close().unwrap().rows, 0);
+
+    assert_eq!(
+        data::get_trace_ret(ptr, |t| t.get_loaded_ranges()),
+        vec![(-10.0, 20.0)]
+    );
+    assert_eq!(points(ptr), vec![(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)]);
+}
+
+#[test]
+fn empty_chunks_and_sizes() {
+    let ptr = data::create_trace("loader::empty", "datetime");
+    let mut loader =
+        BulkLoader::new(&[ptr], "datetime", "float", Some(8), None, None, None).unwrap();
+
+    assert!(!loader.write(&[]).unwrap());
+    assert!(!loader.flush());
+    assert_eq!(loader.progress(), 0.0);
+
+    // More bytes than announced
+    loader.write(&payload(&[(0, 1.0), (1, f32::NAN)])).unwrap();
+    assert_eq!(loader.progress(), 1.0);
+
+    let report = loader.close().unwrap();
+    assert_eq!(report.rows, 2);
+    assert_eq!(report.nan, 1);
+
+    let unknown = data::create_trace("loader::unknown_size", "datetime");
+    let mut loader =
+        BulkLoader::new(&[unknown], "datetime", "float", Some(0), None, None, None).unwrap();
+    assert!(loader.progress().is_nan());
+    assert_eq!(loader.close().unwrap().rows, 0);
+    assert!(data::get_trace_ret(unknown, |t| t.get_loaded_ranges()).is_empty());
+}
@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+
+use plotting::data::{self, DataIdx};
+use plotting::structs::DataSegment;
+
+pub fn load_trace(id: &str, rows: &[(i32, f64)]) -> DataIdx {
+    let ptr = data::create_trace(id, "datetime");
+
+    data::get_trace_once(ptr, |trace| {
+        trace.push_segment(Box::new(DataSegment::<i32, f64> {
+            from: rows.first().map_or(0.0, |r| r.0 as f64),
+            to: rows.last().map_or(0.0, |r| r.0 as f64),
+            data: rows.to_vec(),
+        }))
+    });
+
+    ptr
+}
+
+/// Loads `count` rows `step` apart starting at zero, `value` gets the index of the row.
+pub fn load_sampled(id: &str, count: i32, step: i32, value: impl Fn(i32) -> f64) -> DataIdx {
+    let rows: Vec<(i32, f64)> = (0..count).map(|i| (i * step, value(i))).collect();
+
+    load_trace(id, &rows)
+}
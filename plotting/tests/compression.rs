@@ -0,0 +1,149 @@
+use std::io::Write;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use plotting::data;
+use plotting::ingest::{decompress, BulkLoader, Compression, Decompressor, DedupPolicy};
+
+fn payload() -> Vec<u8> {
+    (0..2000i32)
+        .flat_map(|i| {
+            let mut row = i.to_le_bytes().to_vec();
+            row.extend_from_slice(&((i % 7) as f32).to_le_bytes());
+            row
+        })
+        .collect()
+}
+
+fn compressed() -> Vec<(Compression, Vec<u8>)> {
+    let raw = payload();
+
+    let mut gzip = GzEncoder::new(vec![], flate2::Compression::default());
+    gzip.write_all(&raw).unwrap();
+
+    let mut zlib = ZlibEncoder::new(vec![], flate2::Compression::default());
+    zlib.write_all(&raw).unwrap();
+
+    vec![
+        (Compression::None, raw.clone()),
+        (Compression::Gzip, gzip.finish().unwrap()),
+        (Compression::Zlib, zlib.finish().unwrap()),
+        (
+            Compression::Zstd,
+            ruzstd::encoding::compress_to_vec(
+                &raw[..],
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ),
+        ),
+    ]
+}
+
+#[test]
+fn detect_and_decompress() {
+    let raw = payload();
+
+    for (compression, data) in compressed() {
+        assert_eq!(Compression::detect(&data), compression);
+        assert_eq!(&*decompress(&data).unwrap(), &raw[..], "{:?}", compression);
+    }
+
+    // Rows that merely look like a zlib header are kept as they are
+    let fake = [0x78, 0x01, 0, 0, 0, 0, 0, 0];
+    assert_eq!(Compression::detect(&fake), Compression::Zlib);
+    assert_eq!(&*decompress(&fake).unwrap(), &fake[..]);
+
+    // So do rows starting with the gzip magic, unless their header parses
+    let fake = [0x1f, 0x8b, 0x08, 0xff, 0, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(Compression::detect(&fake), Compression::Gzip);
+    assert_eq!(&*decompress(&fake).unwrap(), &fake[..]);
+    assert_eq!(Compression::detect(&[0x1f, 0x8b, 0, 1]), Compression::None);
+
+    let (_, mut gzip) = compressed().swap_remove(1);
+    gzip.truncate(gzip.len() / 2);
+    assert!(decompress(&gzip).is_err());
+}
+
+#[test]
+fn stream_gzip_lookalike() {
+    // Reserved flag bits set, the header never parses
+    let rows: Vec<u8> = [0x1f, 0x8b, 0x08, 0xe0]
+        .iter()
+        .copied()
+        .cycle()
+        .take(40)
+        .collect();
+
+    for size in [1, 3, 40] {
+        let mut decompressor = Decompressor::default();
+        let mut out = vec![];
+
+        for chunk in rows.chunks(size) {
+            decompressor.write(chunk, &mut out).unwrap();
+        }
+
+        decompressor.finish(&mut out).unwrap();
+        assert_eq!(out, rows, "chunks of {}", size);
+    }
+
+    // A payload shorter than a header is passed on too
+    let mut decompressor = Decompressor::default();
+    let mut out = vec![];
+    decompressor.write(&rows[..6], &mut out).unwrap();
+    decompressor.finish(&mut out).unwrap();
+    assert_eq!(out, &rows[..6]);
+}
+
+#[test]
+fn load_compressed() {
+    for (compression, data) in compressed() {
+        let whole = data::create_trace(&format!("compressed::{:?}", compression), "datetime");
+        let report = data::bulkload(
+            &[whole],
+            "datetime",
+            "float",
+            &data,
+            DedupPolicy::Last,
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.rows, 2000);
+
+        let streamed = data::create_trace(&format!("streamed::{:?}", compression), "datetime");
+        let mut loader =
+            BulkLoader::new(&[streamed], "datetime", "float", None, None, None, None).unwrap();
+
+        for chunk in data.chunks(3) {
+            loader.write(chunk).unwrap();
+        }
+
+        assert_eq!(loader.close().unwrap(), report);
+
+        let points = |ptr| {
+            data::get_trace_ret(ptr, |t| {
+                t.get_data_high_prec(0.0, 2000.0).collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(points(streamed), points(whole), "{:?}", compression);
+    }
+}
+
+#[test]
+fn empty_and_truncated() {
+    assert_eq!(Compression::detect(&[]), Compression::None);
+    assert!(decompress(&[]).unwrap().is_empty());
+
+    let mut gzip = GzEncoder::new(vec![], flate2::Compression::default());
+    gzip.write_all(&[]).unwrap();
+    assert!(decompress(&gzip.finish().unwrap()).unwrap().is_empty());
+
+    // Streams cut short fail once the payload is finished
+    for (compression, data) in compressed().into_iter().skip(1) {
+        let mut decompressor = Decompressor::default();
+        let mut out = vec![];
+
+        for chunk in data[..data.len() / 2].chunks(64) {
+            decompressor.write(chunk, &mut out).unwrap();
+        }
+
+        assert!(decompressor.finish(&mut out).is_err(), "{:?}", compression);
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::{self, ranks, Correlation};
+
+#[test]
+fn align_on_common_timestamps() {
+    let (xs, ys) = stats::align(&[
+        vec![(0.0, 1.0), (1.0, 2.0), (2.0, 3.0), (4.0, 5.0)],
+        vec![(1.0, 20.0), (2.0, 30.0), (3.0, 40.0), (4.0, 50.0)],
+    ]);
+
+    assert_eq!(xs, vec![1.0, 2.0, 4.0]);
+    assert_eq!(ys, vec![vec![2.0, 3.0, 5.0], vec![20.0, 30.0, 50.0]]);
+}
+
+#[test]
+fn spearman_ranks_ties() {
+    assert_eq!(ranks(&[10.0, 20.0, 10.0, 5.0]), vec![2.5, 4.0, 2.5, 1.0]);
+}
+
+#[test]
+fn matrix_and_lag() {
+    let wave = |i: i32| ((i as f64) * 0.3).sin();
+
+    let a = common::load_sampled("a", 200, 60, wave);
+    let b = common::load_sampled("b", 200, 60, |i| -2.0 * wave(i));
+    let c = common::load_sampled("c", 200, 60, |i| wave(i - 3));
+
+    let matrix = data::calc_correlation(&[a, b, c], 0.0, 12_000.0, &Correlation::Pearson, 5);
+
+    assert!((matrix.coefficients[0][0] - 1.0).abs() < 1e-9);
+    assert!((matrix.coefficients[0][1] + 1.0).abs() < 1e-9);
+
+    let lags = matrix.lags.unwrap();
+    assert_eq!(lags[0][2].0, 3);
+    assert!((lags[0][2].1 - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn skips_non_finite() {
+    let a = vec![1.0, 2.0, f64::NAN, 3.0, 4.0];
+    let b = vec![2.0, 4.0, 5.0, f64::INFINITY, 8.0];
+
+    let matrix = stats::CorrelationMatrix::new(&[a, b], &Correlation::Spearman, 0);
+    assert!((matrix.coefficients[0][1] - 1.0).abs() < 1e-9);
+
+    assert_eq!(ranks(&[f64::NAN, 1.0]), vec![2.0, 1.0]);
+}
+
+#[test]
+fn empty_and_constant() {
+    let a = common::load_sampled("corr_a", 10, 60, |i| i as f64);
+    let flat = common::load_sampled("corr_flat", 10, 60, |_| 1.0);
+
+    let matrix = data::calc_correlation(&[a, flat], 0.0, 600.0, &Correlation::Pearson, 2);
+    assert!(matrix.coefficients[0][1].is_nan());
+    assert!(matrix.lags.unwrap()[0][1].1.is_nan());
+
+    // Nothing is loaded after the traces end
+    let matrix = data::calc_correlation(&[a, flat], 1000.0, 2000.0, &Correlation::Spearman, 2);
+    assert!(matrix.coefficients.iter().flatten().all(|r| r.is_nan()));
+    assert_eq!(matrix.lags.unwrap()[0][0].0, 0);
+
+    let matrix = data::calc_correlation(&[], 0.0, 600.0, &Correlation::Pearson, 0);
+    assert!(matrix.coefficients.is_empty());
+    assert!(matrix.lags.is_none());
+}
+
+#[test]
+fn parse_method() {
+    assert!(matches!(
+        Correlation::parse("spearman"),
+        Ok(Correlation::Spearman)
+    ));
+    assert!(Correlation::parse("kendall").is_err());
+}
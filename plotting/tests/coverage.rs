@@ -0,0 +1,74 @@
+mod common;
+
+use plotting::data;
+use plotting::structs::DataSegment;
+
+fn push(ptr: data::DataIdx, from: i32, to: i32) {
+    data::get_trace_once(ptr, |t| {
+        t.push_segment(Box::new(DataSegment::<i32, f64> {
+            from: from as f64,
+            to: to as f64,
+            data: (from..=to).map(|i| (i, 1.0)).collect(),
+        }))
+    });
+}
+
+#[test]
+fn loaded_ranges_merge() {
+    let ptr = common::load_sampled("coverage-a", 11, 1, |_| 1.0);
+    push(ptr, 10, 20);
+    push(ptr, 15, 25);
+    push(ptr, 40, 50);
+
+    let ranges = data::get_trace_ret(ptr, |t| t.get_loaded_ranges());
+    assert_eq!(ranges, vec![(0.0, 25.0), (40.0, 50.0)]);
+
+    let missing = data::get_trace_ret(ptr, |t| t.get_missing_ranges(-10.0, 45.0));
+    assert_eq!(missing, vec![(-10.0, 0.0), (25.0, 40.0)]);
+
+    let missing = data::get_trace_ret(ptr, |t| t.get_missing_ranges(5.0, 20.0));
+    assert!(missing.is_empty());
+}
+
+#[test]
+fn missing_across_traces() {
+    let a = common::load_sampled("coverage-b", 31, 1, |_| 1.0);
+    push(a, 60, 100);
+
+    let rows: Vec<(i32, f64)> = (20..=70).map(|i| (i, 1.0)).collect();
+    let b = common::load_trace("coverage-c", &rows);
+
+    assert_eq!(
+        data::calc_missing_ranges(&[a, b], 0.0, 100.0),
+        vec![(0.0, 20.0), (30.0, 60.0), (70.0, 100.0)]
+    );
+
+    let empty = data::create_trace("coverage-d", "datetime");
+    assert_eq!(
+        data::calc_missing_ranges(&[empty], 0.0, 100.0),
+        vec![(0.0, 100.0)]
+    );
+}
+
+#[test]
+fn empty_and_inverted_ranges() {
+    let ptr = common::load_sampled("coverage-e", 11, 1, |_| 1.0);
+    push(ptr, 20, 30);
+
+    for (from, to) in [
+        (5.0, 5.0),
+        (15.0, 15.0),
+        (25.0, 5.0),
+        (f64::NAN, 5.0),
+        (0.0, f64::NAN),
+    ] {
+        assert!(
+            data::calc_missing_ranges(&[ptr], from, to).is_empty(),
+            "{}..{}",
+            from,
+            to
+        );
+    }
+
+    assert!(data::calc_missing_ranges(&[], 0.0, 100.0).is_empty());
+}
@@ -0,0 +1,316 @@
+mod common;
+
+use plotting::renderers::scene::{BarLayout, Bars, Fill, LineShape, SOLID};
+use plotting::renderers::{calc_ticks, BundleEntry, CpuRenderer, Renderer};
+use plotting::structs::{RenderJob, TraceStyle};
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const AXES: [u8; 4] = [77, 77, 77, 255];
+const EMPTY: [u8; 4] = [0, 0, 0, 0];
+
+fn plot_job(render_axes: bool, render_grid: bool) -> RenderJob {
+    let mut job = RenderJob::new("datetime".to_owned(), 1, 0);
+    job.x_from = 0.0;
+    job.x_to = 100.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.render_axes = render_axes;
+    job.render_grid = render_grid;
+    job
+}
+
+#[test]
+fn trace_pixels() {
+    let ptr = common::load_sampled("cpu::trace", 101, 1, |i| if i < 50 { 5.0 } else { 20.0 });
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    let mut job = plot_job(false, false);
+    job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+
+    let result = renderer.render(job).unwrap();
+
+    // y = 5 is in the middle of a 50 pixel high viewport, rows count from the top
+    for x in 0..49 {
+        assert_eq!(renderer.pixel(x, 24), RED, "column {}", x);
+    }
+
+    // The jump above the range is clipped at the top edge
+    assert_eq!(renderer.pixel(49, 0), RED);
+    assert_eq!(renderer.pixel(60, 0), EMPTY);
+    assert_eq!(renderer.pixel(10, 10), EMPTY);
+    assert_eq!(renderer.pixels().len(), 100 * 50 * 4);
+
+    let x_ticks: Vec<f64> = result.x_ticks.iter().map(|t| t.val).collect();
+    let expected: Vec<f64> = calc_ticks(0.0, 100.0).iter().map(|t| t.val).collect();
+    assert_eq!(x_ticks, expected);
+    assert_eq!(result.y_ticks.len(), calc_ticks(0.0, 10.0).len());
+}
+
+#[test]
+fn axes_and_grid() {
+    let mut renderer = CpuRenderer::new(110, 60);
+    let mut job = plot_job(true, true);
+    job.x_label_space = 10;
+    job.y_label_space = 10;
+
+    let result = renderer.render(job).unwrap();
+
+    // Axes run along the pixels just outside of the graph area
+    assert_eq!(renderer.pixel(9, 5), AXES);
+    assert_eq!(renderer.pixel(60, 50), AXES);
+
+    // Tick marks point away from the graph
+    let tick = &result.x_ticks[0];
+    let x = 10 + (tick.pos * 100.0) as u32;
+    assert_eq!(renderer.pixel(x, 52), AXES);
+
+    // Grid lines are translucent
+    assert_eq!(renderer.pixel(x, 20)[3], 166);
+    assert_eq!(renderer.pixel(x + 1, 20), EMPTY);
+}
+
+#[test]
+fn bundles_and_blacklist() {
+    let shown = common::load_sampled("cpu::shown", 101, 1, |_| 2.0);
+    let hidden = common::load_sampled("cpu::hidden", 101, 1, |_| 8.0);
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    let bundle = renderer
+        .create_bundle(
+            0.0,
+            100.0,
+            &[
+                BundleEntry {
+                    handle: shown,
+                    width: 3,
+                    color: [0, 0, 255],
+                    points_mode: false,
+                    dash: SOLID,
+                    fill: Fill::None,
+                    bars: None,
+                    shape: LineShape::Linear,
+                },
+                BundleEntry {
+                    handle: hidden,
+                    width: 1,
+                    color: [0, 0, 255],
+                    points_mode: false,
+                    dash: SOLID,
+                    fill: Fill::None,
+                    bars: None,
+                    shape: LineShape::Linear,
+                },
+            ],
+        )
+        .unwrap();
+
+    let mut job = plot_job(false, false);
+    job.add_bundle(bundle);
+    job.blacklist_trace(hidden);
+    renderer.render(job).unwrap();
+
+    // Three pixels wide line around y = 2
+    for row in 38..=40 {
+        assert_eq!(renderer.pixel(30, row), BLUE, "row {}", row);
+    }
+    assert_eq!(renderer.pixel(30, 37), EMPTY);
+    assert_eq!(renderer.pixel(30, 41), EMPTY);
+    assert_eq!(renderer.pixel(30, 9), EMPTY);
+
+    renderer.dispose_bundle(bundle).unwrap();
+
+    let mut job = plot_job(false, false);
+    job.add_bundle(bundle);
+    renderer.render(job).unwrap();
+
+    assert_eq!(renderer.pixel(30, 39), EMPTY);
+}
+
+#[test]
+fn baseline_fill() {
+    let ptr = common::load_sampled("cpu::fill", 101, 1, |_| 5.0);
+
+    let row = [
+        &(ptr as u32).to_be_bytes()[..],
+        &1u32.to_be_bytes(),
+        &[0, 0, 255, 0, 0, 0, 0, 0],
+        &[1, 255, 0, 0],
+        &2.0f32.to_be_bytes(),
+        &[0; 16],
+    ]
+    .concat();
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    let mut job = plot_job(false, false);
+    job.deserialize_traces(&row);
+    renderer.render(job).unwrap();
+
+    // The area between y = 2 and the line at y = 5 is filled, rows count from the top
+    assert_eq!(renderer.pixel(30, 30), BLUE);
+    assert_eq!(renderer.pixel(30, 39), BLUE);
+    assert_eq!(renderer.pixel(30, 41), EMPTY);
+    assert_eq!(renderer.pixel(30, 10), EMPTY);
+}
+
+#[test]
+fn stacked_bars() {
+    let lower = common::load_trace("cpu::bars_lower", &[(20, 2.0), (60, 2.0)]);
+    let upper = common::load_trace("cpu::bars_upper", &[(20, 3.0), (60, 3.0)]);
+
+    let bars = Some(Bars {
+        layout: BarLayout::Stacked,
+        group: 0,
+        width: 10.0,
+        baseline: 0.0,
+    });
+    let entry = |handle, color| BundleEntry {
+        handle,
+        width: 1,
+        color,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::None,
+        bars,
+        shape: LineShape::Linear,
+    };
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    let bundle = renderer
+        .create_bundle(
+            0.0,
+            100.0,
+            &[entry(lower, [255, 0, 0]), entry(upper, [0, 0, 255])],
+        )
+        .unwrap();
+
+    let mut job = plot_job(false, false);
+    job.add_bundle(bundle);
+    renderer.render(job).unwrap();
+
+    // Bars span x = 15..25 with the upper trace from y = 2 to 5, rows count from the top
+    assert_eq!(renderer.pixel(16, 45), RED);
+    assert_eq!(renderer.pixel(24, 30), BLUE);
+    assert_eq!(renderer.pixel(20, 20), EMPTY);
+    assert_eq!(renderer.pixel(26, 45), EMPTY);
+    assert_eq!(renderer.pixel(60, 45), RED);
+
+    // Nothing is drawn between the samples
+    assert_eq!(renderer.pixel(40, 45), EMPTY);
+}
+
+#[test]
+fn off_scale_bars() {
+    let ptr = common::load_trace(
+        "cpu::off_scale",
+        &[(20, 1e9), (40, -1e9), (60, f64::NAN), (80, 8.0)],
+    );
+
+    let mut job = plot_job(false, false);
+    job.add_trace_style(TraceStyle {
+        idx: ptr,
+        color: [255, 0, 0],
+        width: 1,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::None,
+        bars: Some(Bars {
+            layout: BarLayout::Overlap,
+            group: 0,
+            width: 10.0,
+            baseline: 5.0,
+        }),
+        shape: LineShape::Linear,
+    });
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    renderer.render(job).unwrap();
+
+    // Bars past the range are cut off at the edges of the plot, rows count from the top
+    assert_eq!(renderer.pixel(20, 0), RED);
+    assert_eq!(renderer.pixel(20, 30), EMPTY);
+    assert_eq!(renderer.pixel(40, 49), RED);
+    assert_eq!(renderer.pixel(40, 20), EMPTY);
+    assert_eq!(renderer.pixel(60, 20), EMPTY);
+    assert_eq!(renderer.pixel(60, 30), EMPTY);
+    assert_eq!(renderer.pixel(80, 20), RED);
+}
+
+#[test]
+fn off_scale_fill() {
+    let ptr = common::load_trace("cpu::off_scale_fill", &[(-100000, 1e9), (100000, 1e9)]);
+
+    let mut job = plot_job(false, false);
+    job.x_from = -100000.0;
+    job.x_to = 100001.0;
+    job.add_trace_style(TraceStyle {
+        idx: ptr,
+        color: [0, 0, 255],
+        width: 1,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::Baseline {
+            value: 5.0,
+            alpha: 255,
+        },
+        bars: None,
+        shape: LineShape::Linear,
+    });
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    renderer.render(job).unwrap();
+
+    // The band is cut off at the top of the plot, rows count from the top
+    assert_eq!(renderer.pixel(50, 0), BLUE);
+    assert_eq!(renderer.pixel(50, 24), BLUE);
+    assert_eq!(renderer.pixel(50, 30), EMPTY);
+}
+
+#[test]
+fn gaps_and_empty_ranges() {
+    let ptr = common::load_sampled("cpu::gaps", 101, 1, |i| {
+        if (40..60).contains(&i) {
+            f64::NAN
+        } else {
+            5.0
+        }
+    });
+
+    let mut renderer = CpuRenderer::new(100, 50);
+    let mut job = plot_job(false, false);
+    job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+    renderer.render(job).unwrap();
+
+    assert_eq!(renderer.pixel(20, 24), RED);
+    assert_eq!(renderer.pixel(50, 24), EMPTY);
+    assert_eq!(renderer.pixel(80, 24), RED);
+
+    let mut job = plot_job(false, false);
+    job.add_highlights(ptr, &[20.0, 50.0], &[255, 0, 0]);
+    renderer.render(job).unwrap();
+
+    assert_eq!(renderer.pixel(20, 24), RED);
+    assert_eq!(
+        renderer.pixels().chunks(4).filter(|p| *p == RED).count(),
+        64
+    );
+
+    // Collapsed ranges draw nothing instead of failing
+    for (x_to, y_to) in [(0.0, 10.0), (100.0, 0.0), (f64::NAN, 10.0)] {
+        let mut job = plot_job(false, false);
+        job.x_to = x_to;
+        job.y_to = y_to;
+        job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+            .unwrap();
+
+        renderer.render(job).unwrap();
+        assert!(
+            renderer.pixels().chunks(4).all(|p| p == EMPTY),
+            "{} {}",
+            x_to,
+            y_to
+        );
+    }
+}
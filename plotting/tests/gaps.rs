@@ -0,0 +1,105 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::{Gap, GapKind};
+use plotting::structs::DataSegment;
+
+#[test]
+fn unloaded_and_missing() {
+    let rows: Vec<(i32, f64)> = (0..=100)
+        .filter(|i| !(40..50).contains(i))
+        .map(|i| (i, 1.0))
+        .collect();
+    let ptr = common::load_trace("collector", &rows);
+
+    data::get_trace_once(ptr, |t| {
+        t.push_segment(Box::new(DataSegment::<i32, f64> {
+            from: 200.0,
+            to: 300.0,
+            data: (200..=300).map(|i| (i, 1.0)).collect(),
+        }))
+    });
+
+    let reports = data::calc_gap_reports(&[ptr], -50.0, 301.0, 3.0);
+    let report = &reports[0].1;
+
+    assert_eq!(report.interval, 1.0);
+    assert_eq!(
+        report.gaps,
+        vec![
+            Gap {
+                from: -50.0,
+                to: 0.0,
+                kind: GapKind::Unloaded
+            },
+            Gap {
+                from: 39.0,
+                to: 50.0,
+                kind: GapKind::Missing
+            },
+            Gap {
+                from: 100.0,
+                to: 200.0,
+                kind: GapKind::Unloaded
+            },
+            Gap {
+                from: 300.0,
+                to: 301.0,
+                kind: GapKind::Unloaded
+            },
+        ]
+    );
+    assert!((report.completeness - 192.0 / 351.0 * 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn non_finite_and_empty() {
+    let ptr = common::load_sampled("collector_nan", 101, 1, |i| {
+        if (40..50).contains(&i) {
+            f64::NAN
+        } else {
+            1.0
+        }
+    });
+
+    let report = &data::calc_gap_reports(&[ptr], 0.0, 100.0, 3.0)[0].1;
+    assert_eq!(
+        report.gaps,
+        vec![Gap {
+            from: 39.0,
+            to: 50.0,
+            kind: GapKind::Missing
+        }]
+    );
+    assert!((report.completeness - 90.0).abs() < 1e-9);
+
+    // Loaded, but without a single finite sample
+    let blank = common::load_sampled("collector_blank", 11, 10, |_| f64::NAN);
+    let report = &data::calc_gap_reports(&[blank], 0.0, 100.0, 3.0)[0].1;
+    assert!(report.interval.is_nan());
+    assert_eq!(report.completeness, 0.0);
+    assert_eq!(
+        report.gaps,
+        vec![Gap {
+            from: 0.0,
+            to: 100.0,
+            kind: GapKind::Missing
+        }]
+    );
+
+    let unloaded = data::create_trace("collector_unloaded", "datetime");
+    let report = &data::calc_gap_reports(&[unloaded], 0.0, 100.0, 3.0)[0].1;
+    assert_eq!(
+        report.gaps,
+        vec![Gap {
+            from: 0.0,
+            to: 100.0,
+            kind: GapKind::Unloaded
+        }]
+    );
+
+    assert!(data::calc_gap_reports(&[ptr], 50.0, 50.0, 3.0)[0]
+        .1
+        .gaps
+        .is_empty());
+}
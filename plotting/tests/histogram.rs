@@ -0,0 +1,69 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::{Binning, Histogram};
+
+#[test]
+fn fixed_bins() {
+    let hist = Histogram::new(
+        &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+        &Binning::Fixed(5),
+    );
+
+    assert_eq!(hist.edges, vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+    assert_eq!(hist.counts, vec![2, 2, 2, 2, 3]);
+    assert_eq!(*hist.cdf.last().unwrap(), 1.0);
+}
+
+#[test]
+fn log_bins_skip_non_positive() {
+    let hist = Histogram::new(&[-1.0, 0.0, 1.0, 10.0, 100.0, 1000.0], &Binning::Log(3));
+
+    assert_eq!(hist.counts, vec![1, 1, 2]);
+    assert!((hist.edges[1] - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn auto_bins_per_trace() {
+    let ptr = common::load_sampled("hist", 1000, 60, |i| (i % 100) as f64);
+
+    let hists = data::calc_histograms(&[ptr], 0.0, 60_000.0, &Binning::Auto);
+    let (handle, hist) = &hists[0];
+
+    assert_eq!(*handle, ptr);
+    assert_eq!(hist.counts.iter().sum::<usize>(), 1000);
+    assert_eq!(hist.edges.len(), hist.counts.len() + 1);
+}
+
+#[test]
+fn non_finite_and_constant_values() {
+    let hist = Histogram::new(
+        &[f64::NAN, 3.0, f64::INFINITY, 3.0, f64::NEG_INFINITY],
+        &Binning::Fixed(2),
+    );
+
+    assert_eq!(hist.edges, vec![2.5, 3.0, 3.5]);
+    assert_eq!(hist.counts, vec![0, 2]);
+    assert_eq!(hist.cdf, vec![0.0, 1.0]);
+
+    let hist = Histogram::new(&[f64::NAN], &Binning::Auto);
+    assert!(hist.edges.is_empty() && hist.counts.is_empty() && hist.cdf.is_empty());
+}
+
+#[test]
+fn empty_range() {
+    let ptr = common::load_sampled("hist_range", 10, 60, |i| i as f64);
+
+    for (from, to) in [(1000.0, 2000.0), (-100.0, 0.0), (300.0, 300.0)] {
+        let hists = data::calc_histograms(&[ptr], from, to, &Binning::Auto);
+
+        assert!(hists[0].1.counts.is_empty(), "{}..{}", from, to);
+    }
+}
+
+#[test]
+fn parse_binning() {
+    assert!(matches!(Binning::parse("fixed", 0), Ok(Binning::Fixed(1))));
+    assert!(matches!(Binning::parse("auto", 10), Ok(Binning::Auto)));
+    assert!(Binning::parse("linear", 10).is_err());
+}
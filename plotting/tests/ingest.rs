@@ -0,0 +1,231 @@
+use plotting::data;
+use plotting::ingest::{DedupPolicy, IngestReport, Rows};
+
+fn payload(rows: &[(i32, &[f64])]) -> Vec<u8> {
+    let mut data = vec![];
+
+    for (x, ys) in rows {
+        data.extend_from_slice(&x.to_le_bytes());
+
+        for y in ys.iter() {
+            data.extend_from_slice(&y.to_le_bytes());
+        }
+    }
+
+    data
+}
+
+fn points(ptr: data::DataIdx) -> Vec<(f64, f64)> {
+    data::get_trace_ret(ptr, |t| t.get_data_high_prec(-1000.0, 1000.0).collect())
+}
+
+#[test]
+fn sorts_and_dedupes() {
+    let a = data::create_trace("ingest::a", "datetime");
+    let b = data::create_trace("ingest::b", "datetime");
+
+    let mut data = payload(&[
+        (3, &[3.0, 30.0]),
+        (1, &[1.0, 10.0]),
+        (2, &[2.0, f64::NAN]),
+        (2, &[4.0, 20.0]),
+        (0, &[f64::INFINITY, 0.0]),
+    ]);
+    data.extend_from_slice(&[0, 0, 0]);
+
+    let report = data::bulkload(
+        &[a, b],
+        "datetime",
+        "double",
+        &data,
+        DedupPolicy::Mean,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        report,
+        IngestReport {
+            rows: 5,
+            out_of_order: 2,
+            duplicates: 1,
+            nan: 1,
+            infinite: 1,
+            invalid_timestamps: 0,
+            late: 0,
+            trailing_bytes: 3,
+        }
+    );
+
+    let loaded = data::get_trace_ret(a, |t| t.get_loaded_ranges());
+    assert_eq!(loaded, vec![(0.0, 3.0)]);
+
+    assert_eq!(
+        points(a),
+        vec![(0.0, f64::INFINITY), (1.0, 1.0), (2.0, 3.0), (3.0, 3.0)]
+    );
+    assert_eq!(
+        points(b),
+        vec![(0.0, 0.0), (1.0, 10.0), (2.0, 20.0), (3.0, 30.0)]
+    );
+}
+
+#[test]
+fn dedup_policies() {
+    let rows: &[(i32, &[f64])] = &[(0, &[5.0]), (0, &[1.0]), (0, &[3.0]), (1, &[0.0])];
+    let data = payload(rows);
+
+    for (policy, expected) in [
+        ("first", 5.0),
+        ("last", 3.0),
+        ("mean", 3.0),
+        ("min", 1.0),
+        ("max", 5.0),
+    ] {
+        let ptr = data::create_trace(&format!("ingest::{}", policy), "datetime");
+        let report = data::bulkload(
+            &[ptr],
+            "datetime",
+            "double",
+            &data,
+            DedupPolicy::parse(policy).unwrap(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(report.duplicates, 2);
+        assert_eq!(report.out_of_order, 0);
+        assert_eq!(points(ptr)[0], (0.0, expected), "{}", policy);
+    }
+}
+
+#[test]
+fn empty_payload() {
+    let ptr = data::create_trace("ingest::empty", "datetime");
+    let report = data::bulkload(
+        &[ptr],
+        "datetime",
+        "float",
+        &[1, 2],
+        DedupPolicy::Last,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(report.rows, 0);
+    assert_eq!(report.trailing_bytes, 2);
+    assert!(data::get_trace_ret(ptr, |t| t.get_loaded_ranges()).is_empty());
+}
+
+#[test]
+fn requested_range() {
+    let ptr = data::create_trace("ingest::range", "datetime");
+    let data = payload(&[(5, &[1.0]), (8, &[2.0])]);
+    data::bulkload(
+        &[ptr],
+        "datetime",
+        "double",
+        &data,
+        DedupPolicy::Last,
+        Some((0.0, 10.0)),
+    )
+    .unwrap();
+
+    // An empty sub-range still counts as loaded
+    data::bulkload(
+        &[ptr],
+        "datetime",
+        "double",
+        &[],
+        DedupPolicy::Last,
+        Some((10.0, 20.0)),
+    )
+    .unwrap();
+
+    assert_eq!(
+        data::get_trace_ret(ptr, |t| t.get_loaded_ranges()),
+        vec![(0.0, 20.0)]
+    );
+    assert!(data::get_trace_ret(ptr, |t| t.get_missing_ranges(0.0, 20.0)).is_empty());
+    assert_eq!(points(ptr), vec![(5.0, 1.0), (8.0, 2.0)]);
+}
+
+#[test]
+fn unknown_dedup_policy() {
+    assert_eq!(DedupPolicy::parse("max"), Ok(DedupPolicy::Max));
+    assert!(DedupPolicy::parse("median").is_err());
+}
+
+#[test]
+fn non_finite_timestamps() {
+    let mut data = vec![];
+    for (x, y) in &[
+        (2.0, 20.0),
+        (f64::NAN, 1.0),
+        (1.0, 10.0),
+        (f64::INFINITY, 2.0),
+    ] {
+        data.extend_from_slice(&f64::to_le_bytes(*x));
+        data.extend_from_slice(&f64::to_le_bytes(*y));
+    }
+
+    let desc = data::TYPE_SIZES.get("double").unwrap();
+    let mut report = IngestReport::default();
+    let (mut rows, _) = Rows::decode(&data, desc, desc, 1, &mut report);
+    rows.normalize(DedupPolicy::Last, &mut report);
+
+    assert_eq!(rows.column(0), vec![(1.0, 10.0), (2.0, 20.0)]);
+    assert_eq!(report.rows, 4);
+    assert_eq!(report.invalid_timestamps, 2);
+    assert_eq!(report.out_of_order, 1);
+}
+
+#[test]
+fn dedup_non_finite() {
+    let data = payload(&[
+        (0, &[f64::NAN, 2.0]),
+        (0, &[4.0, f64::NAN]),
+        (0, &[f64::INFINITY, f64::NAN]),
+        (1, &[f64::NAN, 1.0]),
+        (1, &[f64::NAN, 1.0]),
+    ]);
+
+    // Non-finite duplicates are only kept when there is nothing else
+    for policy in ["first", "last", "mean", "min", "max"] {
+        let a = data::create_trace(&format!("ingest::nan::{}", policy), "datetime");
+        let b = data::create_trace(&format!("ingest::nan::{}::b", policy), "datetime");
+        data::bulkload(
+            &[a, b],
+            "datetime",
+            "double",
+            &data,
+            DedupPolicy::parse(policy).unwrap(),
+            None,
+        )
+        .unwrap();
+
+        let a = points(a);
+        assert_eq!(a[0], (0.0, 4.0), "{}", policy);
+        assert!(a[1].1.is_nan(), "{}", policy);
+        assert_eq!(points(b), vec![(0.0, 2.0), (1.0, 1.0)], "{}", policy);
+    }
+}
+
+#[test]
+fn only_invalid_timestamps() {
+    let mut data = vec![];
+    for x in &[f64::NAN, f64::NEG_INFINITY] {
+        data.extend_from_slice(&f64::to_le_bytes(*x));
+        data.extend_from_slice(&f64::to_le_bytes(1.0));
+    }
+
+    let desc = data::TYPE_SIZES.get("double").unwrap();
+    let mut report = IngestReport::default();
+    let (mut rows, rest) = Rows::decode(&data, desc, desc, 1, &mut report);
+    rows.normalize(DedupPolicy::Mean, &mut report);
+
+    assert!(rows.is_empty());
+    assert_eq!(rest, 0);
+    assert_eq!(report.rows, 2);
+    assert_eq!(report.invalid_timestamps, 2);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use plotting::data;
+use plotting::structs::{DataSegment, Interpolation};
+
+fn load() -> usize {
+    let ptr = common::load_trace("capacity", &[(0, 10.0), (10, 20.0), (20, 30.0)]);
+
+    // Adjacent segment, its first sample lies past the border
+    data::get_trace_once(ptr, |t| {
+        t.push_segment(Box::new(DataSegment::<i32, f64> {
+            from: 20.0,
+            to: 40.0,
+            data: vec![(30, 50.0), (40, 60.0)],
+        }))
+    });
+
+    ptr
+}
+
+#[test]
+fn modes() {
+    let ptr = load();
+    let at =
+        |x: f64, mode: Interpolation| data::get_trace_ret(ptr, |t| t.get_data_at_with(x, mode));
+
+    assert_eq!(at(5.0, Interpolation::Linear), Some(15.0));
+    assert_eq!(at(5.0, Interpolation::StepAfter), Some(10.0));
+    assert_eq!(at(5.0, Interpolation::StepBefore), Some(20.0));
+    assert_eq!(at(4.0, Interpolation::Nearest), Some(10.0));
+    assert_eq!(at(10.0, Interpolation::StepBefore), Some(20.0));
+}
+
+#[test]
+fn across_segments() {
+    let ptr = load();
+    let at =
+        |x: f64, mode: Interpolation| data::get_trace_ret(ptr, |t| t.get_data_at_with(x, mode));
+
+    assert_eq!(at(25.0, Interpolation::Linear), Some(40.0));
+    assert_eq!(at(25.0, Interpolation::StepAfter), Some(30.0));
+    assert_eq!(at(41.0, Interpolation::Linear), None);
+}
+
+#[test]
+fn trace_default() {
+    let ptr = load();
+    data::set_interpolation(ptr, "step-after").unwrap();
+
+    assert_eq!(
+        data::get_data_at_iter(&[ptr], 15.0, None).collect::<Vec<_>>(),
+        vec![(ptr, 20.0)]
+    );
+    assert_eq!(
+        data::find_closest(&[ptr], 15.0, 24.0, 3.0, Some(String::from("linear"))).unwrap(),
+        Some(ptr)
+    );
+    assert_eq!(
+        data::find_closest(&[ptr], 15.0, 24.0, 3.0, None).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn gaps_and_out_of_range() {
+    let ptr = common::load_trace("capacity_gaps", &[(0, 10.0), (10, f64::NAN), (20, 30.0)]);
+    let at =
+        |x: f64, mode: Interpolation| data::get_trace_ret(ptr, |t| t.get_data_at_with(x, mode));
+
+    assert_eq!(at(10.0, Interpolation::Linear), None);
+    assert_eq!(at(5.0, Interpolation::Linear), None);
+    assert_eq!(at(5.0, Interpolation::StepAfter), Some(10.0));
+    assert_eq!(at(15.0, Interpolation::StepAfter), None);
+    assert_eq!(at(15.0, Interpolation::StepBefore), Some(30.0));
+    assert_eq!(at(8.0, Interpolation::Nearest), None);
+
+    for mode in &[Interpolation::Linear, Interpolation::Nearest] {
+        assert_eq!(at(f64::NAN, *mode), None);
+        assert_eq!(at(-1.0, *mode), None);
+        assert_eq!(at(21.0, *mode), None);
+    }
+
+    assert_eq!(data::get_data_at_iter(&[ptr], 10.0, None).count(), 0);
+}
+
+#[test]
+fn parse_mode() {
+    assert_eq!(
+        Interpolation::parse("step-before"),
+        Ok(Interpolation::StepBefore)
+    );
+    assert!(Interpolation::parse("cubic").is_err());
+}
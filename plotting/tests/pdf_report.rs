@@ -0,0 +1,146 @@
+mod common;
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use plotting::renderers::{format_y_tick, PdfRenderer, Renderer};
+use plotting::structs::RenderJob;
+
+fn plot_job() -> RenderJob {
+    let mut job = RenderJob::new("datetime".to_owned(), 1, 0);
+    job.x_from = 0.0;
+    job.x_to = 100.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.margin = 5;
+    job.x_label_space = 20;
+    job.y_label_space = 40;
+    job
+}
+
+/// Inflated content streams of a document.
+fn contents(pdf: &[u8]) -> Vec<String> {
+    let mut streams = vec![];
+    let mut rest = pdf;
+
+    while let Some(start) = find(rest, b"stream\n") {
+        rest = &rest[start + 7..];
+        let end = find(rest, b"\nendstream").unwrap();
+
+        let mut text = String::new();
+        ZlibDecoder::new(&rest[..end])
+            .read_to_string(&mut text)
+            .unwrap();
+        streams.push(text);
+
+        rest = &rest[end + b"\nendstream".len()..];
+    }
+
+    streams
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[test]
+fn report() {
+    let ptr = common::load_trace("pdf::load", &[(0, 0.0), (50, 10.0), (75, 5.0)]);
+
+    // Each graph takes 18 + 300 + 2 * 12 + 24 points, two of them fit on an A4 page
+    let mut renderer = PdfRenderer::new(595.0, 842.0, 300.0);
+
+    for i in 0..3 {
+        let mut job = plot_job();
+        job.add_trace(ptr, &[255, 0, 0], 2, i == 2, &[], "linear")
+            .unwrap();
+
+        renderer.set_title(&format!("Graph {}", i));
+        let result = renderer.render(job).unwrap();
+
+        assert!(!result.x_ticks.is_empty());
+    }
+
+    assert_eq!(renderer.page_count(), 2);
+
+    let pdf = renderer.finish();
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(find(&pdf, b"/Count 2").is_some());
+    assert!(find(&pdf, b"/BaseFont /Helvetica").is_some());
+
+    let pages = contents(&pdf);
+    assert_eq!(pages.len(), 2);
+
+    assert!(pages[0].contains("(Graph 0) Tj"));
+    assert!(pages[0].contains("(Graph 1) Tj"));
+    assert!(pages[1].contains("(Graph 2) Tj"));
+
+    // Tick labels and the stats table
+    assert!(pages[0].contains(&format!("({}) Tj", format_y_tick(2.0, 0.0, 10.0))));
+    assert!(pages[0].contains("(pdf::load) Tj"));
+    assert!(pages[0].contains("(5.00) Tj"));
+    assert!(pages[0].contains("(0.00) Tj"));
+    assert!(pages[0].contains("(10.00) Tj"));
+
+    // Two axes, a clip and a stats swatch rectangle per graph, the last graph adds three markers
+    assert_eq!(pages[0].matches(" re\n").count(), 8);
+    assert_eq!(pages[1].matches(" re\n").count(), 7);
+}
+
+#[test]
+fn empty_range() {
+    let ptr = common::load_trace("pdf::empty", &[(200, 1.0), (300, 2.0)]);
+
+    let mut renderer = PdfRenderer::new(595.0, 842.0, 200.0);
+    let mut job = plot_job();
+    job.add_trace(ptr, &[0, 0, 255], 1, false, &[], "linear")
+        .unwrap();
+    renderer.render(job).unwrap();
+
+    let pages = contents(&renderer.finish());
+    assert_eq!(pages[0].matches("(-) Tj").count(), 3);
+}
+
+#[test]
+fn non_finite_gaps() {
+    let ptr = common::load_trace(
+        "pdf::gaps",
+        &[(0, 0.0), (25, f64::NAN), (50, 10.0), (75, 5.0)],
+    );
+
+    let mut renderer = PdfRenderer::new(595.0, 842.0, 200.0);
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace(ptr, &[255, 0, 0], 2, true, &[], "linear")
+        .unwrap();
+    renderer.render(job).unwrap();
+
+    let pages = contents(&renderer.finish());
+    assert!(!pages[0].contains("NaN"));
+
+    // A clip and a stats swatch rectangle, markers only for the three finite points
+    assert_eq!(pages[0].matches(" re\n").count(), 5);
+}
+
+#[test]
+fn resized_page() {
+    let ptr = common::load_trace("pdf::resized", &[(0, 0.0), (100, 10.0)]);
+
+    let mut renderer = PdfRenderer::new(595.0, 842.0, 200.0);
+    renderer.size_changed(300, 100).unwrap();
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+    renderer.render(job).unwrap();
+
+    let pdf = renderer.finish();
+    assert!(find(&pdf, b"/MediaBox [0 0 300 842]").is_some());
+
+    // The clip ends 5 points of job margin short of the right page margin at 264
+    let pages = contents(&pdf);
+    assert!(pages[0].contains("81 731 178 70 re\n"));
+}
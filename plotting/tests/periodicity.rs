@@ -0,0 +1,99 @@
+mod common;
+
+use std::f64::consts::TAU;
+
+use plotting::data;
+use plotting::stats::{PeriodMethod, DATETIME_DAY};
+
+fn daily_and_weekly(x: f64) -> f64 {
+    100.0 + 20.0 * (x / DATETIME_DAY * TAU).sin() + 5.0 * (x / (7.0 * DATETIME_DAY) * TAU).sin()
+}
+
+#[test]
+fn dominant_periods() {
+    let ptr = common::load_sampled("io", 28 * 24 * 4, 15, |i| daily_and_weekly((i * 15) as f64));
+
+    for method in &[PeriodMethod::Fft, PeriodMethod::Autocorrelation] {
+        let periods = data::calc_periods(ptr, 0.0, 1e9, method, 2);
+
+        assert!(
+            (periods[0].period / DATETIME_DAY - 1.0).abs() < 0.05,
+            "{:?}",
+            periods
+        );
+        assert!(periods[0].strength > 0.5, "{:?}", periods);
+    }
+
+    let fft = data::calc_periods(ptr, 0.0, 1e9, &PeriodMethod::Fft, 2);
+    assert!(
+        (fft[1].period / DATETIME_DAY - 7.0).abs() < 0.5,
+        "{:?}",
+        fft
+    );
+}
+
+#[test]
+fn deseasonalized_trace() {
+    let ptr = common::load_sampled("io_hourly", 7 * 24, 60, |i| {
+        daily_and_weekly((i * 60) as f64)
+    });
+    let out = data::create_trace("io_flat", "datetime");
+
+    let period = data::deseasonalize(out, ptr, 0.0, 1e9, 0.0);
+    assert!((period - DATETIME_DAY).abs() < 1.0, "{}", period);
+
+    let flat: Vec<f64> = data::get_trace_ret(out, |t| {
+        t.get_data_high_prec(0.0, 1e9).map(|p| p.1).collect()
+    });
+    let spread = flat.iter().cloned().fold(f64::MIN, f64::max)
+        - flat.iter().cloned().fold(f64::MAX, f64::min);
+
+    assert_eq!(flat.len(), 7 * 24);
+    assert!(spread < 15.0, "{}", spread);
+}
+
+#[test]
+fn gaps_and_empty() {
+    let ptr = common::load_sampled("io_gaps", 28 * 24, 60, |i| {
+        if i % 50 == 7 {
+            f64::NAN
+        } else {
+            daily_and_weekly((i * 60) as f64)
+        }
+    });
+
+    for method in &[PeriodMethod::Fft, PeriodMethod::Autocorrelation] {
+        let periods = data::calc_periods(ptr, 0.0, 1e9, method, 1);
+        assert!(
+            (periods[0].period / DATETIME_DAY - 1.0).abs() < 0.05,
+            "{:?}",
+            periods
+        );
+    }
+
+    let empty = common::load_trace("io_empty", &[]);
+    assert!(data::calc_periods(empty, 0.0, 1e9, &PeriodMethod::Fft, 1).is_empty());
+
+    let flat = common::load_sampled("io_const", 28 * 24, 60, |_| 100.0);
+    for method in &[PeriodMethod::Fft, PeriodMethod::Autocorrelation] {
+        assert!(data::calc_periods(flat, 0.0, 1e9, method, 1).is_empty());
+        assert!(data::calc_periods(ptr, 1e6, 1e9, method, 1).is_empty());
+    }
+
+    // The seasonal medians skip the gaps, which stay gaps
+    let out = data::create_trace("io_gaps_flat", "datetime");
+    data::deseasonalize(out, ptr, 0.0, 1e9, DATETIME_DAY);
+    let flat: Vec<f64> = data::get_trace_ret(out, |t| {
+        t.get_data_high_prec(0.0, 1e9).map(|p| p.1).collect()
+    });
+    assert_eq!(flat.iter().filter(|y| y.is_nan()).count(), 14);
+}
+
+#[test]
+fn parse_method() {
+    assert!(matches!(
+        PeriodMethod::parse("acf"),
+        Ok(PeriodMethod::Autocorrelation)
+    ));
+    assert!(PeriodMethod::parse("wavelet").is_err());
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use plotting::data;
+use plotting::pipeline::{self, Expr, PipelineRequest};
+
+#[test]
+fn expressions() {
+    assert_eq!(Expr::parse("2 * (y + 1) - x").unwrap().eval(3.0, 4.0), 7.0);
+    assert_eq!(Expr::parse("-2 ^ 2").unwrap().eval(0.0, 0.0), -4.0);
+    assert_eq!(
+        Expr::parse("max(abs(y), 1e1) / 4")
+            .unwrap()
+            .eval(0.0, -20.0),
+        5.0
+    );
+
+    assert!(Expr::parse("y +").is_err());
+    assert!(Expr::parse("foo(y)").is_err());
+    assert!(Expr::parse("min(y)").is_err());
+}
+
+#[test]
+fn evaluate_tree() {
+    common::load_trace("src::a::0", &[(0, 1.0), (60, 3.0), (120, 6.0), (180, 10.0)]);
+    common::load_trace("src::b::0", &[(0, 10.0), (60, 20.0), (180, 40.0)]);
+
+    let request: PipelineRequest = serde_json::from_str(
+        r#"{
+            "from": 0,
+            "to": 1000,
+            "pipelines": [
+                {
+                    "type": "sum",
+                    "children": [
+                        { "type": "data", "dataset": { "source": "src", "id": "a" } },
+                        { "type": "data", "dataset": { "source": "src", "id": "b", "variant": "0" } }
+                    ]
+                },
+                {
+                    "type": "expr",
+                    "options": { "expression": "y * 10" },
+                    "child": {
+                        "type": "diff",
+                        "options": {},
+                        "child": { "type": "data", "dataset": { "source": "src", "id": "a" } }
+                    }
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let outputs = pipeline::eval_request(&request).unwrap();
+
+    assert_eq!(outputs[0].id, "sum(src::a,src::b::0)");
+    assert_eq!(outputs[0].x_type, "datetime");
+    assert_eq!(outputs[0].y_type, "double");
+
+    let sum: Vec<(f64, f64)> = data::get_trace_ret(outputs[0].handle, |t| {
+        t.get_data_high_prec(0.0, 1000.0).collect()
+    });
+    assert_eq!(sum, vec![(0.0, 11.0), (60.0, 23.0), (180.0, 50.0)]);
+
+    let diff: Vec<(f64, f64)> = data::get_trace_ret(outputs[1].handle, |t| {
+        t.get_data_high_prec(0.0, 1000.0).collect()
+    });
+    assert_eq!(diff, vec![(60.0, 20.0), (120.0, 30.0), (180.0, 40.0)]);
+}
+
+#[test]
+fn unresolved_leaf() {
+    let request: PipelineRequest = serde_json::from_str(
+        r#"{ "from": 0, "to": 10, "pipelines": [{ "type": "data", "dataset": { "source": "x", "id": "missing" } }] }"#,
+    )
+    .unwrap();
+
+    assert!(pipeline::eval_request(&request).is_err());
+}
+
+#[test]
+fn loaded_ranges_and_ids() {
+    let a = common::load_trace("cov::a::0", &[(0, 1.0), (60, 2.0), (180, 3.0)]);
+    common::load_trace("cov::b::0", &[(60, 10.0), (120, 20.0), (240, 30.0)]);
+
+    let request: PipelineRequest = serde_json::from_str(
+        r#"{
+            "from": 0,
+            "to": 1000,
+            "pipelines": [
+                {
+                    "type": "avg",
+                    "children": [
+                        { "type": "data", "dataset": { "source": "cov", "id": "a" } },
+                        { "type": "data", "dataset": { "source": "cov", "id": "b" } }
+                    ]
+                },
+                { "type": "data", "dataset": { "source": "cov", "id": "a", "variant": "0" } }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let outputs = pipeline::eval_request(&request).unwrap();
+
+    // Only the overlap of the leaves counts as loaded
+    assert_eq!(
+        data::get_trace_ret(outputs[0].handle, |t| t.get_loaded_ranges()),
+        vec![(60.0, 180.0)]
+    );
+    assert_eq!(
+        data::calc_missing_ranges(&[outputs[0].handle], 0.0, 1000.0),
+        vec![(0.0, 60.0), (180.0, 1000.0)]
+    );
+
+    // A bare dataset gets its own trace
+    assert_eq!(outputs[1].id, "data(cov::a::0)");
+    assert_ne!(outputs[1].handle, a);
+    assert_eq!(
+        data::get_trace_ret(a, |t| t.get_data_high_prec(0.0, 1000.0).count()),
+        3
+    );
+}
+
+#[test]
+fn non_finite_and_empty_range() {
+    common::load_trace("nan::a::0", &[(0, 1.0), (60, f64::NAN), (120, 3.0)]);
+
+    let request = |expression: &str, from: u32| -> PipelineRequest {
+        serde_json::from_str(&format!(
+            r#"{{
+                "from": {},
+                "to": 1000,
+                "pipelines": [
+                    {{
+                        "type": "expr",
+                        "options": {{ "expression": "{}" }},
+                        "child": {{ "type": "data", "dataset": {{ "source": "nan", "id": "a" }} }}
+                    }},
+                    {{ "type": "diff", "options": {{}}, "child": {{ "type": "data", "dataset": {{ "source": "nan", "id": "a" }} }} }}
+                ]
+            }}"#,
+            from, expression
+        ))
+        .unwrap()
+    };
+    let points = |handle| -> Vec<(f64, f64)> {
+        data::get_trace_ret(handle, |t| t.get_data_high_prec(0.0, 1000.0).collect())
+    };
+
+    // Gaps carry over to the outputs instead of failing the pipeline
+    let outputs = pipeline::eval_request(&request("y / 0", 0)).unwrap();
+    let quotient = points(outputs[0].handle);
+    assert_eq!(quotient[0], (0.0, f64::INFINITY));
+    assert!(quotient[1].1.is_nan());
+
+    let diff = points(outputs[1].handle);
+    assert_eq!(diff.len(), 2);
+    assert!(diff.iter().all(|p| p.1.is_nan()));
+
+    // Nothing of the source is loaded there, so nothing gets stored
+    let outputs = pipeline::eval_request(&request("y * 2", 500)).unwrap();
+    assert!(points(outputs[0].handle).is_empty());
+    assert!(data::get_trace_ret(outputs[0].handle, |t| t.get_loaded_ranges()).is_empty());
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use plotting::renderers::{export_png, CpuRenderer, Renderer};
+use plotting::structs::RenderJob;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const AXES: [u8; 4] = [77, 77, 77, 255];
+const EMPTY: [u8; 4] = [0, 0, 0, 0];
+
+fn plot_job(ptr: usize) -> RenderJob {
+    let mut job = RenderJob::new("datetime".to_owned(), 1, 0);
+    job.x_from = 0.0;
+    job.x_to = 100.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.render_grid = false;
+    job.x_label_space = 10;
+    job.y_label_space = 10;
+    job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+    job
+}
+
+#[test]
+fn scaled_render() {
+    let ptr = common::load_sampled("png::scaled", 101, 1, |_| 5.0);
+
+    // 110 x 60 CSS pixels at twice the density
+    let mut renderer = CpuRenderer::with_scale(220, 120, 2.0);
+    renderer.render(plot_job(ptr)).unwrap();
+
+    // Graph area spans rows 0..100 and columns 20..220, the trace is two pixels thick
+    assert_eq!(renderer.pixel(100, 48), RED);
+    assert_eq!(renderer.pixel(100, 49), RED);
+    assert_eq!(renderer.pixel(100, 47), EMPTY);
+    assert_eq!(renderer.pixel(100, 50), EMPTY);
+
+    // Axes are two pixels wide, just outside of the graph area
+    assert_eq!(renderer.pixel(18, 40), AXES);
+    assert_eq!(renderer.pixel(19, 40), AXES);
+    assert_eq!(renderer.pixel(20, 40), EMPTY);
+    assert_eq!(renderer.pixel(110, 100), AXES);
+    assert_eq!(renderer.pixel(110, 101), AXES);
+    assert_eq!(renderer.pixel(110, 99), EMPTY);
+}
+
+#[test]
+fn png_bytes() {
+    let ptr = common::load_sampled("png::bytes", 101, 1, |_| 5.0);
+
+    let mut renderer = CpuRenderer::with_scale(330, 180, 3.125);
+    renderer.render(plot_job(ptr)).unwrap();
+    let bytes = renderer.to_png().unwrap();
+
+    let decoder = png::Decoder::new(&bytes[..]);
+    let mut reader = decoder.read_info().unwrap();
+    let info = reader.info();
+
+    assert_eq!((info.width, info.height), (330, 180));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+
+    // 300 DPI
+    let dims = info.pixel_dims.unwrap();
+    assert_eq!(dims.unit, png::Unit::Meter);
+    assert_eq!(dims.xppu, 11811);
+
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(&pixels[..], renderer.pixels());
+}
+
+#[test]
+fn tick_labels() {
+    let ptr = common::load_trace("png::ticks", &[(0, 5.0), (100, 5.0)]);
+
+    let mut job = plot_job(ptr);
+    job.x_label_space = 20;
+    job.y_label_space = 40;
+
+    let mut renderer = CpuRenderer::new(240, 120);
+    renderer.render(job).unwrap();
+
+    // Graph area spans rows 0..100 and columns 40..240, labels are below and left of the ticks
+    let text = |cols: std::ops::Range<u32>, rows: std::ops::Range<u32>| {
+        rows.flat_map(|y| cols.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| renderer.pixel(x, y) == AXES)
+            .count()
+    };
+
+    assert!(text(40..240, 105..120) > 0);
+    assert!(text(0..34, 0..100) > 0);
+}
+
+#[test]
+fn exported_bundle() {
+    let ptr = common::load_sampled("png::bundle", 101, 1, |_| 5.0);
+
+    let mut job = RenderJob::new("datetime".to_owned(), 0, 0);
+    job.x_from = 0.0;
+    job.x_to = 101.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.render_axes = false;
+    job.render_grid = false;
+    job.x_label_space = 10;
+    job.y_label_space = 10;
+
+    // Handle, width, blue, no points, solid and no fill, bars or shape
+    let mut row = vec![];
+    row.extend_from_slice(&(ptr as u32).to_be_bytes());
+    row.extend_from_slice(&1u32.to_be_bytes());
+    row.extend_from_slice(&[0, 0, 255, 0]);
+    row.extend_from_slice(&[0; 28]);
+
+    let bytes = export_png(job, &row, 110, 60, 2.0).unwrap();
+
+    let decoder = png::Decoder::new(&bytes[..]);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+
+    let pixel = |x: usize, y: usize| &pixels[(y * 220 + x) * 4..(y * 220 + x) * 4 + 4];
+    assert_eq!(pixel(100, 48), [0, 0, 255, 255]);
+    assert_eq!(pixel(100, 47), EMPTY);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::{self, RankStat};
+
+#[test]
+fn top_and_bottom() {
+    let ptrs: Vec<usize> = (0..50)
+        .map(|t| {
+            common::load_sampled(&format!("ldev{}", t), 100, 60, |i| {
+                ((t * 37) % 50) as f64 + (i % 2) as f64
+            })
+        })
+        .collect();
+
+    let top = data::calc_ranking(&ptrs, 0.0, 6000.0, &RankStat::Avg, 3, true);
+    assert_eq!(
+        top.iter().map(|r| r.1).collect::<Vec<_>>(),
+        vec![49.5, 48.5, 47.5]
+    );
+
+    let bottom = data::calc_ranking(
+        &ptrs,
+        0.0,
+        6000.0,
+        &RankStat::parse("p95", 0.0).unwrap(),
+        2,
+        false,
+    );
+    assert_eq!(bottom[0].0, ptrs[0]);
+    assert_eq!(
+        bottom.iter().map(|r| r.1).collect::<Vec<_>>(),
+        vec![1.0, 2.0]
+    );
+}
+
+#[test]
+fn time_above() {
+    let ptr = common::load_trace(
+        "above",
+        &[(0, 1.0), (10, 5.0), (30, 6.0), (35, 1.0), (40, 7.0)],
+    );
+
+    let ranked = data::calc_ranking(&[ptr], 0.0, 100.0, &RankStat::TimeAbove(5.0), 1, true);
+    assert_eq!(ranked, vec![(ptr, 25.0)]);
+}
+
+#[test]
+fn non_finite_values() {
+    let ptrs = [
+        common::load_trace("rank::nan", &[(0, 1.0), (10, f64::NAN), (20, 3.0)]),
+        common::load_trace("rank::inf", &[(0, f64::INFINITY), (10, 4.0)]),
+        common::load_trace("rank::empty", &[(0, f64::NAN)]),
+    ];
+
+    let avg = data::calc_ranking(&ptrs, 0.0, 100.0, &RankStat::Avg, 3, true);
+    assert_eq!(avg, vec![(ptrs[1], 4.0), (ptrs[0], 2.0)]);
+
+    let p50 = data::calc_ranking(&ptrs, 0.0, 100.0, &RankStat::Percentile(50.0), 3, false);
+    assert_eq!(p50, vec![(ptrs[0], 2.0), (ptrs[1], 4.0)]);
+}
+
+#[test]
+fn empty_range_and_counts() {
+    let ptrs: Vec<usize> = (0..3)
+        .map(|t| {
+            common::load_sampled(&format!("rank::range{}", t), 10, 60, |i| {
+                (t * 10 + i) as f64
+            })
+        })
+        .collect();
+
+    for stat in &[RankStat::Avg, RankStat::Max, RankStat::TimeAbove(0.0)] {
+        assert!(data::calc_ranking(&ptrs, 1000.0, 2000.0, stat, 3, true).is_empty());
+    }
+
+    assert!(data::calc_ranking(&ptrs, 0.0, 600.0, &RankStat::Avg, 0, true).is_empty());
+    assert_eq!(
+        data::calc_ranking(&ptrs, 0.0, 600.0, &RankStat::Avg, 10, true).len(),
+        3
+    );
+    assert!(data::calc_ranking(&[], 0.0, 600.0, &RankStat::Avg, 3, true).is_empty());
+}
+
+#[test]
+fn sort_values_drops_non_finite() {
+    let mut values = vec![3.0, f64::NAN, 1.0, f64::NEG_INFINITY, 2.0];
+    stats::sort_values(&mut values);
+    assert_eq!(values, [1.0, 2.0, 3.0]);
+
+    assert_eq!(stats::median(&[f64::NAN, 4.0, 2.0]), 3.0);
+    assert!(stats::median(&[f64::NAN]).is_nan());
+}
+
+#[test]
+fn parse_stat() {
+    assert!(matches!(RankStat::parse("above", 5.0), Ok(RankStat::TimeAbove(t)) if t == 5.0));
+    assert!(RankStat::parse("median", 0.0).is_err());
+    assert!(RankStat::parse("p", 0.0).is_err());
+    assert!(RankStat::parse("p120", 0.0).is_err());
+}
@@ -0,0 +1,586 @@
+mod common;
+
+use plotting::renderers::scene::{
+    Anchor, BarLayout, Bars, Fill, Layer, LineCap, LineJoin, LineShape, Primitive, Space, Stroke,
+    Vertices, AXES_COLOR, GRID_COLOR, SOLID,
+};
+use plotting::renderers::{format_x_tick, BundleEntry, Bundles, Scene};
+use plotting::structs::{RenderJob, TraceStyle};
+
+fn plot_job() -> RenderJob {
+    let mut job = RenderJob::new("datetime".to_owned(), 1, 0);
+    job.x_from = 1000.0;
+    job.x_to = 1100.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.margin = 5;
+    job.x_label_space = 20;
+    job.y_label_space = 40;
+    job
+}
+
+fn entry(handle: usize, points_mode: bool) -> BundleEntry {
+    BundleEntry {
+        handle,
+        width: 2,
+        color: [0, 0, 255],
+        points_mode,
+        dash: SOLID,
+        fill: Fill::None,
+        bars: None,
+        shape: LineShape::Linear,
+    }
+}
+
+#[test]
+fn layout() {
+    let mut job = plot_job();
+    job.render_grid = false;
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let plot = scene.plot;
+
+    assert_eq!(
+        (plot.left, plot.bottom, plot.width, plot.height),
+        (45.0, 25.0, 200.0, 100.0)
+    );
+    assert_eq!((plot.x_size, plot.y_size), (100.0, 10.0));
+
+    // Axes hug the plot area from outside
+    assert_eq!(
+        scene.commands[0].primitive,
+        Primitive::Rect {
+            from: (44.0, 24.0),
+            to: (45.0, 125.0),
+            color: AXES_COLOR
+        }
+    );
+    assert_eq!(
+        scene.commands[1].primitive,
+        Primitive::Rect {
+            from: (44.0, 24.0),
+            to: (245.0, 25.0),
+            color: AXES_COLOR
+        }
+    );
+
+    match &scene.commands[2].primitive {
+        Primitive::Lines { segments, .. } => {
+            assert_eq!(segments.len(), scene.x_ticks.len() + scene.y_ticks.len());
+
+            let x = 45.0 + scene.x_ticks[0].pos as f32 * 200.0;
+            assert_eq!(segments[0], [(x, 25.0), (x, 21.0)]);
+        }
+        other => panic!("expected tick marks, got {:?}", other),
+    }
+
+    let labels: Vec<&Primitive> = scene
+        .commands
+        .iter()
+        .filter(|c| c.layer == Layer::Labels)
+        .map(|c| &c.primitive)
+        .collect();
+    assert_eq!(labels.len(), scene.x_ticks.len() + scene.y_ticks.len());
+
+    match labels[0] {
+        Primitive::Text {
+            text, pos, anchor, ..
+        } => {
+            assert_eq!(text, &format_x_tick(scene.x_ticks[0].val, "datetime"));
+            assert_eq!(pos.1, 21.0);
+            assert_eq!(*anchor, Anchor::Middle);
+        }
+        other => panic!("expected a label, got {:?}", other),
+    }
+
+    assert!(scene
+        .commands
+        .iter()
+        .all(|c| c.space == Space::Screen && c.layer != Layer::Grid));
+}
+
+#[test]
+fn grid_and_traces() {
+    let ptr = common::load_trace("scene::trace", &[(1000, 1.0), (1050, 2.0), (1100, 3.0)]);
+    let shown = common::load_trace("scene::shown", &[(900, 1.0)]);
+    let hidden = common::load_trace("scene::hidden", &[(900, 1.0)]);
+
+    let mut bundles = Bundles::default();
+    let bundle = bundles
+        .create(
+            900.0,
+            1200.0,
+            &[entry(hidden, false), entry(shown, true)],
+            |_, _, row| Ok::<_, ()>(vec![(row.handle as f32, 0.0)]),
+        )
+        .unwrap();
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.add_trace(ptr, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+    job.add_bundle(bundle);
+    job.add_bundle(bundle + 1);
+    job.blacklist_trace(hidden);
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &bundles);
+    let layers: Vec<Layer> = scene.commands.iter().map(|c| c.layer).collect();
+    assert_eq!(
+        layers,
+        [Layer::Grid, Layer::Traces, Layer::Traces, Layer::Traces]
+    );
+    assert!(scene.commands.iter().all(|c| c.space == Space::Plot));
+
+    // Grid lines span the plot with x relative to its start
+    match &scene.commands[0].primitive {
+        Primitive::Lines {
+            segments, color, ..
+        } => {
+            let x = (scene.x_ticks[0].val - 1000.0) as f32;
+            assert_eq!(segments[0], [(x, 0.0), (x, 10.0)]);
+            assert_eq!(*color, GRID_COLOR);
+        }
+        other => panic!("expected grid lines, got {:?}", other),
+    }
+
+    // The blacklisted row is skipped, the shown one is drawn with markers
+    let bundled = Vertices::Bundled { bundle, row: 1 };
+    match &scene.commands[1].primitive {
+        Primitive::Polyline {
+            vertices, stroke, ..
+        } => {
+            assert_eq!(vertices, &bundled);
+            assert_eq!(stroke.width, 2.0);
+            assert_eq!(stroke.join, LineJoin::Round);
+        }
+        other => panic!("expected a polyline, got {:?}", other),
+    }
+    match &scene.commands[2].primitive {
+        Primitive::Markers { vertices, .. } => assert_eq!(vertices, &bundled),
+        other => panic!("expected markers, got {:?}", other),
+    }
+
+    let (points, offset) = bundled.resolve(&bundles, &scene.plot);
+    assert_eq!(points, &[(shown as f32, 0.0)]);
+    assert_eq!(offset, 100.0);
+
+    match &scene.commands[3].primitive {
+        Primitive::Polyline { vertices, .. } => {
+            assert_eq!(vertices, &Vertices::Owned(vec![(0.0, 1.0), (50.0, 2.0)]))
+        }
+        other => panic!("expected a polyline, got {:?}", other),
+    }
+}
+
+#[test]
+fn line_style() {
+    let ptr = common::load_trace("scene::style", &[(1000, 1.0), (1100, 2.0)]);
+
+    let mut job = plot_job();
+    job.miter_limit = 2.0;
+    job.set_line_join("miter");
+    job.set_line_cap("square");
+    job.set_line_join("sharp");
+    job.set_line_cap("flat");
+    job.add_trace(ptr, &[255, 0, 0], 3, false, &[], "linear")
+        .unwrap();
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let strokes: Vec<(Layer, Stroke)> = scene
+        .commands
+        .iter()
+        .filter_map(|c| match c.primitive {
+            Primitive::Polyline { stroke, .. } | Primitive::Lines { stroke, .. } => {
+                Some((c.layer, stroke))
+            }
+            _ => None,
+        })
+        .collect();
+
+    // Axes and grid keep their plain lines, unknown joins and caps are ignored
+    assert_eq!(strokes.len(), 3);
+    assert_eq!(strokes[0].1, Stroke::plain(1.0));
+    assert_eq!(strokes[1].1, Stroke::plain(1.0));
+    assert_eq!(
+        strokes[2],
+        (
+            Layer::Traces,
+            Stroke {
+                width: 3.0,
+                join: LineJoin::Miter,
+                cap: LineCap::Square,
+                miter_limit: 2.0,
+                dash: SOLID,
+            }
+        )
+    );
+}
+
+#[test]
+fn rebundle() {
+    let mut bundles = Bundles::default();
+    let load = |_: f64, _: f64, row: &BundleEntry| Ok::<_, ()>(row.handle);
+
+    let bundle = bundles
+        .create(0.0, 10.0, &[entry(1, false), entry(2, false)], load)
+        .unwrap();
+
+    let removed = bundles
+        .rebundle(bundle, &[entry(3, false)], &[1], &[entry(2, true)], load)
+        .unwrap();
+    assert_eq!(removed, [1]);
+
+    let rows = &bundles.get(bundle).unwrap().rows;
+    let handles: Vec<usize> = rows.iter().map(|r| r.data).collect();
+    assert_eq!(handles, [2, 3]);
+    assert!(rows[0].style.points_mode);
+
+    assert!(bundles.dispose(bundle).is_some());
+    assert!(bundles.get(bundle).is_none());
+}
+
+#[test]
+fn rebundle_shape() {
+    let mut bundles = Bundles::default();
+    let load = |_: f64, _: f64, row: &BundleEntry| Ok::<_, ()>((row.handle, row.shape));
+
+    let stepped = BundleEntry {
+        shape: LineShape::StepAfter,
+        ..entry(1, false)
+    };
+    let bundle = bundles
+        .create(0.0, 10.0, &[stepped, entry(2, false)], load)
+        .unwrap();
+
+    // Only the row that changes its shape is loaded again
+    let removed = bundles
+        .rebundle(bundle, &[], &[], &[entry(1, false), entry(2, true)], load)
+        .unwrap();
+    assert_eq!(removed, [(1, LineShape::StepAfter)]);
+
+    let rows = &bundles.get(bundle).unwrap().rows;
+    assert_eq!(rows[0].data, (1, LineShape::Linear));
+    assert_eq!(rows[0].style.shape, LineShape::Linear);
+    assert_eq!(rows[1].data, (2, LineShape::Linear));
+}
+
+#[test]
+fn fills_and_stacks() {
+    let lower = common::load_trace("scene::lower", &[(1000, 1.0), (1050, 2.0), (1100, 3.0)]);
+    let upper = common::load_trace("scene::upper", &[(1000, 2.0), (1050, 2.0), (1100, 2.0)]);
+    let filled = common::load_trace("scene::filled", &[(1000, 4.0), (1100, 6.0)]);
+
+    // Samples are taken up to the end of the range, exclusive
+    let mut job = plot_job();
+    job.x_to = 1110.0;
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace(lower, &[255, 0, 0], 1, false, &[], "linear")
+        .unwrap();
+    job.add_trace(upper, &[0, 255, 0], 1, false, &[], "linear")
+        .unwrap();
+    job.add_trace_style(TraceStyle {
+        idx: filled,
+        color: [0, 0, 255],
+        width: 1,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::Trace {
+            handle: lower,
+            alpha: 51,
+        },
+        bars: None,
+        shape: LineShape::Linear,
+    });
+    job.add_stack_group(&[lower, upper], 102);
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let primitives: Vec<&Primitive> = scene.commands.iter().map(|c| &c.primitive).collect();
+    assert_eq!(primitives.len(), 6);
+
+    // Bands of the stack go first, each on top of the previous sum
+    assert_eq!(
+        primitives[0],
+        &Primitive::Band {
+            upper: vec![(0.0, 1.0), (50.0, 2.0), (100.0, 3.0)],
+            lower: vec![(0.0, 0.0), (50.0, 0.0), (100.0, 0.0)],
+            color: [1.0, 0.0, 0.0, 0.4],
+        }
+    );
+    match primitives[1] {
+        Primitive::Band { upper, color, .. } => {
+            assert_eq!(upper, &[(0.0, 3.0), (50.0, 4.0), (100.0, 5.0)]);
+            assert_eq!(*color, [0.0, 1.0, 0.0, 0.4]);
+        }
+        other => panic!("expected a band, got {:?}", other),
+    }
+
+    // Stacked traces are drawn at their sums only
+    match primitives[3] {
+        Primitive::Polyline { vertices, .. } => assert_eq!(
+            vertices,
+            &Vertices::Owned(vec![(0.0, 3.0), (50.0, 4.0), (100.0, 5.0)])
+        ),
+        other => panic!("expected a polyline, got {:?}", other),
+    }
+
+    // The fill to another trace only covers their common timestamps
+    assert_eq!(
+        primitives[4],
+        &Primitive::Band {
+            upper: vec![(0.0, 4.0), (100.0, 6.0)],
+            lower: vec![(0.0, 1.0), (100.0, 3.0)],
+            color: [0.0, 0.0, 1.0, 0.2],
+        }
+    );
+    assert!(matches!(primitives[5], Primitive::Polyline { .. }));
+}
+
+#[test]
+fn grouped_bars() {
+    let first = common::load_trace("scene::bars_first", &[(995, 2.0), (1050, 4.0)]);
+    let second = common::load_trace("scene::bars_second", &[(1050, 1.0)]);
+    let line = common::load_trace("scene::bars_line", &[(1000, 1.0), (1050, 1.0)]);
+
+    let bars = |baseline| {
+        Some(Bars {
+            layout: BarLayout::Grouped,
+            group: 1,
+            width: 20.0,
+            baseline,
+        })
+    };
+    let style = |idx, bars| TraceStyle {
+        idx,
+        color: [255, 0, 0],
+        width: 1,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::None,
+        bars,
+        shape: LineShape::Linear,
+    };
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace(line, &[0, 0, 255], 1, false, &[], "linear")
+        .unwrap();
+    job.add_trace_style(style(first, bars(0.0)));
+    job.add_trace_style(style(second, bars(0.5)));
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let primitives: Vec<&Primitive> = scene.commands.iter().map(|c| &c.primitive).collect();
+    assert_eq!(primitives.len(), 3);
+
+    // Each trace takes its half of the width, bars reaching into the plot are kept
+    assert_eq!(
+        primitives[0],
+        &Primitive::Rects {
+            rects: vec![[(-15.0, 0.0), (-5.0, 2.0)], [(40.0, 0.0), (50.0, 4.0)]],
+            color: [1.0, 0.0, 0.0, 1.0],
+        }
+    );
+    match primitives[1] {
+        Primitive::Rects { rects, .. } => assert_eq!(rects, &[[(50.0, 0.5), (60.0, 1.0)]]),
+        other => panic!("expected bars, got {:?}", other),
+    }
+
+    // Lines are drawn over the bars
+    assert!(matches!(primitives[2], Primitive::Polyline { .. }));
+}
+
+#[test]
+fn step_shapes() {
+    let samples = vec![
+        (0.0, 1.0),
+        (10.0, 3.0),
+        (f32::NAN, f32::NAN),
+        (20.0, 2.0),
+        (30.0, 4.0),
+    ];
+
+    assert_eq!(
+        LineShape::Linear.apply(samples.clone()).len(),
+        samples.len()
+    );
+    assert_eq!(
+        LineShape::StepBefore.apply(samples.clone())[..3],
+        [(0.0, 1.0), (0.0, 3.0), (10.0, 3.0)]
+    );
+
+    // Breaks get no corners
+    let mid = LineShape::StepMid.apply(samples);
+    assert_eq!(mid.len(), 9);
+    assert_eq!(mid[..4], [(0.0, 1.0), (5.0, 1.0), (5.0, 3.0), (10.0, 3.0)]);
+    assert!(mid[4].0.is_nan());
+    assert_eq!(
+        mid[5..],
+        [(20.0, 2.0), (25.0, 2.0), (25.0, 4.0), (30.0, 4.0)]
+    );
+}
+
+#[test]
+fn step_trace() {
+    let ptr = common::load_trace("scene::steps", &[(1000, 1.0), (1050, 2.0)]);
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace_style(TraceStyle {
+        idx: ptr,
+        color: [255, 0, 0],
+        width: 1,
+        points_mode: true,
+        dash: SOLID,
+        fill: Fill::Baseline {
+            value: 0.0,
+            alpha: 255,
+        },
+        bars: None,
+        shape: LineShape::parse("step-after").unwrap(),
+    });
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let primitives: Vec<&Primitive> = scene.commands.iter().map(|c| &c.primitive).collect();
+    assert_eq!(primitives.len(), 3);
+
+    // The fill follows the steps of the line
+    assert_eq!(
+        primitives[0],
+        &Primitive::Band {
+            upper: vec![(0.0, 1.0), (50.0, 1.0), (50.0, 2.0)],
+            lower: vec![(0.0, 0.0), (50.0, 0.0), (50.0, 0.0)],
+            color: [1.0, 0.0, 0.0, 1.0],
+        }
+    );
+    match primitives[1] {
+        Primitive::Polyline { vertices, .. } => assert_eq!(
+            vertices,
+            &Vertices::Owned(vec![(0.0, 1.0), (50.0, 1.0), (50.0, 2.0)])
+        ),
+        other => panic!("expected a polyline, got {:?}", other),
+    }
+
+    // Markers stay on the samples
+    match primitives[2] {
+        Primitive::Markers { vertices, .. } => {
+            assert_eq!(vertices, &Vertices::Owned(vec![(0.0, 1.0), (50.0, 2.0)]))
+        }
+        other => panic!("expected markers, got {:?}", other),
+    }
+}
+
+#[test]
+fn step_fill_breaks() {
+    let lower = common::load_trace(
+        "scene::step_lower",
+        &[
+            (1000, 0.0),
+            (1020, 1.0),
+            (1040, f64::NAN),
+            (1060, 1.0),
+            (1080, 2.0),
+        ],
+    );
+    let upper = common::load_trace(
+        "scene::step_upper",
+        &[
+            (1000, 2.0),
+            (1020, 3.0),
+            (1040, 4.0),
+            (1060, 5.0),
+            (1080, 6.0),
+        ],
+    );
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace_style(TraceStyle {
+        idx: upper,
+        color: [255, 0, 0],
+        width: 1,
+        points_mode: false,
+        dash: SOLID,
+        fill: Fill::Trace {
+            handle: lower,
+            alpha: 255,
+        },
+        bars: None,
+        shape: LineShape::StepAfter,
+    });
+
+    let scene = Scene::compile(&job, 250.0, 130.0, &Bundles::<()>::default());
+    let bands: Vec<_> = scene
+        .commands
+        .iter()
+        .filter_map(|c| match &c.primitive {
+            Primitive::Band { upper, lower, .. } => Some((upper, lower)),
+            _ => None,
+        })
+        .collect();
+
+    // The band breaks where the lower edge has no value, both edges keep the same corners
+    assert_eq!(
+        bands,
+        [
+            (
+                &vec![(0.0, 2.0), (20.0, 2.0), (20.0, 3.0)],
+                &vec![(0.0, 0.0), (20.0, 0.0), (20.0, 1.0)]
+            ),
+            (
+                &vec![(60.0, 5.0), (80.0, 5.0), (80.0, 6.0)],
+                &vec![(60.0, 1.0), (80.0, 1.0), (80.0, 2.0)]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn parse_line_style() {
+    assert_eq!(LineJoin::parse("bevel"), Ok(LineJoin::Bevel));
+    assert!(LineJoin::parse("sharp").is_err());
+    assert_eq!(LineCap::parse("round"), Ok(LineCap::Round));
+    assert!(LineCap::parse("flat").is_err());
+}
+
+#[test]
+fn unknown_fill_mode() {
+    let mut row = [2, 128, 0, 0, 0, 0, 0, 7];
+    assert_eq!(
+        Fill::from_row(&row),
+        Fill::Trace {
+            handle: 7,
+            alpha: 128
+        }
+    );
+
+    row[0] = 9;
+    assert_eq!(Fill::from_row(&row), Fill::None);
+}
+
+#[test]
+fn unknown_bar_layout() {
+    // Stacked in group 1, two reserved bytes, a width of 2 and a baseline of 0
+    let mut row = vec![3, 1, 0, 0];
+    row.extend_from_slice(&2f32.to_be_bytes());
+    row.extend_from_slice(&0f32.to_be_bytes());
+
+    let bars = Bars::from_row(&row).unwrap();
+    assert_eq!(
+        (bars.layout, bars.group, bars.width),
+        (BarLayout::Stacked, 1, 2.0)
+    );
+
+    row[0] = 4;
+    assert_eq!(Bars::from_row(&row), None);
+}
+
+#[test]
+fn unknown_line_shape() {
+    assert_eq!(LineShape::parse("step-mid"), Ok(LineShape::StepMid));
+    assert!(LineShape::parse("spline").is_err());
+    assert_eq!(LineShape::from_byte(2), LineShape::StepAfter);
+    assert_eq!(LineShape::from_byte(9), LineShape::Linear);
+}
@@ -0,0 +1,184 @@
+use plotting::renderers::scene::{LineCap, LineJoin, Stroke, SOLID};
+use plotting::renderers::stroke::{dashes, tessellate, FRINGE, VERTEX_LEN};
+
+type Point = (f32, f32);
+
+fn stroke(width: f32, join: LineJoin, cap: LineCap) -> Stroke {
+    Stroke {
+        width,
+        join,
+        cap,
+        miter_limit: 4.0,
+        dash: SOLID,
+    }
+}
+
+fn triangles(points: &[Point], stroke: &Stroke) -> Vec<[[f32; VERTEX_LEN]; 3]> {
+    let mut data = vec![];
+    tessellate(points, stroke, &mut data);
+    assert_eq!(data.len() % (VERTEX_LEN * 3), 0);
+
+    data.chunks_exact(VERTEX_LEN * 3)
+        .map(|t| {
+            let v = |i: usize| {
+                let mut out = [0.0; VERTEX_LEN];
+                out.copy_from_slice(&t[i * VERTEX_LEN..(i + 1) * VERTEX_LEN]);
+                out
+            };
+            [v(0), v(1), v(2)]
+        })
+        .collect()
+}
+
+fn area(t: &[[f32; VERTEX_LEN]; 3]) -> f32 {
+    let (a, b, c) = (t[0], t[1], t[2]);
+    ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.0
+}
+
+fn covered(tris: &[[[f32; VERTEX_LEN]; 3]], p: Point) -> bool {
+    tris.iter().any(|t| {
+        let side = |a: [f32; VERTEX_LEN], b: [f32; VERTEX_LEN]| {
+            (b[0] - a[0]) * (p.1 - a[1]) - (b[1] - a[1]) * (p.0 - a[0])
+        };
+        let s = [side(t[0], t[1]), side(t[1], t[2]), side(t[2], t[0])];
+
+        s.iter().all(|&x| x >= 0.0) || s.iter().all(|&x| x <= 0.0)
+    })
+}
+
+#[test]
+fn butt_caps() {
+    let tris = triangles(
+        &[(10.0, 10.0), (30.0, 10.0)],
+        &stroke(4.0, LineJoin::Miter, LineCap::Butt),
+    );
+
+    // The line grows by the fringe on every side
+    let total: f32 = tris.iter().map(area).sum();
+    assert!((total - 22.0 * 6.0).abs() < 1e-3);
+
+    assert!(covered(&tris, (9.5, 12.5)));
+    assert!(!covered(&tris, (8.5, 10.0)));
+    assert!(!covered(&tris, (20.0, 13.5)));
+
+    for v in tris.iter().flatten() {
+        assert!(v[2].abs() <= 2.0 + FRINGE + 1e-3);
+
+        // Fragments fade out past the ends only
+        if v[0] < 10.0 || v[0] > 30.0 {
+            assert_eq!(v[3], FRINGE);
+        } else {
+            assert!(v[3] < 0.0);
+        }
+    }
+}
+
+#[test]
+fn square_and_round_caps() {
+    let line = [(10.0, 10.0), (30.0, 10.0)];
+
+    let square = triangles(&line, &stroke(4.0, LineJoin::Miter, LineCap::Square));
+    let min_x = square
+        .iter()
+        .flatten()
+        .map(|v| v[0])
+        .fold(f32::MAX, f32::min);
+    assert!((min_x - (10.0 - 2.0 - FRINGE)).abs() < 1e-4);
+    assert!(covered(&square, (7.5, 12.5)));
+
+    let round = triangles(&line, &stroke(4.0, LineJoin::Miter, LineCap::Round));
+    let min_x = round
+        .iter()
+        .flatten()
+        .map(|v| v[0])
+        .fold(f32::MAX, f32::min);
+    assert!((min_x - (10.0 - 2.0 - FRINGE)).abs() < 1e-4);
+    assert!(covered(&round, (8.0, 10.0)));
+    assert!(!covered(&round, (7.5, 12.5)));
+
+    // Round caps never fade along the line, only across it
+    assert!(round.iter().flatten().all(|v| v[3] < 0.0));
+}
+
+#[test]
+fn joins() {
+    // A left turn opens the corner towards the bottom right
+    let line = [(0.0, 0.0), (20.0, 0.0), (20.0, 20.0)];
+
+    let miter = triangles(&line, &stroke(4.0, LineJoin::Miter, LineCap::Butt));
+    assert!(covered(&miter, (22.5, -2.5)));
+
+    let bevel = triangles(&line, &stroke(4.0, LineJoin::Bevel, LineCap::Butt));
+    assert!(!covered(&bevel, (22.5, -2.5)));
+    assert!(covered(&bevel, (21.0, -1.0)));
+
+    let round = triangles(&line, &stroke(4.0, LineJoin::Round, LineCap::Butt));
+    assert!(covered(&round, (22.0, -2.0)));
+    assert!(!covered(&round, (22.5, -2.5)));
+    assert!(!covered(&bevel, (22.0, -2.0)));
+
+    // Sharper than the limit falls back to a bevel
+    let mut limited = stroke(4.0, LineJoin::Miter, LineCap::Butt);
+    limited.miter_limit = 1.0;
+    assert!(!covered(&triangles(&line, &limited), (22.5, -2.5)));
+
+    // Reversing lines get a round end at the turn
+    let back = triangles(
+        &[(0.0, 0.0), (20.0, 0.0), (10.0, 0.0)],
+        &stroke(4.0, LineJoin::Round, LineCap::Butt),
+    );
+    assert!(covered(&back, (22.5, 0.0)));
+}
+
+#[test]
+fn breaks() {
+    let tris = triangles(
+        &[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (f32::NAN, f32::NAN),
+            (20.0, 0.0),
+            (30.0, 0.0),
+        ],
+        &stroke(2.0, LineJoin::Round, LineCap::Butt),
+    );
+
+    assert!(covered(&tris, (5.0, 0.0)));
+    assert!(covered(&tris, (25.0, 0.0)));
+    assert!(!covered(&tris, (15.0, 0.0)));
+
+    // Nothing to stroke in a single point
+    let dot = triangles(
+        &[(5.0, 5.0), (5.0, 5.0)],
+        &stroke(2.0, LineJoin::Round, LineCap::Round),
+    );
+    assert!(dot.is_empty());
+}
+
+#[test]
+fn dash_phase() {
+    // The pattern carries over the corner and the break
+    let pieces = dashes(
+        &[
+            (0.0, 0.0),
+            (5.0, 0.0),
+            (5.0, 5.0),
+            (f32::NAN, 0.0),
+            (10.0, 0.0),
+            (20.0, 0.0),
+        ],
+        &[4.0, 2.0],
+    );
+
+    assert_eq!(
+        pieces,
+        [
+            vec![(0.0, 0.0), (4.0, 0.0)],
+            vec![(5.0, 1.0), (5.0, 5.0)],
+            vec![(12.0, 0.0), (16.0, 0.0)],
+            vec![(18.0, 0.0), (20.0, 0.0)],
+        ]
+    );
+
+    assert_eq!(dashes(&[(0.0, 0.0), (1.0, 1.0)], &[]).len(), 1);
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use plotting::renderers::scene::{Fill, LineShape, SOLID};
+use plotting::renderers::{format_x_tick, format_y_tick, BundleEntry, Renderer, SvgRenderer};
+use plotting::structs::RenderJob;
+
+fn plot_job() -> RenderJob {
+    let mut job = RenderJob::new("datetime".to_owned(), 1, 0);
+    job.x_from = 0.0;
+    job.x_to = 100.0;
+    job.y_from = 0.0;
+    job.y_to = 10.0;
+    job.margin = 5;
+    job.x_label_space = 20;
+    job.y_label_space = 40;
+    job
+}
+
+#[test]
+fn document() {
+    let ptr = common::load_trace("svg::trace", &[(0, 0.0), (50, 10.0), (75, 5.0)]);
+    let points = common::load_trace("svg::points", &[(0, 1.0), (75, 1.0)]);
+
+    let mut renderer = SvgRenderer::new(250, 130);
+    let bundle = renderer
+        .create_bundle(
+            0.0,
+            100.0,
+            &[BundleEntry {
+                handle: points,
+                width: 1,
+                color: [0, 128, 0],
+                points_mode: true,
+                dash: SOLID,
+                fill: Fill::None,
+                bars: None,
+                shape: LineShape::Linear,
+            }],
+        )
+        .unwrap();
+
+    let mut job = plot_job();
+    job.add_trace(ptr, &[255, 0, 0], 2, false, &[], "linear")
+        .unwrap();
+    job.add_bundle(bundle);
+
+    let result = renderer.render(job).unwrap();
+    let svg = renderer.document();
+
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="250" height="130""#));
+    assert!(svg.ends_with("</svg>"));
+
+    // Graph area is 200 x 100 with its top left corner at (45, 5)
+    assert!(svg.contains(r#"<path d="M45.00 105.00L145.00 5.00L195.00 55.00" fill="none" stroke="rgb(255,0,0)" stroke-width="2""#));
+    assert!(svg.contains(
+        r#"<path d="M45.00 95.00L195.00 95.00" fill="none" stroke="rgb(0,128,0)" stroke-width="1""#
+    ));
+    assert!(svg.contains(r#"<path class="markers" d="M43.00 93.00h4v4h-4zM193.00 93.00h4v4h-4z" fill="rgb(0,128,0)"/>"#));
+
+    assert_eq!(
+        svg.matches("<text").count(),
+        result.x_ticks.len() + result.y_ticks.len()
+    );
+    assert!(svg.contains(&format!(
+        ">{}</text>",
+        format_x_tick(result.x_ticks[0].val, "datetime")
+    )));
+    assert!(svg.contains(&format!(
+        ">{}</text>",
+        format_y_tick(result.y_ticks[0].val, 0.0, 10.0)
+    )));
+    assert!(svg.contains(r#"class="grid""#));
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    renderer.render(job).unwrap();
+
+    assert!(!renderer.document().contains("<path"));
+}
+
+#[test]
+fn tick_labels() {
+    assert_eq!(format_x_tick(0.0, "datetime"), "01.01. 00:00 UTC");
+    assert_eq!(
+        format_x_tick(27_000_000.0 + 90.0, "datetime"),
+        "03.05. 01:30 UTC"
+    );
+    assert_eq!(format_x_tick(12.5, "int"), "12.5");
+
+    assert_eq!(format_y_tick(2.0, 0.0, 10.0), "2");
+    assert_eq!(format_y_tick(0.25, 0.0, 0.5), "0.25");
+    assert_eq!(format_y_tick(2000.0, 0.0, 5000.0), "2000");
+}
+
+#[test]
+fn dashed_rows() {
+    let ptr = common::load_trace("svg::dashed", &[(0, 0.0), (100, 10.0)]);
+
+    // Handle, width, color, points mode, a dash pattern of 6 on, 3 off, 1 on, 3 off and no fill
+    let mut row = vec![];
+    row.extend_from_slice(&(ptr as u32).to_be_bytes());
+    row.extend_from_slice(&2u32.to_be_bytes());
+    row.extend_from_slice(&[0, 0, 255, 0, 6, 3, 1, 3]);
+    row.extend_from_slice(&[0; 24]);
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.deserialize_traces(&row);
+    assert_eq!(job.get_traces()[0].dash, [6, 3, 1, 3]);
+
+    let mut renderer = SvgRenderer::new(250, 130);
+    renderer.render(job).unwrap();
+    assert!(renderer
+        .document()
+        .contains(r#"stroke="rgb(0,0,255)" stroke-width="2" stroke-linejoin="round" stroke-dasharray="6 3 1 3""#));
+
+    // An odd pattern repeats, as in SVG
+    let mut job = plot_job();
+    job.add_trace(ptr, &[0, 0, 255], 1, false, &[4], "linear")
+        .unwrap();
+    renderer.render(job).unwrap();
+    assert!(renderer.document().contains(r#"stroke-dasharray="4 4""#));
+}
+
+#[test]
+fn step_rows() {
+    let ptr = common::load_trace("svg::steps", &[(0, 0.0), (50, 10.0)]);
+
+    let mut renderer = SvgRenderer::new(250, 130);
+    let bundle = renderer
+        .create_bundle(
+            0.0,
+            100.0,
+            &[BundleEntry {
+                handle: ptr,
+                width: 1,
+                color: [0, 128, 0],
+                points_mode: true,
+                dash: SOLID,
+                fill: Fill::None,
+                bars: None,
+                shape: LineShape::StepMid,
+            }],
+        )
+        .unwrap();
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_bundle(bundle);
+    renderer.render(job).unwrap();
+
+    let svg = renderer.document();
+    assert!(
+        svg.contains(r#"<path d="M45.00 105.00L95.00 105.00L95.00 5.00L145.00 5.00" fill="none""#)
+    );
+    assert!(svg.contains(r#"d="M43.00 103.00h4v4h-4zM143.00 3.00h4v4h-4z""#));
+}
+
+#[test]
+fn non_finite_gaps() {
+    let ptr = common::load_trace(
+        "svg::gaps",
+        &[(0, 0.0), (25, f64::NAN), (50, 10.0), (75, 5.0)],
+    );
+
+    let mut renderer = SvgRenderer::new(250, 130);
+    let bundle = renderer
+        .create_bundle(
+            0.0,
+            100.0,
+            &[BundleEntry {
+                handle: ptr,
+                width: 1,
+                color: [0, 128, 0],
+                points_mode: true,
+                dash: SOLID,
+                fill: Fill::Baseline {
+                    value: 0.0,
+                    alpha: 128,
+                },
+                bars: None,
+                shape: LineShape::Linear,
+            }],
+        )
+        .unwrap();
+
+    let mut job = plot_job();
+    job.render_axes = false;
+    job.render_grid = false;
+    job.add_trace(ptr, &[255, 0, 0], 2, false, &[], "linear")
+        .unwrap();
+    job.add_bundle(bundle);
+    renderer.render(job).unwrap();
+
+    let svg = renderer.document();
+    assert!(!svg.contains("NaN"));
+    assert!(svg.contains(
+        r#"d="M45.00 105.00M145.00 5.00L195.00 55.00" fill="none" stroke="rgb(255,0,0)""#
+    ));
+    assert!(svg.contains(r#"d="M43.00 103.00h4v4h-4zM143.00 3.00h4v4h-4zM193.00 53.00h4v4h-4z""#));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::ThresholdParams;
+
+const VALUES: [f64; 12] = [
+    0.0, 5.0, 11.0, 12.0, 9.5, 11.0, 8.0, 2.0, 11.0, 3.0, 15.0, 14.0,
+];
+
+#[test]
+fn hysteresis_and_min_duration() {
+    let ptr = common::load_sampled("tres", VALUES.len() as i32, 10, |i| VALUES[i as usize]);
+
+    let plain = data::calc_threshold_intervals(ptr, 0.0, 1000.0, 10.0, &ThresholdParams::new());
+    assert_eq!(plain.intervals.len(), 4);
+
+    let mut params = ThresholdParams::new();
+    params.hysteresis = 1.0;
+    params.min_duration = 15.0;
+
+    let report = data::calc_threshold_intervals(ptr, 0.0, 1000.0, 10.0, &params);
+    let spans: Vec<(f64, f64, f64)> = report
+        .intervals
+        .iter()
+        .map(|v| (v.from, v.to, v.peak))
+        .collect();
+
+    assert_eq!(spans, vec![(20.0, 60.0, 12.0)]);
+    assert_eq!(report.total, 40.0);
+}
+
+#[test]
+fn below() {
+    let ptr = common::load_sampled("tres_below", VALUES.len() as i32, 10, |i| {
+        VALUES[i as usize]
+    });
+
+    let mut params = ThresholdParams::new();
+    params.below = true;
+
+    let report = data::calc_threshold_intervals(ptr, 0.0, 1000.0, 3.0, &params);
+    let spans: Vec<(f64, f64, f64)> = report
+        .intervals
+        .iter()
+        .map(|v| (v.from, v.to, v.peak))
+        .collect();
+
+    assert_eq!(
+        spans,
+        vec![(0.0, 10.0, 0.0), (70.0, 80.0, 2.0), (90.0, 100.0, 3.0)]
+    );
+}
+
+#[test]
+fn non_finite_and_empty() {
+    let values = [0.0, 11.0, f64::NAN, 12.0, 5.0, f64::NAN, 13.0, f64::NAN];
+    let ptr = common::load_sampled("tres_gaps", values.len() as i32, 10, |i| values[i as usize]);
+
+    let report = data::calc_threshold_intervals(ptr, 0.0, 1000.0, 10.0, &ThresholdParams::new());
+    let spans: Vec<(f64, f64, f64)> = report
+        .intervals
+        .iter()
+        .map(|v| (v.from, v.to, v.peak))
+        .collect();
+
+    assert_eq!(spans, vec![(10.0, 40.0, 12.0), (60.0, 60.0, 13.0)]);
+    assert_eq!(report.total, 30.0);
+
+    let report = data::calc_threshold_intervals(ptr, 500.0, 1000.0, 10.0, &ThresholdParams::new());
+    assert!(report.intervals.is_empty());
+    assert_eq!(report.total, 0.0);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use plotting::data;
+use plotting::stats::{TrendMethod, TrendParams, DATETIME_DAY};
+
+#[test]
+fn linear_crossing() {
+    let ptr = common::load_sampled("pool", 240, 60, |i| 50.0 + (i * 60) as f64 / DATETIME_DAY);
+
+    let mut params = TrendParams::new();
+    params.days = 35.0;
+    params.threshold = 90.0;
+
+    let forecast = data::calc_forecast(ptr, 0.0, 1e9, &TrendMethod::Linear, &params);
+
+    assert!((forecast.slope * DATETIME_DAY - 1.0).abs() < 1e-9);
+    assert!((forecast.crossing.unwrap() - 40.0 * DATETIME_DAY).abs() < 1e-6);
+    assert_eq!(forecast.points.len(), 240 + 35 * 24);
+}
+
+#[test]
+fn holt_winters_seasonal() {
+    let value =
+        |x: f64| 50.0 + x / DATETIME_DAY + 5.0 * (x / DATETIME_DAY * std::f64::consts::TAU).sin();
+    let ptr = common::load_sampled("seasonal_pool", 14 * 24, 60, |i| value((i * 60) as f64));
+
+    let mut params = TrendParams::new();
+    params.threshold = 70.0;
+
+    let forecast = data::calc_forecast(ptr, 0.0, 1e9, &TrendMethod::HoltWinters, &params);
+    let crossing = forecast.crossing.unwrap() / DATETIME_DAY;
+
+    assert!(crossing > 14.0 && crossing < 21.0, "{}", crossing);
+    assert!(
+        (forecast.slope * DATETIME_DAY - 1.0).abs() < 0.2,
+        "{}",
+        forecast.slope * DATETIME_DAY
+    );
+
+    let (_, y, lower, upper) = forecast.points[forecast.points.len() - 1];
+    assert!(lower < y && y < upper);
+}
+
+#[test]
+fn non_finite_and_short() {
+    let ptr = common::load_sampled("pool_gaps", 240, 60, |i| match i {
+        10 | 100..=110 => f64::NAN,
+        200 => f64::INFINITY,
+        _ => 50.0 + (i * 60) as f64 / DATETIME_DAY,
+    });
+
+    let mut params = TrendParams::new();
+    params.days = 1.0;
+
+    for method in &[TrendMethod::Linear, TrendMethod::HoltWinters] {
+        let forecast = data::calc_forecast(ptr, 0.0, 1e9, method, &params);
+
+        assert!(
+            (forecast.slope * DATETIME_DAY - 1.0).abs() < 1e-3,
+            "{}",
+            forecast.slope * DATETIME_DAY
+        );
+        assert!(forecast.points.iter().all(|p| p.1.is_finite()));
+    }
+
+    // Too few samples to fit anything
+    let forecast = data::calc_forecast(ptr, 0.0, 120.0, &TrendMethod::Linear, &params);
+    assert!(forecast.points.is_empty() && forecast.slope.is_nan());
+    let forecast = data::calc_forecast(ptr, 1e6, 1e9, &TrendMethod::HoltWinters, &params);
+    assert!(forecast.points.is_empty() && forecast.crossing.is_none());
+}
+
+#[test]
+fn parse_method() {
+    assert!(matches!(
+        TrendMethod::parse("holt_winters"),
+        Ok(TrendMethod::HoltWinters)
+    ));
+    assert!(TrendMethod::parse("arima").is_err());
+}
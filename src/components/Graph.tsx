@@ -32,6 +32,22 @@ import './Graph.scss';
 
 export const X_TICK_SPACE = 24;
 
+/** Device pixels per CSS pixel of saved images, 300 DPI. */
+const PRINT_SCALE = 300 / 96;
+
+/** A4 in points and the height of a graph on it. */
+const REPORT_PAGE: [number, number] = [ 595, 842 ];
+const REPORT_GRAPH_HEIGHT = 300;
+
+const download = (data: BlobPart, name: string, type: string) => {
+    const link = document.createElement('a');
+    link.download = name;
+    link.href = URL.createObjectURL(new Blob([ data ], { type }));
+    link.click();
+
+    setTimeout(() => URL.revokeObjectURL(link.href));
+};
+
 const dispatchProps = {
     graph_threshold_select,
     add_graphs,
@@ -118,11 +134,7 @@ class GraphComponent
             }
         });
 
-        const job = this.renderer.createJob(this.props.xType, enabledOffbundle.length, disabled.length)
-            .margin(this.props.style.margin)
-            .labelSpaces(this.props.style.xLabelSpace + X_TICK_SPACE, this.props.style.yLabelSpace)
-            .clear(true)
-            .zoom(...(this.props.zoom ?? [ ...this.props.xRange, 0.0, 1.0 ]));
+        const job = this.createJob(this.renderer, enabledOffbundle.length, disabled.length);
 
         this.renderer.bundles.forEach(b => job.addBundle(b.handle));
         enabledOffbundle.forEach(t => job.addTrace(t));
@@ -139,6 +151,13 @@ class GraphComponent
         this.setState({ rendering: false, xTicks: result.x_ticks, yTicks: result.y_ticks });
     }
 
+    private createJob = (renderer: RendererHandle, traces: number, blacklist: number) =>
+        renderer.createJob(this.props.xType, traces, blacklist)
+            .margin(this.props.style.margin)
+            .labelSpaces(this.props.style.xLabelSpace + X_TICK_SPACE, this.props.style.yLabelSpace)
+            .clear(true)
+            .zoom(...(this.props.zoom ?? [ ...this.props.xRange, 0.0, 1.0 ]));
+
     public async componentDidMount() {
         // Hook global events
         window.addEventListener('resize', this.debounceResize);
@@ -235,6 +254,15 @@ class GraphComponent
             if (redraw === undefined) redraw = true;
         }
 
+        // Show the rows of running bulk loads as they are flushed
+        if (this.props.jobs !== prevProps.jobs && this.renderer) {
+            const flushed = Object.values(this.props.jobs).some(j =>
+                j.relatedGraphs.includes(this.props.id) && j.flushes !== prevProps.jobs[j.handle]?.flushes
+            );
+
+            if (flushed && redraw === undefined) redraw = true;
+        }
+
         if (redraw) {
             await this.redrawGraph();
         }
@@ -431,6 +459,33 @@ class GraphComponent
         }
     }
 
+    private saveImage = async () => {
+        if (!this.renderer) return;
+
+        const { clientWidth, clientHeight } = this.state;
+        const job = this.createJob(this.renderer, 0, 0);
+
+        try {
+            const png = await job.exportPng(this.props.traces.filter(t => t.active), clientWidth, clientHeight, PRINT_SCALE);
+            download(png, `graph-${this.props.id}.png`, 'image/png');
+        } catch (error) {
+            this.setState({ error });
+        }
+    }
+
+    private exportPdf = async () => {
+        if (!this.renderer) return;
+
+        const graph = this.createJob(this.renderer, 0, 0).reportGraph(this.props.title, this.props.traces.filter(t => t.active));
+
+        try {
+            const pdf = await dataWorker.exportPdf(REPORT_PAGE, REPORT_GRAPH_HEIGHT, [ transfer(graph, [ graph.traces ]) ]);
+            download(pdf, `graph-${this.props.id}.pdf`, 'application/pdf');
+        } catch (error) {
+            this.setState({ error });
+        }
+    }
+
     private onTraceContext = ({ data }: { data?: { id: Trace['id'], action: 'delete' | 'deselect' | 'points' | 'edit' } }) => {
         if (!data) return;
 
@@ -633,6 +688,10 @@ class GraphComponent
                                 ]
                             },
 
+                            { type: 'separator' },
+                            { type: 'item', text: t('graph.context.saveImage'), onClick: this.saveImage, disabled: !traces.some(t => t.active) },
+                            { type: 'item', text: t('graph.context.exportPdf'), onClick: this.exportPdf, disabled: !traces.some(t => t.active) },
+
                             { type: 'separator', show: ldevSelectAvailable },
                             { type: 'item', text: t('graph.context.ldevSelect'), onClick: this.onLdevFilter, show: ldevSelectAvailable },
                             { type: 'item', text: t('graph.context.ldevJoin'),   onClick: this.onLdevJoin  , show: ldevSelectAvailable && ldevToHostGroupAvailable },
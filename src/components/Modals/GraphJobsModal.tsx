@@ -51,6 +51,9 @@ class _JobsList extends React.Component<JobsListProps> {
         return (
             <div>
                 <p>{t('modals.graphJobs.running', { count: running.length })}</p>
+                {running.filter(r => r.received !== undefined).map(r => (
+                    <p key={r.handle.toString()}>{t('modals.graphJobs.progress', { handle: r.handle, rows: r.rows, kb: Math.round((r.received ?? 0) / 1024) })}</p>
+                ))}
                 {failed.length > 0 && failed.map(f => (
                     <p key={f.handle.toString()} className='text-danger'>{t('modals.graphJobs.failed', { handle: f.handle })}<br/>{String(f.error)}</p>
                 ))}
@@ -6,6 +6,17 @@ import { t } from '../../locale';
 // import { store } from '../../redux';
 import { ChromePicker, ChromePickerProps, ColorResult } from 'react-color';
 
+const dashPatterns: { [style: string]: number[] } = {
+    solid: [],
+    dashed: [ 6, 4 ],
+    dotted: [ 2, 2 ],
+    dashDot: [ 6, 3, 2, 3 ],
+};
+
+const lineShapes: LineShape[] = [ 'linear', 'step-before', 'step-after', 'step-mid' ];
+
+const sameDash = (a: number[] = [], b: number[] = []) => a.length === b.length && a.every((v, i) => b[i] === v);
+
 interface Args {
     trace: Trace,
 }
@@ -15,6 +26,8 @@ interface State {
     color: Trace['style']['color'],
     width: Trace['style']['width'],
     points: Trace['style']['points'],
+    dash: keyof typeof dashPatterns,
+    shape: LineShape,
 }
 
 type Returns = Partial<Omit<Trace, 'style'>> & { style?: Partial<TraceStyle> };
@@ -30,6 +43,8 @@ class TraceEditModal
             color: props.trace.style.color,
             width: props.trace.style.width,
             points: props.trace.style.points,
+            dash: Object.keys(dashPatterns).find(k => sameDash(dashPatterns[k], props.trace.style.dash)) ?? 'solid',
+            shape: props.trace.style.shape ?? 'linear',
         };
     }
 
@@ -50,7 +65,7 @@ class TraceEditModal
 
     protected renderBody(): JSX.Element {
 
-        const { title, color, width, points } = this.state;
+        const { title, color, width, points, dash, shape } = this.state;
 
         const styles: ChromePickerProps['styles'] = {
             default: {
@@ -70,6 +85,18 @@ class TraceEditModal
                             <Form.Label>{t('trace.width')}</Form.Label>
                             <Form.Control name='width'  value={width}  onChange={this.onFormChange} type='number' min={1} max={8} />
                         </Form.Group>
+                        <Form.Group>
+                            <Form.Label>{t('trace.dash')}</Form.Label>
+                            <Form.Control name='dash' value={dash} onChange={this.onFormChange} as='select'>
+                                {Object.keys(dashPatterns).map(k => <option key={k} value={k}>{t(`trace.dashes.${k}`)}</option>)}
+                            </Form.Control>
+                        </Form.Group>
+                        <Form.Group>
+                            <Form.Label>{t('trace.shape')}</Form.Label>
+                            <Form.Control name='shape' value={shape} onChange={this.onFormChange} as='select'>
+                                {lineShapes.map(k => <option key={k} value={k}>{t(`trace.shapes.${k}`)}</option>)}
+                            </Form.Control>
+                        </Form.Group>
                         <Form.Group>
                             <Form.Check name='points' checked={points} onChange={this.onFormCheck} label={t('trace.pointsMode')} />
                         </Form.Group>
@@ -86,13 +113,15 @@ class TraceEditModal
     private okClicked = () => {
         const diff: Returns = {};
 
-        const { title, color, width, points } = this.state;
+        const { title, color, width, points, dash, shape } = this.state;
         const { trace } = this.props;
 
         if (title !== trace.title) { diff.title = title; }
         if (color.some((v, i) => trace.style.color[i] !== v)) { diff.style = { color }; }
         if (Number(width) !== trace.style.width) { diff.style = { ...(diff.style ?? {}), width: Number(width) }; }
         if (points !== trace.style.points) { diff.style = { ...(diff.style ?? {}), points }; }
+        if (!sameDash(dashPatterns[dash], trace.style.dash)) { diff.style = { ...(diff.style ?? {}), dash: dashPatterns[dash] }; }
+        if (shape !== (trace.style.shape ?? 'linear')) { diff.style = { ...(diff.style ?? {}), shape }; }
 
         this.resolve(Object.keys(diff).length > 0 ? diff : undefined);
     }
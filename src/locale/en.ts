@@ -57,6 +57,9 @@ const en_lang = {
 
             transform: 'Transform',
             toavgsum: 'To AVG/SUM',
+
+            saveImage: 'Save as image',
+            exportPdf: 'Export PDF report',
         },
 
         spreadTz: 'Apply {{- tz}} to all graphs',
@@ -70,6 +73,20 @@ const en_lang = {
         color: 'Trace color',
         width: 'Trace width',
         pointsMode: 'Points mode',
+        dash: 'Line style',
+        dashes: {
+            solid: 'Solid',
+            dashed: 'Dashed',
+            dotted: 'Dotted',
+            dashDot: 'Dash-dot',
+        },
+        shape: 'Line shape',
+        shapes: {
+            'linear': 'Linear',
+            'step-before': 'Step before',
+            'step-after': 'Step after',
+            'step-mid': 'Step in the middle',
+        },
     },
 
     modals: {
@@ -116,6 +133,7 @@ const en_lang = {
             running: '{{count}} job is running',
             running_plural: '{{count}} jobs are running',
             failed: 'Job {{handle}} has failed.',
+            progress: 'Job {{handle}} has loaded {{rows}} rows ({{kb}} kB).',
         },
 
         editGraph: {
@@ -18,7 +18,7 @@ export const invoke_job = createAsyncThunk(
 
         let result: DataJobResult;
         try {
-            result = await job.invoke();
+            result = await job.invoke(progress => dispatch(change_progress({ handle: job.handle, ...progress })));
             dispatch(change_state({ handle: job.handle, state: 'completed' }));
         } catch (err) {
             Logger.error(err);
@@ -50,7 +50,12 @@ export type PendingDataJob = {
     handle: number,
     state: DataJobState,
     relatedGraphs: Graph['id'][],
-    error?: Error
+    error?: Error,
+
+    rows?: number,
+    received?: number,
+    // Incremented whenever loaded rows were pushed to the traces
+    flushes?: number,
 };
 
 export const jobsSlice = createSlice({
@@ -62,6 +67,19 @@ export const jobsSlice = createSlice({
         insert_running: (_state, action: PayloadAction<PendingDataJob>) => {
             _state.items[action.payload.handle] = action.payload;
         },
+        change_progress: (_state, action: PayloadAction<{ handle: number } & DataJobProgress>) => {
+            const { handle, rows, received, flushed } = action.payload;
+            const job = _state.items[handle];
+
+            if (job) {
+                job.rows = rows;
+                job.received = received;
+
+                if (flushed) {
+                    job.flushes = (job.flushes ?? 0) + 1;
+                }
+            }
+        },
         change_state: (_state, action: PayloadAction<Pick<PendingDataJob, 'handle' | 'state' | 'error'>>) => {
             const { handle, state } = action.payload;
 
@@ -84,6 +102,7 @@ export type JobsState = SliceStateDraft<typeof jobsSlice>;
 export const {
     insert_running,
     change_state,
+    change_progress,
 } = jobsSlice.actions;
 
 export default jobsSlice.reducer;
@@ -105,6 +105,27 @@ class Data {
         ];
     }
 
+    public getBulkStream = async (trace: Pick<Dataset, 'source' | 'id'> & { variants?: string[] }, range: [ unknown, unknown ]): Promise<[ string[], Response ]> => {
+
+        const vars = trace.variants ?? await this.getTraceVariants(trace);
+        const response = await fetch(
+            this.getApiPath('data', trace.source, trace.id, 'bulk'),
+            {
+                headers: {
+                    'Content-Type': 'application/json'
+                },
+                method: 'post',
+                body: JSON.stringify({ from: String(range[0]), to: String(range[1]), variants: trace.variants })
+            }
+        );
+
+        if (!response.ok) {
+            throw new Error(`Bulk load of ${trace.source}::${trace.id} failed: ${response.status} ${response.statusText}`);
+        }
+
+        return [ vars, response ];
+    }
+
     public hasLdevMap = (id: Trace['id']): boolean => {
         const split = id.split('::');
         if (!this.sources || split.length < 3) return false;
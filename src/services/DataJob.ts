@@ -1,3 +1,4 @@
+import { proxy } from 'comlink';
 import { dataWorker } from '..';
 
 let availableHandle = 0;
@@ -45,8 +46,8 @@ class DataJob {
         return this;
     }
 
-    public invoke(): Promise<DataJobResult> {
-        return dataWorker.invokeDataJob(this);
+    public invoke(onProgress?: (progress: DataJobProgress) => void): Promise<DataJobResult> {
+        return dataWorker.invokeDataJob(this, onProgress && proxy(onProgress));
     }
 }
 
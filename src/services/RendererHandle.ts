@@ -32,20 +32,7 @@ class RendererHandle {
     }
 
     public async createBundle(range: Graph['xRange'], traces: Pick<Trace, 'handle' | 'style' | 'rev'>[]): Promise<number> {
-        const data = new ArrayBuffer(traces.length * TRACE_LEN);
-        const view = new DataView(data);
-        let cursor = 0;
-
-        for (const trace of traces) {
-            view.setUint32(cursor, trace.handle);
-            view.setUint32(cursor + 4, trace.style.width);
-            view.setUint8(cursor + 8, trace.style.color[0]);
-            view.setUint8(cursor + 9, trace.style.color[1]);
-            view.setUint8(cursor + 10, trace.style.color[2]);
-
-            cursor += TRACE_LEN;
-        }
-
+        const data = serializeTraces(traces);
         const handle = await dataWorker.createBundle(this.handle, range, transfer(data, [data]));
 
         this.bundles.push({ handle, traces: traces.reduce<{ [handle: number]: number }>((prev, t) => { prev[t.handle] = t.rev; return prev; }, {}) });
@@ -58,7 +45,10 @@ class RendererHandle {
     }
 }
 
-const TRACE_LEN = 2 * Uint32Array.BYTES_PER_ELEMENT + 4 * Uint8Array.BYTES_PER_ELEMENT;
+const TRACE_LEN = 2 * Uint32Array.BYTES_PER_ELEMENT + 32 * Uint8Array.BYTES_PER_ELEMENT;
+const DASH_LEN = 4;
+const BAR_LAYOUTS: TraceBars['layout'][] = [ 'overlap', 'grouped', 'stacked' ];
+const LINE_SHAPES: LineShape[] = [ 'linear', 'step-before', 'step-after', 'step-mid' ];
 
 const writeTrace = (trace: Pick<Trace, 'handle' | 'style'>, view: DataView, cursor: number) => {
     view.setUint32(cursor, trace.handle);
@@ -67,12 +57,51 @@ const writeTrace = (trace: Pick<Trace, 'handle' | 'style'>, view: DataView, curs
     view.setUint8( cursor + 9, trace.style.color[1]);
     view.setUint8( cursor + 10, trace.style.color[2]);
     view.setUint8( cursor + 11, trace.style.points ? 1 : 0);
+
+    const dash = trace.style.dash ?? [];
+    for (let i = 0; i < DASH_LEN; ++i) {
+        view.setUint8(cursor + 12 + i, dash[i] ?? 0);
+    }
+
+    const fill = trace.style.fill;
+    if (fill) {
+        view.setUint8(cursor + 16, fill.to === 'baseline' ? 1 : 2);
+        view.setUint8(cursor + 17, fill.alpha);
+
+        if (fill.to === 'baseline') {
+            view.setFloat32(cursor + 20, fill.value);
+        } else {
+            view.setUint32(cursor + 20, fill.handle);
+        }
+    }
+
+    const bars = trace.style.bars;
+    if (bars) {
+        view.setUint8(cursor + 24, BAR_LAYOUTS.indexOf(bars.layout) + 1);
+        view.setUint8(cursor + 25, bars.group);
+        view.setFloat32(cursor + 28, bars.width);
+        view.setFloat32(cursor + 32, bars.baseline);
+    }
+
+    view.setUint8(cursor + 36, LINE_SHAPES.indexOf(trace.style.shape ?? 'linear'));
+};
+
+/** Rows of traces in the bundle stream format. */
+const serializeTraces = (traces: Pick<Trace, 'handle' | 'style'>[]): ArrayBuffer => {
+    const data = new ArrayBuffer(traces.length * TRACE_LEN);
+    const view = new DataView(data);
+
+    traces.forEach((trace, i) => writeTrace(trace, view, i * TRACE_LEN));
+
+    return data;
 };
 
 export class RenderJob {
 
     public content: Partial<Omit<WasmRenderJob, 'free'>> = {};
     public bundles: number[] = [];
+    public stacks: StackGroup[] = [];
+    public highlights: HighlightSet[] = [];
     
     private traces: ArrayBuffer;
     private tracesView: DataView | undefined;
@@ -94,7 +123,24 @@ export class RenderJob {
         delete this.tracesView;
         delete this.blacklistView;
 
-        return dataWorker.invokeRenderJob(this.renderer.raw_handle, this.x_type, this.content, transfer(this.traces, [ this.traces ]), this.bundles, transfer(this.blacklist, [ this.blacklist ]));
+        return dataWorker.invokeRenderJob(this.renderer.raw_handle, this.x_type, this.content, transfer(this.traces, [ this.traces ]), this.bundles, transfer(this.blacklist, [ this.blacklist ]), this.stacks, this.highlights);
+    }
+
+    /**
+     * Renders the job into PNG bytes of `width` by `height` CSS pixels at `scale` device pixels each.
+     * Bundles of the on-screen renderer can't be drawn elsewhere, so `traces` are bundled anew.
+     */
+    public exportPng(traces: Pick<Trace, 'handle' | 'style'>[], width: number, height: number, scale: number) {
+        delete this.tracesView;
+
+        const bundle = serializeTraces(traces);
+
+        return dataWorker.exportPng(this.x_type, this.content, transfer(this.traces, [ this.traces ]), transfer(bundle, [ bundle ]), width, height, scale, this.stacks, this.highlights);
+    }
+
+    /** Graph of a PDF report drawing `traces`, bundles of the on-screen renderer are not available there. */
+    public reportGraph(title: string, traces: Pick<Trace, 'handle' | 'style'>[]) {
+        return { title, x_type: this.x_type, content: this.content, traces: serializeTraces(traces) };
     }
 
     public clear(val: boolean): RenderJob {
@@ -124,6 +170,29 @@ export class RenderJob {
         return this;
     }
 
+    public lineStyle(join: 'miter' | 'round' | 'bevel', cap: 'butt' | 'round' | 'square', miterLimit?: number): RenderJob {
+        this.content.line_join = join;
+        this.content.line_cap = cap;
+
+        if (miterLimit !== undefined) {
+            this.content.miter_limit = miterLimit;
+        }
+
+        return this;
+    }
+
+    /** Stacks the traces in order, they also have to be added to the job or to one of its bundles. */
+    public stack(handles: number[], alpha: number): RenderJob {
+        this.stacks.push({ handles, alpha });
+        return this;
+    }
+
+    /** Marks the trace at the given x values, the trace doesn't have to be drawn by the job. */
+    public highlight(handle: number, xs: number[], color: [number, number, number]): RenderJob {
+        this.highlights.push({ handle, xs, color });
+        return this;
+    }
+
     public addTrace(trace: Trace) {
         writeTrace(trace, this.tracesView!, this.tracesCursor);
         this.tracesCursor += TRACE_LEN;
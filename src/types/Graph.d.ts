@@ -50,6 +50,42 @@ interface TraceStyle {
     color: [number, number, number];
     width: number;
     points: boolean;
+    /** Up to four alternating dash and gap lengths in pixels, solid when empty. */
+    dash?: number[];
+    fill?: TraceFill;
+    bars?: TraceBars;
+    /** How the line gets from one sample to the next, linear when missing. */
+    shape?: LineShape;
+}
+
+type LineShape = 'linear' | 'step-before' | 'step-after' | 'step-mid';
+
+/** Fills the area of a trace to a constant value or to another trace, `alpha` is in 0-255. */
+type TraceFill =
+    | { to: 'baseline', value: number, alpha: number }
+    | { to: 'trace', handle: number, alpha: number };
+
+/**
+ * Draws a trace as bars `width` x units wide, growing from `baseline`.
+ * Traces with the same layout and `group` share their bars side by side or stacked.
+ */
+interface TraceBars {
+    layout: 'overlap' | 'grouped' | 'stacked';
+    group: number;
+    width: number;
+    baseline: number;
+}
+
+interface StackGroup {
+    handles: number[];
+    alpha: number;
+}
+
+/** Points of a trace drawn as larger markers, e.g. the peaks of anomalies. */
+interface HighlightSet {
+    handle: number;
+    xs: number[];
+    color: [number, number, number];
 }
 
 type TraceMetas = {
@@ -5,6 +5,12 @@ type DataJobState =
     | 'error'
 ;
 
+type DataJobProgress = {
+    rows: number,
+    received: number,
+    flushed: boolean,
+};
+
 type DataJobResult = {
     loadedTraces?: { [key: string]: { handle: number, suggestedTitle?: string } };
 };
\ No newline at end of file
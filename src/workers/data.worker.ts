@@ -58,12 +58,12 @@ export class DataWorkerProxy {
         renderer.rebundle(bundle, new Uint8Array(toDel), new Uint8Array(toAdd), new Uint8Array(toMod));
     }
 
-    public invokeRenderJob(handle: number, x_type: string, content: RenderJob['content'], traces: ArrayBuffer, bundles: number[], blacklist: ArrayBuffer): RenderJobResult {
+    public invokeRenderJob(handle: number, x_type: string, content: RenderJob['content'], traces: ArrayBuffer, bundles: number[], blacklist: ArrayBuffer, stacks: StackGroup[], highlights: HighlightSet[] = []): RenderJobResult {
         const renderer = this.renderers[handle];
 
         if (!renderer) throw new Error('Renderer with given handle does not exist.');
 
-        const wmjob = new plotting.RenderJob(x_type, traces.byteLength / 11, 0);
+        const wmjob = new plotting.RenderJob(x_type, traces.byteLength / 40, 0);
         
         wmjob.clear = true;
         Object.assign(wmjob, content);
@@ -76,10 +76,54 @@ export class DataWorkerProxy {
 
         wmjob.deserialize_blacklist(new Uint8Array(blacklist));
 
+        for (const stack of stacks) {
+            wmjob.add_stack_group(new Uint32Array(stack.handles), stack.alpha);
+        }
+
+        for (const highlight of highlights) {
+            wmjob.add_highlights(highlight.handle, new Float64Array(highlight.xs), new Uint8Array(highlight.color));
+        }
+
         return renderer.render(wmjob);
     }
 
-    public async invokeDataJob(job: DataJob): Promise<DataJobResult> {
+    public exportPng(x_type: string, content: RenderJob['content'], traces: ArrayBuffer, bundle: ArrayBuffer, width: number, height: number, scale: number, stacks: StackGroup[] = [], highlights: HighlightSet[] = []): Uint8Array {
+        const wmjob = new plotting.RenderJob(x_type, traces.byteLength / 40, 0);
+
+        wmjob.clear = true;
+        Object.assign(wmjob, content);
+
+        wmjob.deserialize_traces(new Uint8Array(traces));
+
+        for (const stack of stacks) {
+            wmjob.add_stack_group(new Uint32Array(stack.handles), stack.alpha);
+        }
+
+        for (const highlight of highlights) {
+            wmjob.add_highlights(highlight.handle, new Float64Array(highlight.xs), new Uint8Array(highlight.color));
+        }
+
+        return plotting.export_png(wmjob, new Uint8Array(bundle), width, height, scale);
+    }
+
+    public exportPdf(pageSize: [number, number], graphHeight: number, graphs: { title: string, x_type: string, content: RenderJob['content'], traces: ArrayBuffer }[]): Uint8Array {
+        const report = new plotting.PdfReport(pageSize[0], pageSize[1], graphHeight);
+
+        for (const graph of graphs) {
+            const wmjob = new plotting.RenderJob(graph.x_type, graph.traces.byteLength / 40, 0);
+
+            wmjob.clear = true;
+            Object.assign(wmjob, graph.content);
+
+            wmjob.deserialize_traces(new Uint8Array(graph.traces));
+
+            report.add_graph(graph.title, wmjob);
+        }
+
+        return report.finish();
+    }
+
+    public async invokeDataJob(job: DataJob, onProgress?: (progress: DataJobProgress) => void): Promise<DataJobResult> {
         const sources = await DataService.getSources();
 
         const result: DataJobResult['loadedTraces'] = {};
@@ -108,12 +152,35 @@ export class DataWorkerProxy {
                 }
             }
 
-            const data = await DataService.getBulkData(bulk, job.range);
-            if (data[1].byteLength > 0) {
-                plotting.bulkload_segments(new Uint32Array(ids.map(i => this.traces[i])), trace.xType, trace.yType, new Uint8Array(data[1]));
-            } else {
-                Logger.warn(`No data received via bulkload for job ${job.handle}!`);
-                throw new Error('Bulk load resulted in an empty dataset.');
+            const handles = new Uint32Array(ids.map(i => this.traces[i]));
+            const missing: [number, number][] = plotting.missing_ranges(handles, job.range[0], job.range[1]);
+
+            for (const range of missing) {
+                const [ , response ] = await DataService.getBulkStream(bulk, range);
+                const length = Number(response.headers.get('Content-Length')) || undefined;
+                const loader = new plotting.BulkLoader(handles, trace.xType, trace.yType, length, undefined, range[0], range[1]);
+                const reader = response.body?.getReader();
+
+                try {
+                    for (let chunk = await reader?.read(); chunk && !chunk.done; chunk = await reader?.read()) {
+                        const flushed = loader.push(chunk.value);
+                        onProgress?.({ rows: loader.rows, received: loader.received, flushed });
+                    }
+
+                    // An empty sub-range is still marked as loaded, so it isn't fetched again
+                    if (loader.rows === 0) {
+                        Logger.warn(`No data received via bulkload for job ${job.handle} in range ${range[0]} - ${range[1]}.`);
+                    }
+
+                    const report = loader.finish();
+                    onProgress?.({ rows: report.rows, received: loader.received, flushed: true });
+
+                    if (report.out_of_order || report.duplicates || report.nan || report.infinite || report.invalid_timestamps || report.late || report.trailing_bytes) {
+                        Logger.warn(`Bulkload for job ${job.handle} needed normalization: ${JSON.stringify(report)}`);
+                    }
+                } finally {
+                    loader.free();
+                }
             }
         }
 